@@ -0,0 +1,185 @@
+use crate::analysis::{preview_rgba, row_correlation, rows_for_length};
+use crate::color::Color;
+use crate::image::canvas::{Canvas, GLYPH_HEIGHT};
+use crate::{Error, ImageType};
+use std::io::Write;
+use strum::IntoEnumIterator;
+
+/// Widths tried by [`guess_textures`] when no explicit list is given.
+pub const DEFAULT_GUESS_WIDTHS: [u32; 7] = [8, 16, 32, 64, 128, 256, 512];
+
+/// Largest area of a candidate shown in a contact sheet cell, in pixels.
+const CELL_SIZE: u32 = 128;
+const CELL_PADDING: u32 = 4;
+const LABEL_HEIGHT: u32 = GLYPH_HEIGHT + 3;
+
+/// One interpretation of a raw byte range as a texture of a given format and width.
+#[derive(Debug, Clone)]
+pub struct GuessCandidate {
+    pub format: ImageType,
+    pub width: u32,
+    pub height: u32,
+    /// How much each row resembles the next one, from 0 to 1.
+    pub row_correlation: f32,
+    /// How plausible the decoded alpha or palette indices are for this format, from 0 to 1.
+    pub palette_plausibility: f32,
+    /// Combined ranking score, from 0 to 1.
+    pub score: f32,
+    /// The decoded preview in RGBA8. Color-indexed formats are shown as a grayscale ramp.
+    pub rgba: Vec<u8>,
+}
+
+/// Decodes `data` with every [`ImageType`] at each of the given widths and scores the results.
+///
+/// Candidates which would not fit at least two rows into `data` are skipped. The returned list
+/// is sorted from most to least likely.
+pub fn guess_textures(data: &[u8], widths: &[u32]) -> Result<Vec<GuessCandidate>, Error> {
+    let mut candidates = Vec::new();
+
+    for format in ImageType::iter() {
+        for &width in widths {
+            let Some(height) = rows_for_length(data.len(), format, width) else {
+                continue;
+            };
+            if height < 2 {
+                continue;
+            }
+
+            let rgba = preview_rgba(data, format, width, height)?;
+            let row_correlation = row_correlation(&rgba, width, height);
+            let palette_plausibility = palette_plausibility(format, &rgba);
+            let score = 0.75 * row_correlation + 0.25 * palette_plausibility;
+
+            candidates.push(GuessCandidate {
+                format,
+                width,
+                height,
+                row_correlation,
+                palette_plausibility,
+                score,
+                rgba,
+            });
+        }
+    }
+
+    candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+    Ok(candidates)
+}
+
+/// Writes a contact sheet PNG laying out every candidate in a grid, one row per format and one
+/// column per width, each labeled with its format, dimensions and score.
+///
+/// Candidates larger than the grid cells are cropped to their top-left corner.
+pub fn write_contact_sheet<W: Write>(
+    writer: &mut W,
+    candidates: &[GuessCandidate],
+) -> Result<(), Error> {
+    let mut widths: Vec<u32> = candidates.iter().map(|c| c.width).collect();
+    widths.sort_unstable();
+    widths.dedup();
+
+    let formats: Vec<ImageType> = ImageType::iter()
+        .filter(|f| candidates.iter().any(|c| c.format == *f))
+        .collect();
+
+    let cell_width = CELL_SIZE + CELL_PADDING;
+    let cell_height = CELL_SIZE + LABEL_HEIGHT + CELL_PADDING;
+    let mut canvas = Canvas::new(
+        (widths.len() as u32 * cell_width + CELL_PADDING).max(1),
+        (formats.len() as u32 * cell_height + CELL_PADDING).max(1),
+        Color::RGB(0x30, 0x30, 0x30),
+    );
+
+    for candidate in candidates {
+        let column = widths.iter().position(|w| *w == candidate.width).unwrap() as u32;
+        let row = formats.iter().position(|f| *f == candidate.format).unwrap() as u32;
+        let x = CELL_PADDING + column * cell_width;
+        let y = CELL_PADDING + row * cell_height;
+
+        let label = format!(
+            "{} {}X{} {:.2}",
+            format_name(candidate.format),
+            candidate.width,
+            candidate.height,
+            candidate.score
+        );
+        canvas.draw_text(x, y, &label, Color::WHITE);
+
+        let image_y = y + LABEL_HEIGHT;
+        canvas.fill_rect(
+            x,
+            image_y,
+            candidate.width.min(CELL_SIZE),
+            candidate.height.min(CELL_SIZE),
+            Color::BLACK,
+        );
+
+        for row in 0..candidate.height.min(CELL_SIZE) {
+            let start = (row * candidate.width * 4) as usize;
+            let end = start + (candidate.width.min(CELL_SIZE) * 4) as usize;
+            canvas.blit(
+                x,
+                image_y + row,
+                candidate.width.min(CELL_SIZE),
+                1,
+                &candidate.rgba[start..end],
+            );
+        }
+    }
+
+    canvas.write_png(writer)
+}
+
+/// Returns the short upper-case name used in contact sheet labels.
+fn format_name(format: ImageType) -> &'static str {
+    match format {
+        ImageType::I1 => "I1",
+        ImageType::I4 => "I4",
+        ImageType::I8 => "I8",
+        ImageType::Ia4 => "IA4",
+        ImageType::Ia8 => "IA8",
+        ImageType::Ia16 => "IA16",
+        ImageType::Ci4 => "CI4",
+        ImageType::Ci8 => "CI8",
+        ImageType::Rgba16 => "RGBA16",
+        ImageType::Rgba32 => "RGBA32",
+    }
+}
+
+/// Scores how believable the decoded data is for the given format.
+///
+/// Color-indexed data is expected to use a dense range of indices starting at zero, while
+/// formats with an alpha channel are expected to be mostly fully opaque or fully transparent.
+/// Formats without any such signal get a neutral score of 0.5.
+fn palette_plausibility(format: ImageType, rgba: &[u8]) -> f32 {
+    let pixel_count = (rgba.len() / 4).max(1) as f32;
+
+    match format {
+        ImageType::Ci4 | ImageType::Ci8 => {
+            let shift = if format == ImageType::Ci4 { 4 } else { 0 };
+            let mut used = [false; 256];
+            for pixel in rgba.chunks_exact(4) {
+                used[(pixel[0] >> shift) as usize] = true;
+            }
+
+            let max_index = used.iter().rposition(|u| *u).unwrap_or(0);
+            let distinct = used.iter().filter(|u| **u).count();
+            distinct as f32 / (max_index + 1) as f32
+        }
+        ImageType::Rgba16 => {
+            let plausible = rgba
+                .chunks_exact(4)
+                .filter(|p| p[3] == 0xFF || p == &[0, 0, 0, 0])
+                .count();
+            plausible as f32 / pixel_count
+        }
+        ImageType::Rgba32 | ImageType::Ia8 | ImageType::Ia16 => {
+            let plausible = rgba
+                .chunks_exact(4)
+                .filter(|p| p[3] == 0 || p[3] >= 0xF0)
+                .count();
+            plausible as f32 / pixel_count
+        }
+        _ => 0.5,
+    }
+}
@@ -0,0 +1,86 @@
+//! Heuristics for making sense of raw texture data whose format or layout is unknown.
+
+pub mod guess;
+pub mod quality;
+pub mod select;
+pub mod tlut_search;
+pub mod width;
+
+use crate::{Error, ImageType, NativeImage};
+
+/// Decodes raw texel data into RGBA8 for previewing purposes.
+///
+/// Color-indexed formats have no palette at this point, so their indices are shown as a
+/// grayscale ramp by decoding them with the intensity format of the same size.
+pub(crate) fn preview_rgba(
+    data: &[u8],
+    format: ImageType,
+    width: u32,
+    height: u32,
+) -> Result<Vec<u8>, Error> {
+    let format = match format {
+        ImageType::Ci4 => ImageType::I4,
+        ImageType::Ci8 => ImageType::I8,
+        format => format,
+    };
+
+    let image = NativeImage::read(data, format, width, height)?;
+    let mut rgba = Vec::with_capacity((width * height * 4) as usize);
+    image.decode(&mut rgba, None)?;
+
+    Ok(rgba)
+}
+
+/// Returns the number of whole rows of `width` texels of `format` contained in `len` bytes,
+/// or `None` if such rows would not be byte aligned.
+pub(crate) fn rows_for_length(len: usize, format: ImageType, width: u32) -> Option<u32> {
+    let bits_per_row = width as usize * format.get_size().get_bpp() as usize;
+    if width == 0 || bits_per_row % 8 != 0 {
+        return None;
+    }
+
+    Some((len / (bits_per_row / 8)) as u32)
+}
+
+/// Measures how similar vertically adjacent pixels are, compared to pixels picked far apart.
+///
+/// Returns a value between 0 (no better than random pairs) and 1 (every row equals the next).
+/// Images that are a single flat color carry no information and score 0.
+pub(crate) fn row_correlation(rgba: &[u8], width: u32, height: u32) -> f32 {
+    if height < 2 {
+        return 0.0;
+    }
+
+    let luma = luma(rgba);
+
+    let width = width as usize;
+    let count = luma.len();
+    let half = count / 2;
+
+    let vertical: f32 = luma
+        .iter()
+        .zip(&luma[width..])
+        .map(|(a, b)| (a - b).abs())
+        .sum::<f32>()
+        / (count - width) as f32;
+
+    let baseline: f32 = (0..count)
+        .map(|i| (luma[i] - luma[(i + half) % count]).abs())
+        .sum::<f32>()
+        / count as f32;
+
+    if baseline == 0.0 {
+        return 0.0;
+    }
+
+    (1.0 - vertical / baseline).clamp(0.0, 1.0)
+}
+
+/// Reduces RGBA8 pixels to a single value per pixel which combines their luminance and alpha.
+pub(crate) fn luma(rgba: &[u8]) -> Vec<f32> {
+    rgba.chunks_exact(4)
+        .map(|p| {
+            (p[0] as f32 * 0.2126 + p[1] as f32 * 0.7152 + p[2] as f32 * 0.0722 + p[3] as f32) / 2.0
+        })
+        .collect()
+}
@@ -0,0 +1,127 @@
+use crate::diff::psnr;
+use crate::gbi::{tmem_capacity, tmem_footprint};
+use crate::image::native_image::parse_tlut;
+use crate::{Error, ImageFormat, ImageType, NativeImage, PNGImage, TextureLUT};
+
+/// How well an image survives conversion to a format, and what it costs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QualityReport {
+    pub format: ImageType,
+    /// Size of the texel data in bytes.
+    pub byte_size: usize,
+    /// Size of the palette in bytes, zero for formats which are not color-indexed.
+    pub tlut_size: usize,
+    /// Whether the texture can be loaded into TMEM at once, next to its palette if it has one.
+    pub fits_tmem: bool,
+    /// Mean squared error over every RGBA channel.
+    pub mse: f64,
+    /// Peak signal-to-noise ratio in decibels, infinite for a lossless conversion.
+    pub psnr: f64,
+    /// Largest error of any RGBA channel of any pixel.
+    pub max_error: u8,
+    /// Largest error of the alpha channel of any pixel.
+    pub max_alpha_error: u8,
+    /// Number of pixels whose alpha changed.
+    pub alpha_errors: usize,
+}
+
+/// Encodes `png` as `format`, decodes it back with [`NativeImage::decode`] and measures the
+/// difference with the original pixels.
+///
+/// The PNG is encoded with [`encode_format`].
+pub fn analyze_format(png: &PNGImage, format: ImageType) -> Result<QualityReport, Error> {
+    let original = png.as_rgba8();
+    let (width, height) = (png.width(), png.height());
+
+    let (data, tlut) = encode_format(png, format)?;
+    let image = NativeImage {
+        format,
+        width,
+        height,
+        data,
+    };
+
+    let mut decoded = Vec::with_capacity(original.len());
+    match &tlut {
+        Some(tlut) => {
+            let entries = format.get_size().get_tlut_size().unwrap_or_default();
+            let colors = parse_tlut(
+                &padded(tlut, entries),
+                format.get_size(),
+                TextureLUT::Rgba16,
+            )?;
+            image.decode(&mut decoded, Some(&colors))?;
+        }
+        None => image.decode(&mut decoded, None)?,
+    }
+
+    let mut squared_error = 0u64;
+    let mut max_error = 0;
+    let mut max_alpha_error = 0;
+    let mut alpha_errors = 0;
+
+    for (a, b) in original.chunks_exact(4).zip(decoded.chunks_exact(4)) {
+        for (a, b) in a.iter().zip(b) {
+            let error = a.abs_diff(*b);
+            squared_error += (error as u64).pow(2);
+            max_error = max_error.max(error);
+        }
+
+        let alpha_error = a[3].abs_diff(b[3]);
+        max_alpha_error = max_alpha_error.max(alpha_error);
+        alpha_errors += (alpha_error != 0) as usize;
+    }
+
+    let mse = squared_error as f64 / original.len().max(1) as f64;
+    let tlut_size = tlut.as_ref().map_or(0, Vec::len);
+
+    Ok(QualityReport {
+        format,
+        byte_size: image.data.len(),
+        tlut_size,
+        fits_tmem: tmem_footprint(format, width, height) <= tmem_capacity(format),
+        mse,
+        psnr: psnr(mse),
+        max_error,
+        max_alpha_error,
+        alpha_errors,
+    })
+}
+
+/// Encodes `png` as `format`, returning the texel data along with the RGBA16 palette of
+/// color-indexed formats.
+///
+/// Unlike [`PNGImage::as_native`], any PNG can be encoded to any format: color-indexed formats
+/// use the palette of the PNG if it is small enough and otherwise quantize it with
+/// [`PNGImage::quantize`], other formats are encoded from the RGBA8 pixels of the PNG.
+pub fn encode_format(
+    png: &PNGImage,
+    format: ImageType,
+) -> Result<(Vec<u8>, Option<Vec<u8>>), Error> {
+    let mut data = Vec::new();
+
+    if format.get_format() == ImageFormat::Ci {
+        let entries = format.get_size().get_tlut_size().unwrap_or_default();
+        let indexed = png.quantize(entries);
+        indexed.as_native(&mut data, format)?;
+
+        let tlut = indexed
+            .palette()
+            .ok_or(Error::MissingPngPalette)?
+            .iter()
+            .flat_map(|color| color.rgba16())
+            .collect();
+        Ok((data, Some(tlut)))
+    } else {
+        PNGImage::from_rgba8(png.width(), png.height(), png.as_rgba8())
+            .as_native(&mut data, format)?;
+        Ok((data, None))
+    }
+}
+
+/// Pads a palette with zeroes to the given number of entries, so it can be parsed as a full TLUT.
+fn padded(tlut: &[u8], entries: usize) -> Vec<u8> {
+    let mut tlut = tlut.to_vec();
+    tlut.resize(tlut.len().max(entries * 2), 0);
+    tlut
+}
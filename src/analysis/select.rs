@@ -0,0 +1,114 @@
+use crate::analysis::quality::{QualityReport, analyze_format};
+use crate::color::Color;
+use crate::{Error, ImageFormat, ImageType, PNGImage};
+use std::collections::HashSet;
+use strum::IntoEnumIterator;
+
+/// Properties of the pixels of an image that bound which formats can store it losslessly.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PixelStats {
+    /// Number of distinct RGBA8 colors.
+    pub distinct_colors: usize,
+    /// Number of distinct colors once reduced to RGBA5551.
+    pub distinct_rgba5551: usize,
+    /// Whether every pixel has equal red, green and blue channels.
+    pub grayscale: bool,
+    /// Number of distinct alpha values.
+    pub alpha_levels: usize,
+    /// Whether every pixel survives a round trip through RGBA5551 unchanged.
+    pub rgba5551_exact: bool,
+}
+
+/// The format chosen for an image, along with how it fares in it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FormatChoice {
+    pub format: ImageType,
+    pub report: QualityReport,
+    pub stats: PixelStats,
+}
+
+/// Gathers the [`PixelStats`] of an image.
+pub fn pixel_stats(png: &PNGImage) -> PixelStats {
+    let rgba = png.as_rgba8();
+
+    let mut colors = HashSet::new();
+    let mut rgba5551 = HashSet::new();
+    let mut alphas = HashSet::new();
+    let mut grayscale = true;
+    let mut rgba5551_exact = true;
+
+    for p in rgba.chunks_exact(4) {
+        let color = Color::RGBA(p[0], p[1], p[2], p[3]);
+        let pixel = color.to_u16();
+
+        colors.insert([p[0], p[1], p[2], p[3]]);
+        rgba5551.insert(pixel);
+        alphas.insert(p[3]);
+        grayscale &= p[0] == p[1] && p[1] == p[2];
+        rgba5551_exact &= Color::from_u16(pixel) == color;
+    }
+
+    PixelStats {
+        distinct_colors: colors.len(),
+        distinct_rgba5551: rgba5551.len(),
+        grayscale,
+        alpha_levels: alphas.len(),
+        rgba5551_exact,
+    }
+}
+
+/// Picks the format storing `png` in the fewest bytes, palette included, whose mean squared
+/// error stays within `error_budget`.
+///
+/// A budget of zero only accepts lossless formats. Formats that the pixel statistics already
+/// rule out are skipped, and ties go to formats without a palette. RGBA32 is used if no other
+/// format fits the budget.
+pub fn select_format(png: &PNGImage, error_budget: f64) -> Result<FormatChoice, Error> {
+    let stats = pixel_stats(png);
+    let lossless = error_budget <= 0.0;
+
+    let mut candidates = Vec::new();
+    for format in ImageType::iter() {
+        if lossless && !could_be_lossless(format, &stats) {
+            continue;
+        }
+
+        // Formats the image cannot be encoded to, e.g. due to its width, are not candidates
+        let Ok(report) = analyze_format(png, format) else {
+            continue;
+        };
+        if report.mse <= error_budget.max(0.0) {
+            candidates.push(report);
+        }
+    }
+
+    let report = match candidates.into_iter().min_by_key(|report| {
+        (
+            report.byte_size + report.tlut_size,
+            report.format.get_format() == ImageFormat::Ci,
+        )
+    }) {
+        Some(report) => report,
+        None => analyze_format(png, ImageType::Rgba32)?,
+    };
+
+    Ok(FormatChoice {
+        format: report.format,
+        report,
+        stats,
+    })
+}
+
+/// Returns whether an image with the given statistics could be stored in `format` losslessly.
+fn could_be_lossless(format: ImageType, stats: &PixelStats) -> bool {
+    let opaque = stats.alpha_levels == 1;
+
+    match format {
+        ImageType::I1 | ImageType::I4 | ImageType::I8 => stats.grayscale && opaque,
+        ImageType::Ia4 | ImageType::Ia8 | ImageType::Ia16 => stats.grayscale,
+        ImageType::Ci4 => stats.rgba5551_exact && stats.distinct_rgba5551 <= 16,
+        ImageType::Ci8 => stats.rgba5551_exact && stats.distinct_rgba5551 <= 256,
+        ImageType::Rgba16 => stats.rgba5551_exact,
+        ImageType::Rgba32 => true,
+    }
+}
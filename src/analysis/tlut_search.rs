@@ -0,0 +1,155 @@
+use crate::analysis::row_correlation;
+use crate::image::native_image::tlut_entry_to_rgba;
+use crate::{Error, ImageFormat, NativeImage, TextureLUT};
+use byteorder::{BigEndian, ByteOrder};
+
+/// Default alignment, in bytes, of the offsets tried by [`find_tluts`].
+///
+/// TLUTs are loaded with `G_LOADTLUT` from 64-bit aligned addresses.
+pub const DEFAULT_TLUT_ALIGNMENT: usize = 8;
+
+/// A block of data which may be the palette of a color-indexed image.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TlutCandidate {
+    /// Offset of the block within the searched data.
+    pub offset: usize,
+    /// The TLUT mode the block was interpreted with.
+    pub mode: TextureLUT,
+    /// Number of entries in the block.
+    pub entries: usize,
+    /// How much the entries themselves look like a palette, from 0 to 1.
+    pub plausibility: f32,
+    /// How coherent the image looks when rendered with this palette, from 0 to 1.
+    pub image_score: f32,
+    /// Combined ranking score, from 0 to 1.
+    pub score: f32,
+}
+
+/// Scans `data` for blocks that look like a TLUT for the given color-indexed image.
+///
+/// Every `alignment`-byte offset is tried as both an RGBA16 and an IA16 palette with as many
+/// entries as the image format can index. Each block is judged on its own (consistent alpha,
+/// similar neighboring colors) and by rendering the image with it through
+/// [`NativeImage::as_png`] and measuring how coherent the result looks. Blocks that are a single
+/// repeated value are skipped. The returned list is sorted from most to least likely.
+pub fn find_tluts(
+    data: &[u8],
+    image: &NativeImage,
+    alignment: usize,
+) -> Result<Vec<TlutCandidate>, Error> {
+    if image.format.get_format() != ImageFormat::Ci {
+        return Err(Error::NotColorIndexed(image.format));
+    }
+
+    let size = image.format.get_size();
+    let entries = size
+        .get_tlut_size()
+        .ok_or(Error::InvalidSizeForTlut(size))?;
+    let block_size = entries * 2;
+
+    let mut candidates = Vec::new();
+    let mut offset = 0;
+
+    while offset + block_size <= data.len() {
+        let block: Vec<u16> = data[offset..offset + block_size]
+            .chunks_exact(2)
+            .map(BigEndian::read_u16)
+            .collect();
+
+        if block.iter().any(|entry| *entry != block[0]) {
+            for mode in [TextureLUT::Rgba16, TextureLUT::Ia16] {
+                let plausibility = tlut_plausibility(&block, mode);
+
+                let color_table: Vec<u8> = block
+                    .iter()
+                    .flat_map(|entry| tlut_entry_to_rgba(*entry, mode))
+                    .collect();
+                let rgba = render_with_tlut(image, &color_table)?;
+                let image_score = row_correlation(&rgba, image.width, image.height);
+
+                candidates.push(TlutCandidate {
+                    offset,
+                    mode,
+                    entries,
+                    plausibility,
+                    image_score,
+                    score: 0.5 * plausibility + 0.5 * image_score,
+                });
+            }
+        }
+
+        offset += alignment.max(2);
+    }
+
+    candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+    Ok(candidates)
+}
+
+/// Scores how much a block of entries looks like a palette in the given mode.
+///
+/// Real palettes are expected to be mostly opaque, with the odd fully transparent entry, and to
+/// change gradually between neighboring entries.
+fn tlut_plausibility(block: &[u16], mode: TextureLUT) -> f32 {
+    let count = block.len() as f32;
+
+    let (alpha_score, continuity) = match mode {
+        TextureLUT::Ia16 => {
+            let consistent = block
+                .iter()
+                .filter(|entry| matches!(*entry & 0xFF, 0x00 | 0xFF))
+                .count();
+
+            let distance: f32 = block
+                .windows(2)
+                .map(|pair| ((pair[0] >> 8) as f32 - (pair[1] >> 8) as f32).abs())
+                .sum::<f32>()
+                / (count - 1.0);
+
+            (consistent as f32 / count, 1.0 - distance / 85.0)
+        }
+        _ => {
+            let consistent = block
+                .iter()
+                .filter(|entry| *entry & 1 == 1 || **entry == 0)
+                .count();
+
+            let channels = |entry: u16| {
+                [
+                    (entry >> 11) & 0x1F,
+                    (entry >> 6) & 0x1F,
+                    (entry >> 1) & 0x1F,
+                ]
+            };
+            let distance: f32 = block
+                .windows(2)
+                .map(|pair| {
+                    channels(pair[0])
+                        .iter()
+                        .zip(channels(pair[1]))
+                        .map(|(a, b)| (*a as f32 - b as f32).abs())
+                        .sum::<f32>()
+                })
+                .sum::<f32>()
+                / (count - 1.0);
+
+            (consistent as f32 / count, 1.0 - distance / 31.0)
+        }
+    };
+
+    0.5 * alpha_score + 0.5 * continuity.clamp(0.0, 1.0)
+}
+
+/// Renders a color-indexed image with the given RGBA8 color table through its PNG encoding.
+fn render_with_tlut(image: &NativeImage, color_table: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut png_data = Vec::new();
+    image.as_png(&mut png_data, Some(color_table))?;
+
+    let mut decoder = png::Decoder::new(png_data.as_slice());
+    decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::ALPHA);
+    let mut reader = decoder.read_info()?;
+    let mut rgba = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut rgba)?;
+    rgba.truncate(info.buffer_size());
+
+    Ok(rgba)
+}
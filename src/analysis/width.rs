@@ -0,0 +1,88 @@
+use crate::analysis::{luma, preview_rgba};
+use crate::{Error, ImageType};
+
+/// Narrowest width considered by [`infer_dimensions`].
+pub const MIN_INFERRED_WIDTH: u32 = 4;
+/// Widest width considered by [`infer_dimensions`], matching the largest tile the RDP can address.
+pub const MAX_INFERRED_WIDTH: u32 = 1024;
+
+/// A possible set of dimensions for raw texture data.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DimensionCandidate {
+    pub width: u32,
+    pub height: u32,
+    /// Autocorrelation between each texel and the one directly below it.
+    pub row_correlation: f32,
+    /// Ranking score. Positive values mean rows line up better than fractions of rows would.
+    pub score: f32,
+}
+
+/// Infers likely dimensions for raw texel data of a known format.
+///
+/// Every width that evenly divides the texel count of `data` into at least two byte-aligned rows
+/// is considered. A candidate width is scored by how much more the texels correlate with the texel
+/// one row below than with the texel a half, third or quarter of a row further. This favors the
+/// true width over both its fractions, whose rows land mid-row, and its multiples, whose own
+/// fractions land on real rows. The returned list is sorted from most to least likely.
+pub fn infer_dimensions(data: &[u8], format: ImageType) -> Result<Vec<DimensionCandidate>, Error> {
+    let bpp = format.get_size().get_bpp() as usize;
+    let texel_count = data.len() * 8 / bpp;
+    if texel_count == 0 {
+        return Ok(Vec::new());
+    }
+
+    let rgba = preview_rgba(data, format, texel_count as u32, 1)?;
+    let values = luma(&rgba);
+
+    let mut candidates: Vec<DimensionCandidate> = (MIN_INFERRED_WIDTH..=MAX_INFERRED_WIDTH)
+        .filter(|&width| {
+            let width = width as usize;
+            texel_count % width == 0 && texel_count / width >= 2 && (width * bpp) % 8 == 0
+        })
+        .map(|width| {
+            let row_correlation = autocorrelation(&values, width as usize);
+            let fraction_correlation = (2..=4)
+                .map(|divisor| autocorrelation(&values, width as usize / divisor))
+                .fold(f32::MIN, f32::max);
+
+            DimensionCandidate {
+                width,
+                height: (texel_count / width as usize) as u32,
+                row_correlation,
+                score: row_correlation - fraction_correlation,
+            }
+        })
+        .collect();
+
+    candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+    Ok(candidates)
+}
+
+/// Computes the Pearson correlation between `values` and itself shifted by `lag`.
+fn autocorrelation(values: &[f32], lag: usize) -> f32 {
+    if lag == 0 || lag >= values.len() {
+        return 0.0;
+    }
+
+    let a = &values[..values.len() - lag];
+    let b = &values[lag..];
+    let n = a.len() as f32;
+
+    let mean_a = a.iter().sum::<f32>() / n;
+    let mean_b = b.iter().sum::<f32>() / n;
+
+    let (mut covariance, mut variance_a, mut variance_b) = (0.0, 0.0, 0.0);
+    for (x, y) in a.iter().zip(b) {
+        let dx = x - mean_a;
+        let dy = y - mean_b;
+        covariance += dx * dy;
+        variance_a += dx * dx;
+        variance_b += dy * dy;
+    }
+
+    if variance_a == 0.0 || variance_b == 0.0 {
+        return 0.0;
+    }
+
+    covariance / (variance_a * variance_b).sqrt()
+}
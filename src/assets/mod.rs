@@ -0,0 +1,20 @@
+//! Readers for the asset descriptions used by decompilation projects.
+
+pub mod splat;
+pub mod zapd;
+
+use crate::Error;
+
+/// Parses a decimal or `0x`-prefixed hexadecimal number from an asset description.
+pub(crate) fn parse_number(value: &str) -> Result<u32, Error> {
+    let value = value.trim();
+    let result = match value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+    {
+        Some(hex) => u32::from_str_radix(hex, 16),
+        None => value.parse(),
+    };
+
+    result.map_err(|_| Error::InvalidAssetDescription(format!("invalid number '{value}'")))
+}
@@ -0,0 +1,298 @@
+//! Support for the YAML configurations of the splat binary splitting tool.
+//!
+//! Only image and `palette` segments are handled, every other segment type is only used to find
+//! where the previous segment ends.
+
+use crate::assets::parse_number;
+use crate::image::native_image::{parse_tlut, parse_tlut_entries};
+use crate::{
+    Error, ImageFormat, ImageType, NativeImage, PNGImage, TextureLUT, create_palette_from_png,
+};
+use yaml_rust2::{Yaml, YamlLoader};
+
+/// An image segment, such as `[0x1000, ci4, tex/foo, 32, 32]`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SplatImage {
+    pub name: String,
+    pub format: ImageType,
+    pub offset: usize,
+    pub width: u32,
+    pub height: u32,
+    /// Name of the palette segment of a color-indexed image. Defaults to the image's own name.
+    pub palette: Option<String>,
+}
+
+/// A `palette` segment.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SplatPalette {
+    pub name: String,
+    pub offset: usize,
+    /// Size of the segment in bytes, if another segment follows it.
+    pub size: Option<usize>,
+}
+
+/// The image and palette segments of a splat configuration.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SplatConfig {
+    pub images: Vec<SplatImage>,
+    pub palettes: Vec<SplatPalette>,
+}
+
+/// A segment in either list or dictionary form, flattened out of its parent.
+struct Entry {
+    start: usize,
+    kind: Option<String>,
+    name: Option<String>,
+    width: Option<u32>,
+    height: Option<u32>,
+    palette: Option<String>,
+}
+
+impl SplatImage {
+    /// Returns the name of the PNG file for this image, e.g. `tex/foo.png`.
+    pub fn file_name(&self) -> String {
+        format!("{}.png", self.name)
+    }
+
+    /// Returns the size of the image data in bytes.
+    pub fn byte_size(&self) -> usize {
+        (self.width * self.height * self.format.get_size().get_bpp()).div_ceil(8) as usize
+    }
+}
+
+/// Parses the image and palette segments out of a splat YAML configuration.
+///
+/// Segments may be nested as `subsegments` and written in either list form
+/// (`[start, type, name, width, height]`) or dictionary form.
+pub fn parse_splat_yaml(yaml: &str) -> Result<SplatConfig, Error> {
+    let documents = YamlLoader::load_from_str(yaml)
+        .map_err(|e| Error::InvalidAssetDescription(e.to_string()))?;
+    let root = documents
+        .first()
+        .ok_or_else(|| Error::InvalidAssetDescription("empty splat configuration".to_string()))?;
+
+    let mut entries = Vec::new();
+    if let Some(segments) = root["segments"].as_vec() {
+        flatten_segments(segments, &mut entries)?;
+    }
+    entries.sort_by_key(|entry| entry.start);
+
+    let mut config = SplatConfig::default();
+
+    for (i, entry) in entries.iter().enumerate() {
+        let Some(kind) = entry.kind.as_deref() else {
+            continue;
+        };
+        let name = entry
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("{:X}", entry.start));
+
+        if kind == "palette" {
+            let next_start = entries[i + 1..]
+                .iter()
+                .map(|next| next.start)
+                .find(|start| *start > entry.start);
+
+            config.palettes.push(SplatPalette {
+                name,
+                offset: entry.start,
+                size: next_start.map(|start| start - entry.start),
+            });
+        } else if let Some(format) = ImageType::from_name(kind) {
+            let (Some(width), Some(height)) = (entry.width, entry.height) else {
+                return Err(Error::InvalidAssetDescription(format!(
+                    "image segment {name} is missing its width or height"
+                )));
+            };
+
+            config.images.push(SplatImage {
+                name,
+                format,
+                offset: entry.start,
+                width,
+                height,
+                palette: entry.palette.clone(),
+            });
+        }
+    }
+
+    Ok(config)
+}
+
+impl SplatConfig {
+    /// Returns the palette segment used by a color-indexed image.
+    pub fn palette_for(&self, image: &SplatImage) -> Option<&SplatPalette> {
+        let name = image.palette.as_ref().unwrap_or(&image.name);
+        self.palettes.iter().find(|palette| &palette.name == name)
+    }
+
+    /// Extracts every image segment from the ROM, returning each PNG with its file name.
+    ///
+    /// Color-indexed images are written as indexed PNGs embedding their paired palette, which
+    /// holds as many entries as fit in its segment.
+    pub fn extract(&self, rom: &[u8]) -> Result<Vec<(String, Vec<u8>)>, Error> {
+        let mut pngs = Vec::new();
+
+        for image in &self.images {
+            let data = rom
+                .get(image.offset..image.offset + image.byte_size())
+                .ok_or_else(|| out_of_bounds(&image.name))?;
+            let native = NativeImage::read(data, image.format, image.width, image.height)?;
+
+            let palette = if image.format.get_format() == ImageFormat::Ci {
+                let palette = self.palette_for(image).ok_or(Error::MissingTlut)?;
+                let tlut = rom
+                    .get(palette.offset..)
+                    .ok_or_else(|| out_of_bounds(&palette.name))?;
+                let size = image.format.get_size();
+                match palette.size {
+                    Some(bytes) => {
+                        let max = size.get_tlut_size().unwrap_or_default();
+                        Some(parse_tlut_entries(
+                            tlut,
+                            (bytes / 2).min(max),
+                            TextureLUT::Rgba16,
+                        )?)
+                    }
+                    None => Some(parse_tlut(tlut, size, TextureLUT::Rgba16)?),
+                }
+            } else {
+                None
+            };
+
+            let mut png = Vec::new();
+            native.as_png(&mut png, palette.as_deref())?;
+            pngs.push((image.file_name(), png));
+        }
+
+        Ok(pngs)
+    }
+
+    /// Writes every image segment, and the palettes paired with color-indexed images, back into
+    /// the ROM.
+    ///
+    /// `load_png` is called with each image to obtain the bytes of its PNG file. Palette entries
+    /// past the end of their segment are dropped as long as no pixel uses them; if a palette is
+    /// shorter, the rest of the segment is left untouched.
+    pub fn rebuild<F>(&self, rom: &mut Vec<u8>, mut load_png: F) -> Result<(), Error>
+    where
+        F: FnMut(&SplatImage) -> Result<Vec<u8>, Error>,
+    {
+        for image in &self.images {
+            let png_bytes = load_png(image)?;
+            let png = PNGImage::read(png_bytes.as_slice())?;
+
+            if (png.width(), png.height()) != (image.width, image.height) {
+                return Err(Error::InvalidAssetDescription(format!(
+                    "{} is {}x{} but segment {} is declared as {}x{}",
+                    image.file_name(),
+                    png.width(),
+                    png.height(),
+                    image.name,
+                    image.width,
+                    image.height
+                )));
+            }
+
+            let mut data = Vec::new();
+            png.as_native(&mut data, image.format)?;
+            write_at(rom, image.offset, &data);
+
+            if image.format.get_format() == ImageFormat::Ci {
+                let palette = self.palette_for(image).ok_or(Error::MissingTlut)?;
+
+                let mut tlut = Vec::new();
+                create_palette_from_png(png_bytes.as_slice(), &mut tlut)?;
+
+                if let Some(size) = palette.size.filter(|&size| tlut.len() > size) {
+                    let used = png.indices().unwrap_or_default().into_iter().max();
+                    if used.is_some_and(|index| index as usize >= size / 2) {
+                        return Err(Error::InvalidAssetDescription(format!(
+                            "palette of {} does not fit in segment {}",
+                            image.file_name(),
+                            palette.name
+                        )));
+                    }
+                    tlut.truncate(size);
+                }
+                write_at(rom, palette.offset, &tlut);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn flatten_segments(segments: &[Yaml], entries: &mut Vec<Entry>) -> Result<(), Error> {
+    for segment in segments {
+        match segment {
+            Yaml::Array(fields) => {
+                let Some(start) = fields.first().map(yaml_number).transpose()? else {
+                    continue;
+                };
+                entries.push(Entry {
+                    start: start as usize,
+                    kind: fields.get(1).and_then(yaml_string),
+                    name: fields.get(2).and_then(yaml_string),
+                    width: fields.get(3).map(yaml_number).transpose()?,
+                    height: fields.get(4).map(yaml_number).transpose()?,
+                    palette: None,
+                });
+            }
+            Yaml::Hash(_) => {
+                if let Some(start) = optional_number(&segment["start"])? {
+                    entries.push(Entry {
+                        start: start as usize,
+                        kind: yaml_string(&segment["type"]),
+                        name: yaml_string(&segment["name"]),
+                        width: optional_number(&segment["width"])?,
+                        height: optional_number(&segment["height"])?,
+                        palette: yaml_string(&segment["palette"]),
+                    });
+                }
+
+                if let Some(subsegments) = segment["subsegments"].as_vec() {
+                    flatten_segments(subsegments, entries)?;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+fn yaml_number(value: &Yaml) -> Result<u32, Error> {
+    match value {
+        Yaml::Integer(value) => u32::try_from(*value)
+            .map_err(|_| Error::InvalidAssetDescription(format!("invalid number {value}"))),
+        Yaml::String(value) | Yaml::Real(value) => parse_number(value),
+        _ => Err(Error::InvalidAssetDescription(format!(
+            "expected a number, got {value:?}"
+        ))),
+    }
+}
+
+fn optional_number(value: &Yaml) -> Result<Option<u32>, Error> {
+    match value {
+        Yaml::BadValue | Yaml::Null => Ok(None),
+        value => yaml_number(value).map(Some),
+    }
+}
+
+fn yaml_string(value: &Yaml) -> Option<String> {
+    value.as_str().map(str::to_string)
+}
+
+fn write_at(rom: &mut Vec<u8>, offset: usize, data: &[u8]) {
+    let end = offset + data.len();
+    if rom.len() < end {
+        rom.resize(end, 0);
+    }
+    rom[offset..end].copy_from_slice(data);
+}
+
+fn out_of_bounds(name: &str) -> Error {
+    Error::InvalidAssetDescription(format!("segment {name} lies outside of the ROM"))
+}
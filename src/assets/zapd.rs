@@ -0,0 +1,202 @@
+//! Support for the ZAPD XML files describing the assets of Zelda64 decompilation projects.
+//!
+//! Only `<Texture>` resources are handled, every other resource type is ignored.
+
+use crate::assets::parse_number;
+use crate::image::native_image::{parse_tlut, parse_tlut_entries};
+use crate::{Error, ImageFormat, ImageType, NativeImage, PNGImage, TextureLUT};
+
+/// A `<File>` element, describing the resources within one binary file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ZapdFile {
+    pub name: String,
+    pub segment: Option<u8>,
+    pub textures: Vec<ZapdTexture>,
+}
+
+/// A `<Texture>` element.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ZapdTexture {
+    pub name: String,
+    /// Base name of the extracted file. Defaults to the texture name.
+    pub out_name: String,
+    pub format: ImageType,
+    pub width: u32,
+    pub height: u32,
+    pub offset: usize,
+    /// Offset of the palette of a color-indexed texture, which is itself described by another
+    /// texture of the same file.
+    pub tlut_offset: Option<usize>,
+}
+
+impl ZapdTexture {
+    /// Returns the name of the PNG file ZAPD uses for this texture, e.g. `gFooTex.rgba16.png`.
+    pub fn file_name(&self) -> String {
+        format!(
+            "{}.{}.png",
+            self.out_name,
+            format!("{:?}", self.format).to_lowercase()
+        )
+    }
+
+    /// Returns the size of the texture data in bytes.
+    pub fn byte_size(&self) -> usize {
+        (self.width * self.height * self.format.get_size().get_bpp()).div_ceil(8) as usize
+    }
+}
+
+/// Parses the `<File>` elements of a ZAPD XML document along with the textures they contain.
+pub fn parse_zapd_xml(xml: &str) -> Result<Vec<ZapdFile>, Error> {
+    let document = roxmltree::Document::parse(xml)
+        .map_err(|e| Error::InvalidAssetDescription(e.to_string()))?;
+
+    let mut files = Vec::new();
+
+    for file_node in document.descendants().filter(|n| n.has_tag_name("File")) {
+        let name = required_attribute(&file_node, "Name")?.to_string();
+        let segment = file_node
+            .attribute("Segment")
+            .map(|segment| parse_number(segment).map(|segment| segment as u8))
+            .transpose()?;
+
+        let mut textures = Vec::new();
+        for node in file_node
+            .descendants()
+            .filter(|n| n.has_tag_name("Texture"))
+        {
+            let name = required_attribute(&node, "Name")?.to_string();
+            let format_name = required_attribute(&node, "Format")?;
+            let format = ImageType::from_name(&format_name.to_lowercase()).ok_or_else(|| {
+                Error::InvalidAssetDescription(format!(
+                    "texture {name} has unknown format '{format_name}'"
+                ))
+            })?;
+
+            textures.push(ZapdTexture {
+                out_name: node.attribute("OutName").unwrap_or(&name).to_string(),
+                format,
+                width: parse_number(required_attribute(&node, "Width")?)?,
+                height: parse_number(required_attribute(&node, "Height")?)?,
+                offset: parse_number(required_attribute(&node, "Offset")?)? as usize,
+                tlut_offset: node
+                    .attribute("TlutOffset")
+                    .map(|offset| parse_number(offset).map(|offset| offset as usize))
+                    .transpose()?,
+                name,
+            });
+        }
+
+        files.push(ZapdFile {
+            name,
+            segment,
+            textures,
+        });
+    }
+
+    Ok(files)
+}
+
+impl ZapdFile {
+    /// Extracts every texture of the file from its binary, returning each PNG with its file name.
+    ///
+    /// Color-indexed textures are written as indexed PNGs using the palette at their
+    /// `TlutOffset`, interpreted according to the format of the texture declared there. The
+    /// palette holds as many entries as that texture has texels, or a full TLUT if no texture
+    /// is declared at the offset.
+    pub fn extract(&self, binary: &[u8]) -> Result<Vec<(String, Vec<u8>)>, Error> {
+        let mut pngs = Vec::new();
+
+        for texture in &self.textures {
+            let data = binary
+                .get(texture.offset..texture.offset + texture.byte_size())
+                .ok_or_else(|| out_of_bounds(texture))?;
+            let image = NativeImage::read(data, texture.format, texture.width, texture.height)?;
+
+            let palette = if texture.format.get_format() == ImageFormat::Ci {
+                let tlut_offset = texture.tlut_offset.ok_or(Error::MissingTlut)?;
+                let tlut = binary
+                    .get(tlut_offset..)
+                    .ok_or_else(|| out_of_bounds(texture))?;
+                let size = texture.format.get_size();
+                let mode = self.tlut_mode_at(tlut_offset);
+                match self.texture_at(tlut_offset) {
+                    Some(declared) => {
+                        let entries = (declared.width * declared.height) as usize;
+                        let max = size.get_tlut_size().unwrap_or_default();
+                        Some(parse_tlut_entries(tlut, entries.min(max), mode)?)
+                    }
+                    None => Some(parse_tlut(tlut, size, mode)?),
+                }
+            } else {
+                None
+            };
+
+            let mut png = Vec::new();
+            image.as_png(&mut png, palette.as_deref())?;
+            pngs.push((texture.file_name(), png));
+        }
+
+        Ok(pngs)
+    }
+
+    /// Writes every texture of the file back into its binary.
+    ///
+    /// `load_png` is called with each texture to obtain its (possibly edited) image. Palettes are
+    /// rebuilt from the textures declared at their offsets, not from the color-indexed images.
+    pub fn reassemble<F>(&self, binary: &mut Vec<u8>, mut load_png: F) -> Result<(), Error>
+    where
+        F: FnMut(&ZapdTexture) -> Result<PNGImage, Error>,
+    {
+        for texture in &self.textures {
+            let image = load_png(texture)?;
+            if (image.width(), image.height()) != (texture.width, texture.height) {
+                return Err(Error::InvalidAssetDescription(format!(
+                    "{} is {}x{} but texture {} is declared as {}x{}",
+                    texture.file_name(),
+                    image.width(),
+                    image.height(),
+                    texture.name,
+                    texture.width,
+                    texture.height
+                )));
+            }
+
+            let mut data = Vec::new();
+            image.as_native(&mut data, texture.format)?;
+
+            let end = texture.offset + data.len();
+            if binary.len() < end {
+                binary.resize(end, 0);
+            }
+            binary[texture.offset..end].copy_from_slice(&data);
+        }
+
+        Ok(())
+    }
+
+    /// Returns the TLUT mode matching the format of the texture declared at `offset`.
+    fn tlut_mode_at(&self, offset: usize) -> TextureLUT {
+        match self.texture_at(offset) {
+            Some(texture) if texture.format == ImageType::Ia16 => TextureLUT::Ia16,
+            _ => TextureLUT::Rgba16,
+        }
+    }
+
+    /// Returns the texture declared at `offset`.
+    fn texture_at(&self, offset: usize) -> Option<&ZapdTexture> {
+        self.textures.iter().find(|t| t.offset == offset)
+    }
+}
+
+fn required_attribute<'a>(node: &roxmltree::Node<'a, '_>, name: &str) -> Result<&'a str, Error> {
+    node.attribute(name).ok_or_else(|| {
+        Error::InvalidAssetDescription(format!(
+            "<{}> is missing the {name} attribute",
+            node.tag_name().name()
+        ))
+    })
+}
+
+fn out_of_bounds(texture: &ZapdTexture) -> Error {
+    Error::InvalidAssetDescription(format!("texture {} lies outside of its file", texture.name))
+}
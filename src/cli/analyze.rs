@@ -0,0 +1,62 @@
+use crate::cli::defines::BinaryFormat;
+use anyhow::Result;
+use clap::Args;
+use pigment64::analysis::quality::analyze_format;
+use pigment64::{Error, ImageType, PNGImage};
+use std::fs::File;
+use std::io::BufReader;
+use strum::IntoEnumIterator;
+
+// MARK: - Args
+
+#[derive(Args, Debug)]
+pub struct AnalyzeArgs {
+    /// Path to the PNG input file
+    input: String,
+
+    /// Formats to evaluate, separated by commas. Defaults to every format
+    #[arg(value_enum, short, long, value_delimiter = ',')]
+    formats: Vec<BinaryFormat>,
+}
+
+// MARK: - Handlers
+
+pub fn handle_analyze(args: &AnalyzeArgs) -> Result<()> {
+    let input_file = File::open(&args.input)?;
+    let image = PNGImage::read(BufReader::new(input_file))?;
+
+    let formats = if args.formats.is_empty() {
+        ImageType::iter().collect()
+    } else {
+        args.formats
+            .iter()
+            .map(|format| format.as_native().ok_or(Error::PaletteConversionError))
+            .collect::<Result<Vec<_>, _>>()?
+    };
+
+    println!(
+        "{:<8} {:>7} {:>5} {:>5} {:>9} {:>8} {:>4} {:>6} {:>8}",
+        "Format", "Bytes", "TLUT", "TMEM", "MSE", "PSNR", "Max", "Alpha", "Alpha px"
+    );
+
+    for format in formats {
+        let name = format!("{format:?}").to_lowercase();
+        match analyze_format(&image, format) {
+            Ok(report) => println!(
+                "{:<8} {:>7} {:>5} {:>5} {:>9.2} {:>8.2} {:>4} {:>6} {:>8}",
+                name,
+                report.byte_size,
+                report.tlut_size,
+                if report.fits_tmem { "yes" } else { "no" },
+                report.mse,
+                report.psnr,
+                report.max_error,
+                report.max_alpha_error,
+                report.alpha_errors
+            ),
+            Err(e) => println!("{name:<8} {e}"),
+        }
+    }
+
+    Ok(())
+}
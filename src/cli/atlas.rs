@@ -0,0 +1,177 @@
+use crate::cli::binary::write_c_array;
+use crate::cli::defines::BinaryFormat;
+use anyhow::Result;
+use clap::{Args, ValueEnum};
+use pigment64::image::atlas::{AtlasOptions, build_atlas};
+use pigment64::{Error, PNGImage};
+use std::fs::{self, File};
+use std::io::{BufReader, Write};
+use std::path::Path;
+
+// MARK: - Args
+
+#[derive(Args, Debug)]
+pub struct AtlasArgs {
+    /// Paths to the PNG input files
+    #[arg(required = true)]
+    inputs: Vec<String>,
+
+    /// Format of the atlas
+    #[arg(value_enum, short, long)]
+    format: BinaryFormat,
+
+    /// Output file for the atlas
+    #[arg(short, long)]
+    output: String,
+
+    /// Output the atlas as a raw C array which can be `#include`d in a file
+    #[arg(long)]
+    c_array: bool,
+
+    /// Transparent texels left between neighbouring images
+    #[arg(long, default_value_t = 0)]
+    padding: u32,
+
+    /// Number of times the edge texels of each image are repeated around it
+    #[arg(long, default_value_t = 0)]
+    extrude: u32,
+
+    /// Round the dimensions of the atlas up to powers of two
+    #[arg(long)]
+    power_of_two: bool,
+
+    /// Only accept layouts that fit in TMEM
+    #[arg(long)]
+    fit_tmem: bool,
+
+    /// Output file for the palette of color-indexed formats. Defaults to the output file name with
+    /// ".tlut.bin" appended
+    #[arg(long)]
+    palette_output: Option<String>,
+
+    /// Format of the table of image coordinates
+    #[arg(value_enum, long, default_value = "c")]
+    layout: Layout,
+
+    /// Output file for the table of image coordinates. Defaults to printing it
+    #[arg(long)]
+    layout_output: Option<String>,
+}
+
+// MARK: - Handlers
+
+pub fn handle_atlas(args: &AtlasArgs) -> Result<()> {
+    let image_type = args
+        .format
+        .as_native()
+        .ok_or(Error::PaletteConversionError)?;
+
+    let images = args
+        .inputs
+        .iter()
+        .map(|input| Ok(PNGImage::read(BufReader::new(File::open(input)?))?))
+        .collect::<Result<Vec<_>>>()?;
+
+    let options = AtlasOptions {
+        padding: args.padding,
+        extrude: args.extrude,
+        power_of_two: args.power_of_two,
+        fit_tmem: args.fit_tmem,
+    };
+    let atlas = build_atlas(&images, image_type, options)?;
+
+    if args.c_array {
+        let mut output: Box<dyn Write> = Box::new(File::create(&args.output)?);
+        write_c_array(&mut output, &atlas.data, args.format.get_width());
+    } else {
+        fs::write(&args.output, &atlas.data)?;
+    }
+
+    if let Some(tlut) = &atlas.tlut {
+        let palette_output = args
+            .palette_output
+            .clone()
+            .unwrap_or_else(|| format!("{}.tlut.bin", args.output));
+        fs::write(palette_output, tlut)?;
+    }
+
+    let names: Vec<String> = args
+        .inputs
+        .iter()
+        .map(|input| {
+            Path::new(input)
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_else(|| input.clone())
+        })
+        .collect();
+
+    let mut layout = String::new();
+    match args.layout {
+        Layout::C => {
+            layout.push_str(&format!(
+                "// {}x{} atlas, x, y, width, height\n",
+                atlas.width, atlas.height
+            ));
+            for (entry, name) in atlas.entries.iter().zip(&names) {
+                layout.push_str(&format!(
+                    "{{ {}, {}, {}, {} }}, // {}\n",
+                    entry.x, entry.y, entry.width, entry.height, name
+                ));
+            }
+        }
+        Layout::Json => {
+            let entries: Vec<String> = atlas
+                .entries
+                .iter()
+                .zip(&names)
+                .map(|(entry, name)| {
+                    format!(
+                        "    {{ \"name\": \"{}\", \"x\": {}, \"y\": {}, \"width\": {}, \"height\": {} }}",
+                        json_escape(name),
+                        entry.x,
+                        entry.y,
+                        entry.width,
+                        entry.height
+                    )
+                })
+                .collect();
+            layout.push_str(&format!(
+                "{{\n  \"width\": {},\n  \"height\": {},\n  \"images\": [\n{}\n  ]\n}}\n",
+                atlas.width,
+                atlas.height,
+                entries.join(",\n")
+            ));
+        }
+    }
+
+    match &args.layout_output {
+        Some(path) => fs::write(path, layout)?,
+        None => print!("{layout}"),
+    }
+
+    Ok(())
+}
+
+// MARK: - Structs
+
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum, Debug)]
+pub enum Layout {
+    C,
+    Json,
+}
+
+// MARK: - Helpers
+
+fn json_escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -1,8 +1,12 @@
-use crate::cli::defines::BinaryFormat;
+use crate::cli::defines::{BinaryFormat, PaletteFormat, read_palette_file};
 use crate::write_buf_as_raw_array;
-use anyhow::Result;
+use anyhow::{Result, bail};
+use clap::builder::PossibleValue;
 use clap::{Args, ValueEnum};
-use pigment64::Error;
+use pigment64::analysis::quality::encode_format;
+use pigment64::analysis::select::select_format;
+use pigment64::palette::formats::{PaletteFileFormat, pad_palette};
+use pigment64::{Error, ImageType};
 use std::{
     fs::File,
     io::{self, BufReader, BufWriter, Write},
@@ -21,9 +25,37 @@ pub struct BinaryArgs {
     #[arg(short)]
     output: Option<String>,
 
-    /// Output format
-    #[arg(value_enum, short, long)]
-    format: BinaryFormat,
+    /// Output format, or "auto" to pick the smallest format that stores the image losslessly
+    #[arg(short, long, value_enum, ignore_case = true)]
+    format: Format,
+
+    /// Mean squared error allowed when picking the format with "auto". Defaults to 0, so only
+    /// lossless formats are picked
+    #[arg(long)]
+    error_budget: Option<f64>,
+
+    /// Output file for the palette when "auto" picks a color-indexed format. Defaults to input
+    /// file name with ".tlut.bin" appended
+    #[arg(long)]
+    palette_output: Option<String>,
+
+    /// Palette holding several 16-color banks that a CI4 image is encoded against
+    #[arg(short, long, requires = "palette_bank")]
+    palette: Option<String>,
+
+    /// Format of `--palette`, or of the output with `--format palette`. Defaults to guessing it
+    /// from the file extension, and to an RGBA16 TLUT binary otherwise
+    #[arg(value_enum, long)]
+    palette_format: Option<PaletteFormat>,
+
+    /// Bank of `--palette` used by the CI4 image
+    #[arg(long, requires = "palette")]
+    palette_bank: Option<u8>,
+
+    /// Pad the palette written with `--format palette` to this many entries, such as 16 or 256
+    /// for a full TLUT. Defaults to writing only the entries of the PNG palette
+    #[arg(long)]
+    palette_entries: Option<usize>,
 
     /// Flip the image on the x axis
     #[arg(long)]
@@ -54,9 +86,41 @@ pub fn handle_binary(args: &BinaryArgs) -> Result<()> {
 
     // Convert the image
     let mut bin: Vec<u8> = Vec::new();
+    let format;
 
-    if let BinaryFormat::Palette = args.format {
-        pigment64::create_palette_from_png(&mut input_reader, &mut bin)?;
+    if args.format != Format::Auto {
+        if args.error_budget.is_some() {
+            bail!("--error-budget is only supported by the auto format");
+        }
+        if args.palette_output.is_some() {
+            bail!("--palette-output is only supported by the auto format");
+        }
+    }
+
+    if let Format::Fixed(BinaryFormat::Palette) = args.format {
+        format = BinaryFormat::Palette;
+        let palette_format = PaletteFormat::resolve(
+            args.palette_format,
+            args.output.as_deref().unwrap_or_default(),
+        );
+
+        if args.c_array && palette_format != PaletteFileFormat::Rgba16 {
+            bail!("--c-array is only supported by TLUT binaries");
+        }
+
+        match args.palette_entries {
+            None if palette_format == PaletteFileFormat::Rgba16 => {
+                pigment64::create_palette_from_png(&mut input_reader, &mut bin)?;
+            }
+            entries => {
+                let image = pigment64::PNGImage::read(&mut input_reader)?;
+                let mut colors = image.palette().ok_or(Error::MissingPngPalette)?;
+                if let Some(entries) = entries {
+                    colors = pad_palette(&colors, entries)?;
+                }
+                bin = pigment64::palette::formats::write_palette(&colors, palette_format)?;
+            }
+        }
     } else {
         let mut image = pigment64::PNGImage::read(&mut input_reader)?;
 
@@ -64,12 +128,39 @@ pub fn handle_binary(args: &BinaryArgs) -> Result<()> {
             image = image.flip(args.flip_x, args.flip_y);
         }
 
-        let image_type = args
-            .format
-            .as_native()
-            .ok_or(Error::PaletteConversionError)?;
+        let image_type = match args.format {
+            Format::Fixed(format) => {
+                let image_type = format.as_native().ok_or(Error::PaletteConversionError)?;
+                match (&args.palette, args.palette_bank) {
+                    (Some(palette), Some(bank)) => {
+                        if image_type != ImageType::Ci4 {
+                            bail!("--palette-bank is only supported by the ci4 format");
+                        }
+                        let colors = read_palette_file(palette, args.palette_format)?;
+                        image.as_ci4_with_palette_bank(&mut bin, &colors, bank)?;
+                    }
+                    _ => image.as_native(&mut bin, image_type)?,
+                }
+                image_type
+            }
+            Format::Auto => {
+                let choice = select_format(&image, args.error_budget.unwrap_or_default())?;
+                println!(
+                    "Selected format: {} ({} bytes, PSNR {:.2} dB)",
+                    format!("{:?}", choice.format).to_lowercase(),
+                    choice.report.byte_size + choice.report.tlut_size,
+                    choice.report.psnr
+                );
 
-        image.as_native(&mut bin, image_type)?;
+                let (data, tlut) = encode_format(&image, choice.format)?;
+                bin = data;
+                if let Some(tlut) = tlut {
+                    write_palette(args, &tlut)?;
+                }
+                choice.format
+            }
+        };
+        format = BinaryFormat::from_native(image_type);
 
         if args.word_swap {
             let mut native_image = pigment64::NativeImage {
@@ -104,14 +195,8 @@ pub fn handle_binary(args: &BinaryArgs) -> Result<()> {
 
     if args.c_array {
         // Override array width if the user passed the appropriate flag
-        let c_array_width = args.c_array_width.unwrap_or(args.format.get_width());
-
-        match c_array_width {
-            CArrayWidth::U8 => write_buf_as_u8(&mut output_file, &bin),
-            CArrayWidth::U16 => write_buf_as_u16(&mut output_file, &bin),
-            CArrayWidth::U32 => write_buf_as_u32(&mut output_file, &bin),
-            CArrayWidth::U64 => write_buf_as_u64(&mut output_file, &bin),
-        }
+        let c_array_width = args.c_array_width.unwrap_or(format.get_width());
+        write_c_array(&mut output_file, &bin, c_array_width);
     } else {
         BufWriter::new(output_file).write_all(&bin)?;
     }
@@ -121,6 +206,38 @@ pub fn handle_binary(args: &BinaryArgs) -> Result<()> {
 
 // MARK: - Structs
 
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Format {
+    Auto,
+    Fixed(BinaryFormat),
+}
+
+impl ValueEnum for Format {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[
+            Format::Auto,
+            Format::Fixed(BinaryFormat::Ci4),
+            Format::Fixed(BinaryFormat::Ci8),
+            Format::Fixed(BinaryFormat::I1),
+            Format::Fixed(BinaryFormat::I4),
+            Format::Fixed(BinaryFormat::I8),
+            Format::Fixed(BinaryFormat::Ia4),
+            Format::Fixed(BinaryFormat::Ia8),
+            Format::Fixed(BinaryFormat::Ia16),
+            Format::Fixed(BinaryFormat::Rgba16),
+            Format::Fixed(BinaryFormat::Rgba32),
+            Format::Fixed(BinaryFormat::Palette),
+        ]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        match self {
+            Format::Auto => Some(PossibleValue::new("auto")),
+            Format::Fixed(format) => format.to_possible_value(),
+        }
+    }
+}
+
 #[derive(Copy, Clone, PartialEq, Eq, ValueEnum, Debug)]
 pub enum CArrayWidth {
     U8,
@@ -131,6 +248,28 @@ pub enum CArrayWidth {
 
 // MARK: - Helpers
 
+fn write_palette(args: &BinaryArgs, tlut: &[u8]) -> Result<()> {
+    let path = args.palette_output.clone().unwrap_or_else(|| {
+        let mut path = args.input.clone();
+        path.push_str(".tlut.bin");
+        path
+    });
+    println!("Writing palette to {path}");
+
+    let file = File::create(path)?;
+    BufWriter::new(file).write_all(tlut)?;
+    Ok(())
+}
+
+pub(crate) fn write_c_array(output_file: &mut Box<dyn Write>, bin: &[u8], width: CArrayWidth) {
+    match width {
+        CArrayWidth::U8 => write_buf_as_u8(output_file, bin),
+        CArrayWidth::U16 => write_buf_as_u16(output_file, bin),
+        CArrayWidth::U32 => write_buf_as_u32(output_file, bin),
+        CArrayWidth::U64 => write_buf_as_u64(output_file, bin),
+    }
+}
+
 fn write_buf_as_u8(output_file: &mut Box<dyn Write>, bin: &[u8]) {
     write_buf_as_raw_array!(output_file, bin, u8);
 }
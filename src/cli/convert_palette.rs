@@ -0,0 +1,38 @@
+use crate::cli::defines::{PaletteFormat, read_palette_file};
+use anyhow::Result;
+use clap::Args;
+use pigment64::palette::formats::write_palette;
+use std::fs;
+
+// MARK: - Args
+
+#[derive(Args, Debug)]
+pub struct ConvertPaletteArgs {
+    /// Path to the palette input file
+    input: String,
+
+    /// Path to the palette output file
+    output: String,
+
+    /// Format of the input. Defaults to guessing it from the file extension, and to an RGBA16
+    /// TLUT binary otherwise
+    #[arg(value_enum, long)]
+    from: Option<PaletteFormat>,
+
+    /// Format of the output. Defaults to guessing it from the file extension, and to an RGBA16
+    /// TLUT binary otherwise
+    #[arg(value_enum, long)]
+    to: Option<PaletteFormat>,
+}
+
+// MARK: - Handlers
+
+pub fn handle_convert_palette(args: &ConvertPaletteArgs) -> Result<()> {
+    let colors = read_palette_file(&args.input, args.from)?;
+    let format = PaletteFormat::resolve(args.to, &args.output);
+
+    fs::write(&args.output, write_palette(&colors, format)?)?;
+    println!("Converted {} colors", colors.len());
+
+    Ok(())
+}
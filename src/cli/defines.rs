@@ -1,6 +1,10 @@
 use crate::cli::binary::CArrayWidth;
 use clap::ValueEnum;
+use pigment64::color::Color;
+use pigment64::palette::formats::{PaletteFileFormat, read_palette};
 use pigment64::{ImageSize, ImageType};
+use std::fs;
+use std::path::Path;
 
 #[derive(Copy, Clone, PartialEq, Eq, ValueEnum, Debug)]
 pub enum BinaryFormat {
@@ -50,6 +54,21 @@ impl BinaryFormat {
         }
     }
 
+    pub fn from_native(image_type: ImageType) -> Self {
+        match image_type {
+            ImageType::Ci4 => BinaryFormat::Ci4,
+            ImageType::Ci8 => BinaryFormat::Ci8,
+            ImageType::I1 => BinaryFormat::I1,
+            ImageType::I4 => BinaryFormat::I4,
+            ImageType::I8 => BinaryFormat::I8,
+            ImageType::Ia4 => BinaryFormat::Ia4,
+            ImageType::Ia8 => BinaryFormat::Ia8,
+            ImageType::Ia16 => BinaryFormat::Ia16,
+            ImageType::Rgba16 => BinaryFormat::Rgba16,
+            ImageType::Rgba32 => BinaryFormat::Rgba32,
+        }
+    }
+
     pub fn get_size(&self) -> Option<ImageSize> {
         match self {
             BinaryFormat::Ci4 => Some(ImageSize::Bits4),
@@ -66,3 +85,61 @@ impl BinaryFormat {
         }
     }
 }
+
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum, Debug)]
+pub enum PaletteFormat {
+    Rgba16,
+    Ia16,
+    Gpl,
+    Jasc,
+    Act,
+    Png,
+    Swatch,
+}
+
+impl PaletteFormat {
+    /// Returns the given format, or the one guessed from the extension of `path`.
+    pub fn resolve(format: Option<PaletteFormat>, path: &str) -> PaletteFileFormat {
+        match format {
+            Some(PaletteFormat::Rgba16) => PaletteFileFormat::Rgba16,
+            Some(PaletteFormat::Ia16) => PaletteFileFormat::Ia16,
+            Some(PaletteFormat::Gpl) => PaletteFileFormat::Gpl,
+            Some(PaletteFormat::Jasc) => PaletteFileFormat::Jasc,
+            Some(PaletteFormat::Act) => PaletteFileFormat::Act,
+            Some(PaletteFormat::Png) => PaletteFileFormat::Png,
+            Some(PaletteFormat::Swatch) => PaletteFileFormat::Swatch,
+            None => PaletteFileFormat::from_path(Path::new(path)),
+        }
+    }
+}
+
+/// Reads the colors of a palette file of the given format, or of the format guessed from its
+/// extension.
+pub fn read_palette_file(path: &str, format: Option<PaletteFormat>) -> anyhow::Result<Vec<Color>> {
+    Ok(read_palette(
+        &fs::read(path)?,
+        PaletteFormat::resolve(format, path),
+    )?)
+}
+
+/// Parses a decimal or `0x`-prefixed hexadecimal integer from the command line.
+pub fn parse_int(value: &str) -> Result<u32, String> {
+    let result = match value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+    {
+        Some(hex) => u32::from_str_radix(hex, 16),
+        None => value.parse(),
+    };
+
+    result.map_err(|e| format!("invalid number '{value}': {e}"))
+}
+
+/// Derives a C identifier from the name of an input file, without its extension.
+pub fn symbol_name(input: &str) -> String {
+    Path::new(input)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| input.to_string())
+        .replace(|c: char| !c.is_ascii_alphanumeric(), "_")
+}
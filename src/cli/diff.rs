@@ -0,0 +1,168 @@
+use crate::cli::defines::BinaryFormat;
+use anyhow::{Result, anyhow};
+use clap::Args;
+use pigment64::diff::diff_images;
+use pigment64::image::native_image::parse_tlut_entries;
+use pigment64::{
+    Error, ImageFormat, ImageType, NativeImage, PNGImage, TextureLUT, create_palette_from_png,
+};
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::Path;
+
+// MARK: - Args
+
+#[derive(Args, Debug)]
+pub struct DiffArgs {
+    /// Path to the expected texture, either a PNG or a binary
+    expected: String,
+
+    /// Path to the actual texture, either a PNG or a binary
+    actual: String,
+
+    /// Format of the textures. PNG inputs are encoded to it first
+    #[arg(value_enum, short, long)]
+    format: BinaryFormat,
+
+    /// Width of binary inputs. Defaults to the width of the PNG input
+    #[arg(long)]
+    width: Option<u32>,
+
+    /// Height of binary inputs. Defaults to the height of the PNG input
+    #[arg(long)]
+    height: Option<u32>,
+
+    /// Path to the palette binary of the expected texture (only used for CI formats)
+    #[arg(long)]
+    expected_palette: Option<String>,
+
+    /// Path to the palette binary of the actual texture (only used for CI formats)
+    #[arg(long)]
+    actual_palette: Option<String>,
+
+    /// Output file for the diff image
+    #[arg(short, long)]
+    output: Option<String>,
+}
+
+// MARK: - Handlers
+
+pub fn handle_diff(args: &DiffArgs) -> Result<()> {
+    let image_type = args
+        .format
+        .as_native()
+        .ok_or(Error::PaletteConversionError)?;
+
+    let expected = Texture::load(&args.expected)?;
+    let actual = Texture::load(&args.actual)?;
+
+    // Binary inputs take their dimensions from the arguments, or from the PNG input
+    let png_dimensions = [&expected, &actual].iter().find_map(|t| match t {
+        Texture::Png(png, _) => Some((png.width(), png.height())),
+        Texture::Binary(_) => None,
+    });
+    let dimensions = match (args.width, args.height, png_dimensions) {
+        (Some(width), Some(height), _) => (width, height),
+        (width, height, Some((png_width, png_height))) => {
+            (width.unwrap_or(png_width), height.unwrap_or(png_height))
+        }
+        _ => {
+            return Err(anyhow!(
+                "--width and --height are required for binary inputs"
+            ));
+        }
+    };
+
+    let (expected, expected_tlut) =
+        expected.into_native(image_type, dimensions, args.expected_palette.as_deref())?;
+    let (actual, actual_tlut) =
+        actual.into_native(image_type, dimensions, args.actual_palette.as_deref())?;
+
+    let diff = diff_images(
+        &expected,
+        expected_tlut.as_deref(),
+        &actual,
+        actual_tlut.as_deref(),
+    )?;
+
+    let texel_count = diff.width * diff.height;
+    println!(
+        "Differing texels: {}/{}",
+        diff.differing_texels, texel_count
+    );
+    if let Some((x, y)) = diff.first_difference() {
+        println!("First difference: ({x}, {y})");
+    }
+    println!("Max channel error: {}", diff.max_channel_error);
+    println!("PSNR: {:.2} dB", diff.psnr);
+
+    if let Some(output) = &args.output {
+        let mut writer = BufWriter::new(File::create(output)?);
+        diff.write_png(&mut writer)?;
+    }
+
+    Ok(())
+}
+
+// MARK: - Structs
+
+enum Texture {
+    /// A PNG along with its raw bytes, needed to read its palette.
+    Png(PNGImage, Vec<u8>),
+    Binary(Vec<u8>),
+}
+
+impl Texture {
+    fn load(path: &str) -> Result<Self> {
+        let bytes = fs::read(path)?;
+        let is_png = Path::new(path)
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("png"));
+
+        if is_png {
+            Ok(Texture::Png(PNGImage::read(bytes.as_slice())?, bytes))
+        } else {
+            Ok(Texture::Binary(bytes))
+        }
+    }
+
+    /// Converts the texture to a native image, along with its decoded palette for CI formats.
+    ///
+    /// PNGs keep their own dimensions, binaries are read with the given ones.
+    fn into_native(
+        self,
+        image_type: ImageType,
+        dimensions: (u32, u32),
+        palette_path: Option<&str>,
+    ) -> Result<(NativeImage, Option<Vec<u8>>)> {
+        let (data, tlut, (width, height)) = match self {
+            Texture::Png(png, bytes) => {
+                let mut data = Vec::new();
+                png.as_native(&mut data, image_type)?;
+
+                let mut tlut = Vec::new();
+                if image_type.get_format() == ImageFormat::Ci {
+                    create_palette_from_png(bytes.as_slice(), &mut tlut)?;
+                }
+                let tlut = Some(tlut).filter(|tlut| !tlut.is_empty());
+                (data, tlut, (png.width(), png.height()))
+            }
+            Texture::Binary(data) => (data, palette_path.map(fs::read).transpose()?, dimensions),
+        };
+
+        let tlut = match tlut {
+            Some(tlut) if image_type.get_format() == ImageFormat::Ci => {
+                // Palettes written from PNGs only hold the colors they use
+                let tlut_size = image_type.get_size().get_tlut_size().unwrap_or_default();
+                let entries = (tlut.len() / 2).min(tlut_size);
+                Some(parse_tlut_entries(&tlut, entries, TextureLUT::Rgba16)?)
+            }
+            _ => None,
+        };
+
+        Ok((
+            NativeImage::read(data.as_slice(), image_type, width, height)?,
+            tlut,
+        ))
+    }
+}
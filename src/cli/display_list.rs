@@ -0,0 +1,111 @@
+use crate::cli::defines::parse_int;
+use anyhow::Result;
+use clap::{Args, ValueEnum};
+use pigment64::gbi::display_list::extract_textures;
+use pigment64::gbi::{Microcode, SegmentTable};
+use pigment64::image::native_image::parse_tlut_entries;
+use pigment64::{ImageFormat, TextureLUT};
+use std::fs;
+use std::path::PathBuf;
+
+// MARK: - Args
+
+#[derive(Args, Debug)]
+pub struct DisplayListArgs {
+    /// Path to the binary input file containing the display list and its textures
+    input: String,
+
+    /// Segmented address of the display list. Segment 0 maps to the start of the input file
+    #[arg(value_parser = parse_int)]
+    address: u32,
+
+    /// Maps a segment to an offset within the input file, e.g. `--segment 6=0x1000`
+    #[arg(long, value_parser = parse_segment)]
+    segment: Vec<(u8, u32)>,
+
+    /// Microcode the display list was built for
+    #[arg(value_enum, short, long, default_value = "f3dex2")]
+    microcode: MicrocodeArg,
+
+    /// Directory to write the textures to. Defaults to the current directory
+    #[arg(short, long)]
+    output: Option<String>,
+}
+
+// MARK: - Handlers
+
+pub fn handle_display_list(args: &DisplayListArgs) -> Result<()> {
+    let data = fs::read(&args.input)?;
+
+    let mut segments = SegmentTable::default();
+    for (segment, offset) in &args.segment {
+        segments.set(*segment, *offset as usize);
+    }
+
+    let microcode = match args.microcode {
+        MicrocodeArg::F3d | MicrocodeArg::F3dex => Microcode::F3dex,
+        MicrocodeArg::F3dex2 => Microcode::F3dex2,
+    };
+
+    let result = extract_textures(&data, &segments, microcode, args.address)?;
+    let output_dir = PathBuf::from(args.output.clone().unwrap_or_else(|| ".".to_string()));
+
+    for texture in &result.textures {
+        let image = &texture.image;
+        let file_name = format!("{:08X}.{:?}.png", texture.address, image.format).to_lowercase();
+
+        let palette = if image.format.get_format() == ImageFormat::Ci {
+            let Some(tlut) = &texture.tlut else {
+                eprintln!("Skipping {file_name}: its palette could not be found");
+                continue;
+            };
+            let mode = match texture.tlut_mode {
+                TextureLUT::None => TextureLUT::Rgba16,
+                mode => mode,
+            };
+            Some(parse_tlut_entries(tlut, tlut.len() / 2, mode)?)
+        } else {
+            None
+        };
+
+        // Palettes may only be partly loaded, which leaves some indices without a color
+        let mut png = Vec::new();
+        if let Err(error) = image.as_png(&mut png, palette.as_deref()) {
+            eprintln!("Skipping {file_name}: {error}");
+            continue;
+        }
+
+        println!("{file_name}: {}x{}", image.width, image.height);
+        fs::write(output_dir.join(file_name), png)?;
+    }
+
+    for address in &result.unresolved {
+        eprintln!("Could not resolve address {address:08X}, is its segment mapped?");
+    }
+
+    Ok(())
+}
+
+// MARK: - Structs
+
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum, Debug)]
+pub enum MicrocodeArg {
+    F3d,
+    F3dex,
+    F3dex2,
+}
+
+// MARK: - Helpers
+
+fn parse_segment(value: &str) -> Result<(u8, u32), String> {
+    let (segment, offset) = value
+        .split_once('=')
+        .ok_or_else(|| format!("expected SEGMENT=OFFSET, got '{value}'"))?;
+
+    let segment = parse_int(segment)?;
+    if segment > 0x0F {
+        return Err(format!("segment {segment} is out of range"));
+    }
+
+    Ok((segment as u8, parse_int(offset)?))
+}
@@ -0,0 +1,110 @@
+use anyhow::Result;
+use clap::{Args, ValueEnum};
+use pigment64::color::Color;
+use pigment64::palette::edit::{
+    PaletteOrder, match_reference, merge_duplicates, remove_unused, set_transparent_index,
+    sort_palette,
+};
+use pigment64::{PNGImage, create_palette_from_png};
+use std::fs::{self, File};
+use std::io::BufReader;
+
+// MARK: - Args
+
+#[derive(Args, Debug)]
+pub struct EditPaletteArgs {
+    /// Path to the indexed PNG input file
+    input: String,
+
+    /// Output file for the edited PNG
+    #[arg(short, long)]
+    output: String,
+
+    /// Merge entries that are identical at RGBA5551 precision
+    #[arg(long)]
+    merge_duplicates: bool,
+
+    /// Remove entries no pixel uses
+    #[arg(long)]
+    remove_unused: bool,
+
+    /// Sort the entries, keeping transparent ones first
+    #[arg(value_enum, long)]
+    sort: Option<Order>,
+
+    /// Move this entry to index 0 and make it transparent
+    #[arg(long)]
+    transparent_index: Option<u8>,
+
+    /// Palette binary whose order the entries are rearranged to match, such as the original TLUT
+    #[arg(long)]
+    reference: Option<String>,
+
+    /// Output file for the edited palette as a binary
+    #[arg(long)]
+    palette_output: Option<String>,
+}
+
+// MARK: - Handlers
+
+/// Edits are applied in the order of the arguments above.
+pub fn handle_edit_palette(args: &EditPaletteArgs) -> Result<()> {
+    let input_file = File::open(&args.input)?;
+    let mut image = PNGImage::read(BufReader::new(input_file))?;
+    let initial = image.palette().map_or(0, |palette| palette.len());
+
+    if args.merge_duplicates {
+        image = merge_duplicates(&image)?;
+    }
+    if args.remove_unused {
+        image = remove_unused(&image)?;
+    }
+    if let Some(order) = args.sort {
+        image = sort_palette(&image, order.into())?;
+    }
+    if let Some(index) = args.transparent_index {
+        image = set_transparent_index(&image, index)?;
+    }
+    if let Some(reference) = &args.reference {
+        let colors: Vec<Color> = fs::read(reference)?
+            .chunks_exact(2)
+            .map(|entry| Color::from_u16(u16::from_be_bytes([entry[0], entry[1]])))
+            .collect();
+        image = match_reference(&image, &colors)?;
+    }
+
+    let mut png = Vec::new();
+    image.as_png(&mut png)?;
+    fs::write(&args.output, &png)?;
+
+    println!(
+        "Palette went from {} to {} entries",
+        initial,
+        image.palette().map_or(0, |palette| palette.len())
+    );
+
+    if let Some(palette_output) = &args.palette_output {
+        let mut tlut = Vec::new();
+        create_palette_from_png(png.as_slice(), &mut tlut)?;
+        fs::write(palette_output, tlut)?;
+    }
+
+    Ok(())
+}
+
+// MARK: - Structs
+
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum, Debug)]
+pub enum Order {
+    Luminance,
+    Hue,
+}
+
+impl From<Order> for PaletteOrder {
+    fn from(order: Order) -> Self {
+        match order {
+            Order::Luminance => PaletteOrder::Luminance,
+            Order::Hue => PaletteOrder::Hue,
+        }
+    }
+}
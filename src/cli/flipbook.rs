@@ -0,0 +1,185 @@
+use crate::cli::defines::BinaryFormat;
+use anyhow::Result;
+use clap::{Args, Subcommand, ValueEnum};
+use pigment64::image::flipbook::{Flipbook, StripLayout, split_strip};
+use pigment64::{Error, PNGImage};
+use std::fs::{self, File};
+use std::io::BufReader;
+
+// MARK: - Args
+
+#[derive(Subcommand, Debug)]
+pub enum FlipbookCommands {
+    /// Encodes the frames of an APNG, a strip PNG or several PNGs to a native frame sequence
+    Pack {
+        #[clap(flatten)]
+        args: PackArgs,
+    },
+    /// Converts a native frame sequence to an APNG or a strip PNG
+    Unpack {
+        #[clap(flatten)]
+        args: UnpackArgs,
+    },
+}
+
+#[derive(Args, Debug)]
+pub struct PackArgs {
+    /// Paths to the PNG input files. A single input is read as an APNG, or as a strip PNG with
+    /// --frames; several inputs are one frame each
+    #[arg(required = true)]
+    inputs: Vec<String>,
+
+    /// Format of every frame
+    #[arg(value_enum, short, long)]
+    format: BinaryFormat,
+
+    /// Number of frames of a strip PNG input
+    #[arg(long)]
+    frames: Option<u32>,
+
+    /// Layout of the frames of a strip PNG input
+    #[arg(value_enum, long, default_value = "horizontal", requires = "frames")]
+    layout: Layout,
+
+    /// Output file for the frames, one after the other. Defaults to the first input file name
+    /// with ".bin" appended
+    #[arg(short, long)]
+    output: Option<String>,
+
+    /// Output file for the palette shared by CI frames. Defaults to the first input file name
+    /// with ".tlut.bin" appended
+    #[arg(short, long)]
+    palette_output: Option<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct UnpackArgs {
+    /// Path to the binary input file, holding every frame one after the other
+    input: String,
+
+    /// Format of every frame
+    #[arg(value_enum, short, long)]
+    format: BinaryFormat,
+
+    /// Width of a frame
+    #[arg(long)]
+    width: u32,
+
+    /// Height of a frame
+    #[arg(long)]
+    height: u32,
+
+    /// Path to the RGBA16 TLUT binary shared by CI frames
+    #[arg(short, long)]
+    palette: Option<String>,
+
+    /// Write a strip PNG with the frames laid out this way instead of an APNG
+    #[arg(value_enum, long)]
+    strip: Option<Layout>,
+
+    /// Playback rate of the APNG, in frames per second
+    #[arg(long, default_value_t = 20)]
+    fps: u16,
+
+    /// Output file. Defaults to input file name with ".png" appended
+    #[arg(short, long)]
+    output: Option<String>,
+}
+
+// MARK: - Handlers
+
+pub fn handle_flipbook(command: &FlipbookCommands) -> Result<()> {
+    match command {
+        FlipbookCommands::Pack { args } => handle_pack(args),
+        FlipbookCommands::Unpack { args } => handle_unpack(args),
+    }
+}
+
+fn handle_pack(args: &PackArgs) -> Result<()> {
+    let image_type = args
+        .format
+        .as_native()
+        .ok_or(Error::PaletteConversionError)?;
+
+    let frames = match (args.inputs.as_slice(), args.frames) {
+        ([input], Some(frames)) => {
+            let png = PNGImage::read(BufReader::new(File::open(input)?))?;
+            split_strip(&png, args.layout.into(), frames)?
+        }
+        ([input], None) => PNGImage::read_frames(BufReader::new(File::open(input)?))?,
+        (inputs, _) => inputs
+            .iter()
+            .map(|input| Ok(PNGImage::read(BufReader::new(File::open(input)?))?))
+            .collect::<Result<Vec<_>>>()?,
+    };
+
+    let flipbook = Flipbook::from_frames(&frames, image_type)?;
+    println!(
+        "Flipbook has {} frames of {}x{}",
+        flipbook.frames.len(),
+        flipbook.width,
+        flipbook.height
+    );
+
+    let input = &args.inputs[0];
+    let output = args
+        .output
+        .clone()
+        .unwrap_or_else(|| format!("{input}.bin"));
+    fs::write(output, flipbook.data())?;
+
+    if let Some(tlut) = &flipbook.tlut {
+        let palette_output = args
+            .palette_output
+            .clone()
+            .unwrap_or_else(|| format!("{input}.tlut.bin"));
+        fs::write(palette_output, tlut)?;
+    }
+
+    Ok(())
+}
+
+fn handle_unpack(args: &UnpackArgs) -> Result<()> {
+    let image_type = args
+        .format
+        .as_native()
+        .ok_or(Error::PaletteConversionError)?;
+
+    let data = fs::read(&args.input)?;
+    let tlut = match &args.palette {
+        Some(palette) => Some(fs::read(palette)?),
+        None => None,
+    };
+    let flipbook = Flipbook::from_native(image_type, args.width, args.height, &data, tlut)?;
+
+    let mut png = Vec::new();
+    match args.strip {
+        Some(layout) => flipbook.write_strip(&mut png, layout.into())?,
+        None => flipbook.write_apng(&mut png, 1, args.fps.max(1))?,
+    }
+
+    let output = args
+        .output
+        .clone()
+        .unwrap_or_else(|| format!("{}.png", args.input));
+    fs::write(output, png)?;
+
+    Ok(())
+}
+
+// MARK: - Structs
+
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum, Debug)]
+pub enum Layout {
+    Horizontal,
+    Vertical,
+}
+
+impl From<Layout> for StripLayout {
+    fn from(layout: Layout) -> Self {
+        match layout {
+            Layout::Horizontal => StripLayout::Horizontal,
+            Layout::Vertical => StripLayout::Vertical,
+        }
+    }
+}
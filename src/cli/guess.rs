@@ -0,0 +1,75 @@
+use crate::cli::defines::parse_int;
+use anyhow::Result;
+use clap::Args;
+use pigment64::analysis::guess::{DEFAULT_GUESS_WIDTHS, guess_textures, write_contact_sheet};
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::PathBuf;
+
+// MARK: - Args
+
+#[derive(Args, Debug)]
+pub struct GuessArgs {
+    /// Path to the binary input file
+    input: String,
+
+    /// Offset of the region to examine within the input file
+    #[arg(long, value_parser = parse_int, default_value = "0")]
+    offset: u32,
+
+    /// Length of the region to examine. Defaults to the rest of the file
+    #[arg(long, value_parser = parse_int)]
+    length: Option<u32>,
+
+    /// Comma separated list of candidate widths
+    #[arg(long, value_delimiter = ',', default_values_t = DEFAULT_GUESS_WIDTHS)]
+    widths: Vec<u32>,
+
+    /// Output contact sheet. Defaults to input file name with ".guess.png" appended
+    #[arg(short, long)]
+    output: Option<String>,
+
+    /// Number of top ranked candidates to print
+    #[arg(long, default_value_t = 10)]
+    top: usize,
+}
+
+// MARK: - Handlers
+
+pub fn handle_guess(args: &GuessArgs) -> Result<()> {
+    let data = fs::read(&args.input)?;
+
+    let start = args.offset as usize;
+    let end = match args.length {
+        Some(length) => start + length as usize,
+        None => data.len(),
+    };
+    let region = data
+        .get(start..end)
+        .ok_or_else(|| anyhow::anyhow!("region {start:#X}..{end:#X} is outside of the input"))?;
+
+    let candidates = guess_textures(region, &args.widths)?;
+
+    for candidate in candidates.iter().take(args.top) {
+        println!(
+            "{:?} {}x{}: score {:.3} (rows {:.3}, palette {:.3})",
+            candidate.format,
+            candidate.width,
+            candidate.height,
+            candidate.score,
+            candidate.row_correlation,
+            candidate.palette_plausibility
+        );
+    }
+
+    let output_path = PathBuf::from(args.output.clone().unwrap_or_else(|| {
+        let mut path = args.input.clone();
+        path.push_str(".guess.png");
+        path
+    }));
+
+    let mut output_writer = BufWriter::new(File::create(output_path)?);
+    write_contact_sheet(&mut output_writer, &candidates)?;
+
+    Ok(())
+}
@@ -0,0 +1,100 @@
+use crate::cli::defines::BinaryFormat;
+use anyhow::Result;
+use clap::{Args, ValueEnum};
+use pigment64::gbi::mipmap::{MAX_MIPMAP_LEVELS, MipmapFilter, build_mipmaps};
+use pigment64::{Error, PNGImage};
+use std::fs::{self, File};
+use std::io::BufReader;
+
+// MARK: - Args
+
+#[derive(Args, Debug)]
+pub struct MipmapArgs {
+    /// Path to the PNG input file
+    input: String,
+
+    /// Format of every level
+    #[arg(value_enum, short, long)]
+    format: BinaryFormat,
+
+    /// Filter used to compute each level from the previous one
+    #[arg(value_enum, long, default_value = "box")]
+    filter: Filter,
+
+    /// Maximum number of levels, including the full size one
+    #[arg(long, default_value_t = MAX_MIPMAP_LEVELS)]
+    levels: usize,
+
+    /// Output file for the levels laid out in TMEM. Defaults to input file name with ".mip.bin"
+    /// appended
+    #[arg(short, long)]
+    output: Option<String>,
+
+    /// Output file for the palette of color-indexed formats. Defaults to input file name with
+    /// ".tlut.bin" appended
+    #[arg(long)]
+    palette_output: Option<String>,
+
+    /// Output file for the tile descriptor macros. Defaults to printing them
+    #[arg(long)]
+    tiles_output: Option<String>,
+}
+
+// MARK: - Handlers
+
+pub fn handle_mipmap(args: &MipmapArgs) -> Result<()> {
+    let image_type = args
+        .format
+        .as_native()
+        .ok_or(Error::PaletteConversionError)?;
+
+    let input_file = File::open(&args.input)?;
+    let image = PNGImage::read(BufReader::new(input_file))?;
+
+    let mipmaps = build_mipmaps(&image, image_type, args.filter.into(), args.levels)?;
+
+    let output = args
+        .output
+        .clone()
+        .unwrap_or_else(|| format!("{}.mip.bin", args.input));
+    fs::write(output, &mipmaps.tmem)?;
+
+    if let Some(tlut) = &mipmaps.tlut {
+        let palette_output = args
+            .palette_output
+            .clone()
+            .unwrap_or_else(|| format!("{}.tlut.bin", args.input));
+        fs::write(palette_output, tlut)?;
+    }
+
+    let mut tiles = String::new();
+    for level in &mipmaps.levels {
+        tiles.push_str(&format!("// {}x{}\n", level.width, level.height));
+        tiles.push_str(&level.tile.to_c_macros()?);
+        tiles.push('\n');
+    }
+
+    match &args.tiles_output {
+        Some(path) => fs::write(path, tiles)?,
+        None => print!("{tiles}"),
+    }
+
+    Ok(())
+}
+
+// MARK: - Structs
+
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum, Debug)]
+pub enum Filter {
+    Box,
+    Nearest,
+}
+
+impl From<Filter> for MipmapFilter {
+    fn from(filter: Filter) -> Self {
+        match filter {
+            Filter::Box => MipmapFilter::Box,
+            Filter::Nearest => MipmapFilter::Nearest,
+        }
+    }
+}
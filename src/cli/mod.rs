@@ -1,5 +1,25 @@
 pub mod defines;
 pub mod macros;
 
+pub mod analyze;
+pub mod atlas;
 pub mod binary;
+pub mod convert_palette;
+pub mod diff;
+pub mod display_list;
+pub mod edit_palette;
+pub mod flipbook;
+pub mod guess;
+pub mod mipmap;
+pub mod palette_png;
 pub mod png;
+pub mod s2dex;
+pub mod shared_palette;
+pub mod sp_sprite;
+pub mod splat;
+pub mod split;
+pub mod sprite;
+pub mod tlut_search;
+pub mod variants;
+pub mod verify;
+pub mod zapd;
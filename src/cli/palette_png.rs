@@ -0,0 +1,42 @@
+use crate::cli::defines::{PaletteFormat, read_palette_file};
+use anyhow::Result;
+use clap::Args;
+use pigment64::palette::swatch::{SWATCH_CELL_SIZE, render_swatches};
+use std::fs::File;
+use std::io::BufWriter;
+
+// MARK: - Args
+
+#[derive(Args, Debug)]
+pub struct PalettePngArgs {
+    /// Path to the palette input file
+    input: String,
+
+    /// Format of the palette. Defaults to guessing it from the file extension, and to an RGBA16
+    /// TLUT binary otherwise
+    #[arg(value_enum, long)]
+    palette_format: Option<PaletteFormat>,
+
+    /// Width and height of each swatch, in pixels
+    #[arg(long, default_value_t = SWATCH_CELL_SIZE)]
+    cell_size: u32,
+
+    /// Output file. Defaults to input file name with ".png" appended
+    #[arg(short, long)]
+    output: Option<String>,
+}
+
+// MARK: - Handlers
+
+pub fn handle_palette_png(args: &PalettePngArgs) -> Result<()> {
+    let colors = read_palette_file(&args.input, args.palette_format)?;
+    let swatches = render_swatches(&colors, args.cell_size);
+
+    let output = args
+        .output
+        .clone()
+        .unwrap_or_else(|| format!("{}.png", args.input));
+    swatches.as_png(&mut BufWriter::new(File::create(output)?))?;
+
+    Ok(())
+}
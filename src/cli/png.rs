@@ -1,7 +1,12 @@
-use crate::cli::defines::BinaryFormat;
+use crate::cli::defines::{BinaryFormat, PaletteFormat, parse_int, read_palette_file};
 use anyhow::Result;
-use clap::Args;
-use pigment64::{Error, TextureLUT, image::native_image::parse_tlut};
+use clap::{Args, ValueEnum};
+use pigment64::analysis::width::infer_dimensions;
+use pigment64::color::Color;
+use pigment64::image::native_image::{TlutIndexPolicy, parse_tlut_entries};
+use pigment64::palette::banks::palette_bank;
+use pigment64::palette::formats::PaletteFileFormat;
+use pigment64::{Error, TextureLUT};
 use std::fs::File;
 use std::io::{BufReader, BufWriter, Read, Write};
 use std::path::PathBuf;
@@ -13,13 +18,13 @@ pub struct PngArgs {
     /// Path to the binary input file
     input: String,
 
-    /// Width of the binary image
-    #[arg(long)]
-    width: u32,
+    /// Width of the binary image, or "auto" to infer it from the data
+    #[arg(long, value_parser = parse_width)]
+    width: Width,
 
-    /// Height of the binary image
+    /// Height of the binary image. Defaults to as many rows as the input contains
     #[arg(long)]
-    height: u32,
+    height: Option<u32>,
 
     /// Input format
     #[arg(value_enum, short, long)]
@@ -29,10 +34,29 @@ pub struct PngArgs {
     #[arg(short, long)]
     output: Option<String>,
 
-    /// Path to the palette binary file (only required for CI formats)
+    /// Path to the palette file (only required for CI formats)
     #[arg(short, long)]
     palette: Option<String>,
 
+    /// Format of the palette file. Defaults to guessing it from the file extension, and to an
+    /// RGBA16 TLUT binary otherwise
+    #[arg(value_enum, long, requires = "palette")]
+    palette_format: Option<PaletteFormat>,
+
+    /// Bank of the palette used by CI4 images, for palettes holding several 16-color banks
+    #[arg(long, requires = "palette")]
+    palette_bank: Option<u8>,
+
+    /// Number of entries in the palette, for partial TLUTs. Defaults to every entry the palette
+    /// holds, up to 16 for ci4 and 256 for ci8
+    #[arg(long, requires = "palette")]
+    palette_entries: Option<usize>,
+
+    /// How to draw indices past the last entry of the palette: fail, use the last entry, or draw
+    /// them in magenta
+    #[arg(value_enum, long, default_value_t = OutOfRange::Error)]
+    out_of_range: OutOfRange,
+
     /// Flip the image on the x axis
     #[arg(long)]
     flip_x: bool,
@@ -46,6 +70,39 @@ pub struct PngArgs {
     word_swap: bool,
 }
 
+// MARK: - Structs
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Width {
+    Auto,
+    Fixed(u32),
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum, Debug)]
+pub enum OutOfRange {
+    Error,
+    Clamp,
+    Marker,
+}
+
+impl OutOfRange {
+    fn policy(self) -> TlutIndexPolicy {
+        match self {
+            OutOfRange::Error => TlutIndexPolicy::Error,
+            OutOfRange::Clamp => TlutIndexPolicy::Clamp,
+            OutOfRange::Marker => TlutIndexPolicy::Marker(Color::RGB(0xFF, 0, 0xFF)),
+        }
+    }
+}
+
+fn parse_width(value: &str) -> Result<Width, String> {
+    if value.eq_ignore_ascii_case("auto") {
+        Ok(Width::Auto)
+    } else {
+        parse_int(value).map(Width::Fixed)
+    }
+}
+
 // MARK: - Handlers
 
 pub fn handle_png(args: &PngArgs) -> Result<()> {
@@ -63,8 +120,30 @@ pub fn handle_png(args: &PngArgs) -> Result<()> {
         .as_native()
         .ok_or(Error::PaletteConversionError)?;
 
-    let mut image =
-        pigment64::NativeImage::read(&mut input_reader, image_type, args.width, args.height)?;
+    let mut image = pigment64::NativeImage::read(&mut input_reader, image_type, 0, 0)?;
+
+    let (width, height) = match args.width {
+        Width::Fixed(width) => {
+            let row_bits = width * image_type.get_size().get_bpp();
+            let height = args
+                .height
+                .unwrap_or((image.data.len() as u32 * 8) / row_bits.max(1));
+            (width, height)
+        }
+        Width::Auto => {
+            let candidate = infer_dimensions(&image.data, image_type)?
+                .into_iter()
+                .find(|c| args.height.is_none_or(|height| c.height == height))
+                .ok_or_else(|| anyhow::anyhow!("could not infer a width for the input"))?;
+            println!(
+                "Inferred dimensions: {}x{}",
+                candidate.width, candidate.height
+            );
+            (candidate.width, candidate.height)
+        }
+    };
+    image.width = width;
+    image.height = height;
 
     if args.word_swap {
         image.swap_word_rows();
@@ -78,18 +157,52 @@ pub fn handle_png(args: &PngArgs) -> Result<()> {
             .palette
             .as_ref()
             .ok_or_else(|| anyhow::anyhow!("--palette is required for ci4/ci8 formats"))?;
-        let palette_file = File::open(palette_path)?;
-        let mut palette_reader = BufReader::new(palette_file);
-        let mut palette_bytes = Vec::new();
-        palette_reader.read_to_end(&mut palette_bytes)?;
-
         let image_size = args
             .format
             .get_size()
             .ok_or(Error::PaletteConversionError)?;
 
-        let palette = parse_tlut(&palette_bytes, image_size, TextureLUT::Rgba16)?;
-        image.as_png(&mut output, Some(&palette))?;
+        let palette = match PaletteFormat::resolve(args.palette_format, palette_path) {
+            PaletteFileFormat::Rgba16 | PaletteFileFormat::Ia16 if args.palette_bank.is_none() => {
+                let palette_file = File::open(palette_path)?;
+                let mut palette_reader = BufReader::new(palette_file);
+                let mut palette_bytes = Vec::new();
+                palette_reader.read_to_end(&mut palette_bytes)?;
+
+                let mode = match args.palette_format {
+                    Some(PaletteFormat::Ia16) => TextureLUT::Ia16,
+                    _ => TextureLUT::Rgba16,
+                };
+                // Short TLUTs are read up to their last entry unless a count is given
+                let tlut_size = image_size
+                    .get_tlut_size()
+                    .ok_or(Error::InvalidSizeForTlut(image_size))?;
+                let entries = args
+                    .palette_entries
+                    .unwrap_or_else(|| tlut_size.min(palette_bytes.len() / 2));
+                parse_tlut_entries(&palette_bytes, entries, mode)?
+            }
+            _ => {
+                let colors = read_palette_file(palette_path, args.palette_format)?;
+                let colors = match args.palette_bank {
+                    Some(bank) => palette_bank(&colors, bank)?,
+                    None => &colors[..colors.len().min(image_size.get_tlut_size().unwrap_or(0))],
+                };
+                let colors = match args.palette_entries {
+                    Some(entries) if entries > colors.len() => {
+                        return Err(Error::TlutTooShort {
+                            expected: entries,
+                            actual: colors.len(),
+                        }
+                        .into());
+                    }
+                    Some(entries) => &colors[..entries],
+                    None => colors,
+                };
+                colors.iter().flat_map(|c| [c.r, c.g, c.b, c.a]).collect()
+            }
+        };
+        image.as_png_with_policy(&mut output, Some(&palette), args.out_of_range.policy())?;
     } else {
         image.as_png(&mut output, None)?;
     }
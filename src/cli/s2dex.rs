@@ -0,0 +1,156 @@
+use crate::cli::binary::{CArrayWidth, write_c_array};
+use crate::cli::defines::{BinaryFormat, symbol_name};
+use anyhow::Result;
+use clap::{Args, ValueEnum};
+use pigment64::analysis::quality::encode_format;
+use pigment64::gbi::s2dex::{ObjBg, ObjLoad, ObjSprite, ObjTxtr};
+use pigment64::{Error, PNGImage};
+use std::fs::File;
+use std::io::{BufReader, Write};
+
+// MARK: - Args
+
+#[derive(Args, Debug)]
+pub struct S2dexArgs {
+    /// Path to the PNG input file
+    input: String,
+
+    /// Format of the texture
+    #[arg(value_enum, short, long)]
+    format: BinaryFormat,
+
+    /// Object drawing the texture
+    #[arg(value_enum, long, default_value = "sprite")]
+    object: Object,
+
+    /// How the texture is loaded into TMEM
+    #[arg(value_enum, long, default_value = "block")]
+    load: Load,
+
+    /// TMEM address of the sprite texture, in 64-bit words
+    #[arg(long, default_value_t = 0)]
+    tmem: u16,
+
+    /// Horizontal position of the object on screen
+    #[arg(long, default_value_t = 0, allow_negative_numbers = true)]
+    x: i16,
+
+    /// Vertical position of the object on screen
+    #[arg(long, default_value_t = 0, allow_negative_numbers = true)]
+    y: i16,
+
+    /// Width of the background frame on screen. Defaults to the width of the image
+    #[arg(long)]
+    frame_width: Option<u32>,
+
+    /// Height of the background frame on screen. Defaults to the height of the image
+    #[arg(long)]
+    frame_height: Option<u32>,
+
+    /// Prefix of the C symbols. Defaults to the input file name without its extension
+    #[arg(long)]
+    name: Option<String>,
+
+    /// Output file. Defaults to input file name with ".inc.c" appended
+    #[arg(short, long)]
+    output: Option<String>,
+}
+
+// MARK: - Handlers
+
+pub fn handle_s2dex(args: &S2dexArgs) -> Result<()> {
+    let image_type = args
+        .format
+        .as_native()
+        .ok_or(Error::PaletteConversionError)?;
+
+    let input_file = File::open(&args.input)?;
+    let image = PNGImage::read(BufReader::new(input_file))?;
+    let (width, height) = (image.width(), image.height());
+    let (mut data, tlut) = encode_format(&image, image_type)?;
+
+    let name = args
+        .name
+        .clone()
+        .unwrap_or_else(|| symbol_name(&args.input));
+    let texture = format!("{name}_tex");
+
+    // Objects are computed first so that invalid textures don't leave a partial output behind
+    let mut objects = String::new();
+    if let Some(tlut) = &tlut {
+        let txtr = ObjTxtr::tlut(0, (tlut.len() / 2) as u16)?;
+        objects.push_str(&txtr.to_c(&format!("{name}_tlut_txtr"), &format!("{name}_tlut")));
+        objects.push('\n');
+    }
+
+    match args.object {
+        Object::Sprite => {
+            let txtr = match args.load.into() {
+                ObjLoad::Block => ObjTxtr::block(image_type, width, height, args.tmem)?,
+                ObjLoad::Tile => ObjTxtr::tile(image_type, width, height, args.tmem)?,
+            };
+            objects.push_str(&txtr.to_c(&format!("{name}_txtr"), &texture));
+            objects.push('\n');
+
+            let mut sprite = ObjSprite::new(image_type, width, height, args.tmem)?;
+            sprite.obj_x = args.x << 2;
+            sprite.obj_y = args.y << 2;
+            objects.push_str(&sprite.to_c(&format!("{name}_sprite"))?);
+        }
+        Object::Bg => {
+            let frame = (
+                args.x,
+                args.y,
+                args.frame_width.unwrap_or(width),
+                args.frame_height.unwrap_or(height),
+            );
+            let bg = ObjBg::with_frame(image_type, width, height, args.load.into(), frame)?;
+            objects.push_str(&bg.to_c(&format!("{name}_bg"), &texture)?);
+        }
+    }
+
+    let output = args
+        .output
+        .clone()
+        .unwrap_or_else(|| format!("{}.inc.c", args.input));
+    let mut output_file: Box<dyn Write> = Box::new(File::create(output)?);
+
+    // Texels are 64-bit aligned so the RDP can load them
+    data.resize(data.len().next_multiple_of(8), 0);
+    writeln!(output_file, "static u64 {texture}[] = {{")?;
+    write_c_array(&mut output_file, &data, CArrayWidth::U64);
+    writeln!(output_file, "}};\n")?;
+
+    if let Some(tlut) = &tlut {
+        writeln!(output_file, "static u16 {name}_tlut[] = {{")?;
+        write_c_array(&mut output_file, tlut, CArrayWidth::U16);
+        writeln!(output_file, "}};\n")?;
+    }
+
+    write!(output_file, "{objects}")?;
+
+    Ok(())
+}
+
+// MARK: - Structs
+
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum, Debug)]
+pub enum Object {
+    Sprite,
+    Bg,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum, Debug)]
+pub enum Load {
+    Block,
+    Tile,
+}
+
+impl From<Load> for ObjLoad {
+    fn from(load: Load) -> Self {
+        match load {
+            Load::Block => ObjLoad::Block,
+            Load::Tile => ObjLoad::Tile,
+        }
+    }
+}
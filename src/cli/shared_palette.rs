@@ -0,0 +1,85 @@
+use crate::cli::defines::BinaryFormat;
+use anyhow::Result;
+use clap::Args;
+use pigment64::palette::shared::build_shared_palette;
+use pigment64::{Error, PNGImage};
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+
+// MARK: - Args
+
+#[derive(Args, Debug)]
+pub struct SharedPaletteArgs {
+    /// Paths to the PNG input files
+    #[arg(required = true)]
+    inputs: Vec<String>,
+
+    /// Color-indexed format of every image
+    #[arg(value_enum, short, long)]
+    format: BinaryFormat,
+
+    /// Output file for the shared palette
+    #[arg(short, long)]
+    palette_output: String,
+
+    /// Directory the images are written to, each named after its input file with ".bin"
+    /// appended. Defaults to writing them next to their input file
+    #[arg(short, long)]
+    output_dir: Option<PathBuf>,
+
+    /// Also write each image as an indexed PNG using the shared palette, with ".png" appended
+    #[arg(long)]
+    png: bool,
+}
+
+// MARK: - Handlers
+
+pub fn handle_shared_palette(args: &SharedPaletteArgs) -> Result<()> {
+    let image_type = args
+        .format
+        .as_native()
+        .ok_or(Error::PaletteConversionError)?;
+
+    let images = args
+        .inputs
+        .iter()
+        .map(|input| Ok(PNGImage::read(BufReader::new(File::open(input)?))?))
+        .collect::<Result<Vec<_>>>()?;
+
+    let shared = build_shared_palette(&images, image_type)?;
+    println!(
+        "Shared palette has {} colors for {} images",
+        shared.palette.len(),
+        shared.images.len()
+    );
+    fs::write(&args.palette_output, shared.tlut())?;
+
+    for (input, image) in args.inputs.iter().zip(&shared.images) {
+        let path = match &args.output_dir {
+            Some(dir) => dir.join(Path::new(input).file_name().unwrap_or(input.as_ref())),
+            None => PathBuf::from(input),
+        };
+
+        let mut bin = Vec::new();
+        image.as_native(&mut bin, image_type)?;
+        fs::write(append_extension(&path, "bin"), bin)?;
+
+        if args.png {
+            let mut png = Vec::new();
+            image.as_png(&mut png)?;
+            fs::write(append_extension(&path, "png"), png)?;
+        }
+    }
+
+    Ok(())
+}
+
+// MARK: - Helpers
+
+fn append_extension(path: &Path, extension: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(".");
+    path.push(extension);
+    PathBuf::from(path)
+}
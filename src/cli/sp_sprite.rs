@@ -0,0 +1,125 @@
+use crate::cli::binary::{CArrayWidth, write_c_array};
+use crate::cli::defines::{BinaryFormat, symbol_name};
+use anyhow::Result;
+use clap::{Args, ValueEnum};
+use pigment64::gbi::split::split_image;
+use pigment64::gbi::sprite::{SpriteAttribute, sp_bitmap_name, sp_sprite_source, sp_tlut_name};
+use pigment64::{Error, PNGImage};
+use std::fs::File;
+use std::io::{BufReader, Write};
+
+// MARK: - Args
+
+#[derive(Args, Debug)]
+pub struct SpSpriteArgs {
+    /// Path to the PNG input file
+    input: String,
+
+    /// Format of the sprite's bitmaps
+    #[arg(value_enum, short, long)]
+    format: BinaryFormat,
+
+    /// Maximum width of a bitmap. Defaults to the width of the image
+    #[arg(long)]
+    width: Option<u32>,
+
+    /// Prefix of the C symbols. Defaults to the input file name without its extension
+    #[arg(long)]
+    name: Option<String>,
+
+    /// Attribute flags of the sprite
+    #[arg(value_enum, long, value_delimiter = ',')]
+    attributes: Vec<Attribute>,
+
+    /// Output file. Defaults to input file name with ".inc.c" appended
+    #[arg(short, long)]
+    output: Option<String>,
+}
+
+// MARK: - Handlers
+
+pub fn handle_sp_sprite(args: &SpSpriteArgs) -> Result<()> {
+    let image_type = args
+        .format
+        .as_native()
+        .ok_or(Error::PaletteConversionError)?;
+
+    let input_file = File::open(&args.input)?;
+    let image = PNGImage::read(BufReader::new(input_file))?;
+    let split = split_image(&image, image_type, args.width, 0)?;
+
+    let name = args
+        .name
+        .clone()
+        .unwrap_or_else(|| symbol_name(&args.input));
+    let attributes: Vec<SpriteAttribute> = args.attributes.iter().map(|&a| a.into()).collect();
+
+    let output = args
+        .output
+        .clone()
+        .unwrap_or_else(|| format!("{}.inc.c", args.input));
+    let mut output_file: Box<dyn Write> = Box::new(File::create(output)?);
+
+    // Texels are 64-bit aligned so the RDP can load them
+    for (index, tile) in split.tiles.iter().enumerate() {
+        let mut data = tile.data.clone();
+        data.resize(data.len().next_multiple_of(8), 0);
+
+        writeln!(
+            output_file,
+            "static u64 {}[] = {{",
+            sp_bitmap_name(&name, index)
+        )?;
+        write_c_array(&mut output_file, &data, CArrayWidth::U64);
+        writeln!(output_file, "}};\n")?;
+    }
+
+    if let Some(tlut) = &split.tlut {
+        writeln!(output_file, "static u16 {}[] = {{", sp_tlut_name(&name))?;
+        write_c_array(&mut output_file, tlut, CArrayWidth::U16);
+        writeln!(output_file, "}};\n")?;
+    }
+
+    write!(
+        output_file,
+        "{}",
+        sp_sprite_source(&name, &split, &attributes)?
+    )?;
+
+    Ok(())
+}
+
+// MARK: - Structs
+
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum, Debug)]
+pub enum Attribute {
+    Transparent,
+    Cutout,
+    Hidden,
+    Z,
+    Scale,
+    FastCopy,
+    Overlap,
+    TexShift,
+    FracPos,
+    TexShuf,
+    Extern,
+}
+
+impl From<Attribute> for SpriteAttribute {
+    fn from(attribute: Attribute) -> Self {
+        match attribute {
+            Attribute::Transparent => SpriteAttribute::Transparent,
+            Attribute::Cutout => SpriteAttribute::Cutout,
+            Attribute::Hidden => SpriteAttribute::Hidden,
+            Attribute::Z => SpriteAttribute::Z,
+            Attribute::Scale => SpriteAttribute::Scale,
+            Attribute::FastCopy => SpriteAttribute::FastCopy,
+            Attribute::Overlap => SpriteAttribute::Overlap,
+            Attribute::TexShift => SpriteAttribute::TexShift,
+            Attribute::FracPos => SpriteAttribute::FracPos,
+            Attribute::TexShuf => SpriteAttribute::TexShuf,
+            Attribute::Extern => SpriteAttribute::Extern,
+        }
+    }
+}
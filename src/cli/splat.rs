@@ -0,0 +1,79 @@
+use anyhow::Result;
+use clap::{Args, Subcommand};
+use pigment64::assets::splat::parse_splat_yaml;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::PathBuf;
+
+// MARK: - Args
+
+#[derive(Subcommand, Debug)]
+pub enum SplatCommands {
+    /// Extracts every image segment of a splat configuration to PNGs
+    Extract {
+        #[clap(flatten)]
+        args: SplatArgs,
+
+        /// Directory to write the PNGs to
+        #[arg(short, long)]
+        output_dir: String,
+    },
+    /// Writes the images and palettes of a splat configuration back into a ROM
+    Build {
+        #[clap(flatten)]
+        args: SplatArgs,
+
+        /// Directory containing the PNGs
+        #[arg(long)]
+        png_dir: String,
+
+        /// Output file for the rebuilt ROM
+        #[arg(short, long)]
+        output: String,
+    },
+}
+
+#[derive(Args, Debug)]
+pub struct SplatArgs {
+    /// Path to the splat YAML configuration
+    yaml: String,
+
+    /// Path to the ROM the configuration describes
+    #[arg(short, long)]
+    rom: String,
+}
+
+// MARK: - Handlers
+
+pub fn handle_splat(command: &SplatCommands) -> Result<()> {
+    match command {
+        SplatCommands::Extract { args, output_dir } => {
+            let config = parse_splat_yaml(&fs::read_to_string(&args.yaml)?)?;
+            let rom = fs::read(&args.rom)?;
+
+            for (file_name, png) in config.extract(&rom)? {
+                let path = PathBuf::from(output_dir).join(file_name);
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                File::create(path)?.write_all(&png)?;
+            }
+        }
+        SplatCommands::Build {
+            args,
+            png_dir,
+            output,
+        } => {
+            let config = parse_splat_yaml(&fs::read_to_string(&args.yaml)?)?;
+            let mut rom = fs::read(&args.rom)?;
+
+            config.rebuild(&mut rom, |image| {
+                Ok(fs::read(PathBuf::from(png_dir).join(image.file_name()))?)
+            })?;
+
+            fs::write(output, rom)?;
+        }
+    }
+
+    Ok(())
+}
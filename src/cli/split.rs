@@ -0,0 +1,100 @@
+use crate::cli::binary::write_c_array;
+use crate::cli::defines::BinaryFormat;
+use anyhow::Result;
+use clap::Args;
+use pigment64::gbi::split::split_image;
+use pigment64::{Error, PNGImage};
+use std::fs::{self, File};
+use std::io::{BufReader, Write};
+
+// MARK: - Args
+
+#[derive(Args, Debug)]
+pub struct SplitArgs {
+    /// Path to the PNG input file
+    input: String,
+
+    /// Format of every tile
+    #[arg(value_enum, short, long)]
+    format: BinaryFormat,
+
+    /// Maximum width of a tile. Defaults to the width of the image
+    #[arg(long)]
+    width: Option<u32>,
+
+    /// Number of rows and columns shared by neighbouring tiles, for bilinear filtering
+    #[arg(long, default_value_t = 0)]
+    overlap: u32,
+
+    /// Prefix of the tile files, which are named "<PREFIX>.<INDEX>.bin". Defaults to the input
+    /// file name
+    #[arg(short, long)]
+    output: Option<String>,
+
+    /// Output each tile as a raw C array which can be `#include`d in a file, named
+    /// "<PREFIX>.<INDEX>.inc.c"
+    #[arg(long)]
+    c_array: bool,
+
+    /// Output file for the palette of color-indexed formats. Defaults to the prefix with
+    /// ".tlut.bin" appended
+    #[arg(long)]
+    palette_output: Option<String>,
+
+    /// Output file for the table of tile positions. Defaults to printing it
+    #[arg(long)]
+    layout_output: Option<String>,
+}
+
+// MARK: - Handlers
+
+pub fn handle_split(args: &SplitArgs) -> Result<()> {
+    let image_type = args
+        .format
+        .as_native()
+        .ok_or(Error::PaletteConversionError)?;
+
+    let input_file = File::open(&args.input)?;
+    let image = PNGImage::read(BufReader::new(input_file))?;
+
+    let split = split_image(&image, image_type, args.width, args.overlap)?;
+    let prefix = args.output.clone().unwrap_or_else(|| args.input.clone());
+
+    for (index, tile) in split.tiles.iter().enumerate() {
+        if args.c_array {
+            let mut output: Box<dyn Write> =
+                Box::new(File::create(format!("{prefix}.{index}.inc.c"))?);
+            write_c_array(&mut output, &tile.data, args.format.get_width());
+        } else {
+            fs::write(format!("{prefix}.{index}.bin"), &tile.data)?;
+        }
+    }
+
+    if let Some(tlut) = &split.tlut {
+        let palette_output = args
+            .palette_output
+            .clone()
+            .unwrap_or_else(|| format!("{prefix}.tlut.bin"));
+        fs::write(palette_output, tlut)?;
+    }
+
+    let mut layout = format!(
+        "// {} tiles ({}x{}), x, y, width, height\n",
+        split.tiles.len(),
+        split.columns,
+        split.rows
+    );
+    for tile in &split.tiles {
+        layout.push_str(&format!(
+            "{{ {}, {}, {}, {} }},\n",
+            tile.x, tile.y, tile.width, tile.height
+        ));
+    }
+
+    match &args.layout_output {
+        Some(path) => fs::write(path, layout)?,
+        None => print!("{layout}"),
+    }
+
+    Ok(())
+}
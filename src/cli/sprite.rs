@@ -0,0 +1,140 @@
+use crate::cli::defines::BinaryFormat;
+use anyhow::Result;
+use clap::{Args, Subcommand};
+use pigment64::image::libdragon::{LibdragonSprite, SpriteLod};
+use pigment64::{Error, PNGImage};
+use std::fs::{self, File};
+use std::io::BufReader;
+
+// MARK: - Args
+
+#[derive(Subcommand, Debug)]
+pub enum SpriteCommands {
+    /// Converts a PNG to a libdragon sprite
+    Pack {
+        #[clap(flatten)]
+        args: PackArgs,
+    },
+    /// Converts a libdragon sprite to a PNG
+    Unpack {
+        #[clap(flatten)]
+        args: UnpackArgs,
+    },
+}
+
+#[derive(Args, Debug)]
+pub struct PackArgs {
+    /// Path to the PNG input file
+    input: String,
+
+    /// Texture format of the sprite
+    #[arg(value_enum, short, long)]
+    format: BinaryFormat,
+
+    /// Output file. Defaults to input file name with ".sprite" appended
+    #[arg(short, long)]
+    output: Option<String>,
+
+    /// Number of mipmap levels stored besides the full-size image
+    #[arg(long, default_value_t = 0)]
+    mipmaps: usize,
+
+    /// Number of horizontal slices of the sprite
+    #[arg(long, default_value_t = 1)]
+    hslices: u8,
+
+    /// Number of vertical slices of the sprite
+    #[arg(long, default_value_t = 1)]
+    vslices: u8,
+}
+
+#[derive(Args, Debug)]
+pub struct UnpackArgs {
+    /// Path to the sprite input file
+    input: String,
+
+    /// Output file. Defaults to input file name with ".png" appended
+    #[arg(short, long)]
+    output: Option<String>,
+
+    /// Also write each mipmap level, with ".<LEVEL>.png" appended to the input file name
+    #[arg(long)]
+    mipmaps: bool,
+}
+
+// MARK: - Handlers
+
+pub fn handle_sprite(command: &SpriteCommands) -> Result<()> {
+    match command {
+        SpriteCommands::Pack { args } => handle_pack(args),
+        SpriteCommands::Unpack { args } => handle_unpack(args),
+    }
+}
+
+fn handle_pack(args: &PackArgs) -> Result<()> {
+    let image_type = args
+        .format
+        .as_native()
+        .ok_or(Error::PaletteConversionError)?;
+
+    let png = PNGImage::read(BufReader::new(File::open(&args.input)?))?;
+    let mut sprite = LibdragonSprite::from_png(&png, image_type, args.mipmaps)?;
+    sprite.hslices = args.hslices;
+    sprite.vslices = args.vslices;
+
+    let mut bytes = Vec::new();
+    sprite.write(&mut bytes)?;
+
+    let output = args
+        .output
+        .clone()
+        .unwrap_or_else(|| format!("{}.sprite", args.input));
+    fs::write(output, bytes)?;
+
+    Ok(())
+}
+
+fn handle_unpack(args: &UnpackArgs) -> Result<()> {
+    let sprite = LibdragonSprite::read(BufReader::new(File::open(&args.input)?))?;
+    println!(
+        "Sprite is {}x{} {} with {} mipmap levels",
+        sprite.width,
+        sprite.height,
+        format!("{:?}", sprite.format).to_lowercase(),
+        sprite.lods.len()
+    );
+
+    let mut png = Vec::new();
+    sprite.as_png(&mut png)?;
+    let output = args
+        .output
+        .clone()
+        .unwrap_or_else(|| format!("{}.png", args.input));
+    fs::write(output, png)?;
+
+    if args.mipmaps {
+        for (level, lod) in sprite.lods.iter().enumerate() {
+            let mut png = Vec::new();
+            lod_sprite(&sprite, lod).as_png(&mut png)?;
+            fs::write(format!("{}.{}.png", args.input, level + 1), png)?;
+        }
+    }
+
+    Ok(())
+}
+
+// MARK: - Helpers
+
+/// Returns a mipmap level as a sprite of its own, sharing the palette of the full-size image
+fn lod_sprite(sprite: &LibdragonSprite, lod: &SpriteLod) -> LibdragonSprite {
+    LibdragonSprite {
+        format: lod.format,
+        width: lod.width,
+        height: lod.height,
+        data: lod.data.clone(),
+        hslices: 1,
+        vslices: 1,
+        tlut: sprite.tlut.clone(),
+        lods: Vec::new(),
+    }
+}
@@ -0,0 +1,102 @@
+use crate::cli::defines::{BinaryFormat, parse_int};
+use anyhow::Result;
+use clap::Args;
+use pigment64::analysis::tlut_search::{DEFAULT_TLUT_ALIGNMENT, find_tluts};
+use pigment64::image::native_image::parse_tlut;
+use pigment64::{Error, NativeImage};
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::PathBuf;
+
+// MARK: - Args
+
+#[derive(Args, Debug)]
+pub struct FindTlutArgs {
+    /// Path to the binary input file containing both the image and its palette
+    input: String,
+
+    /// Format of the color-indexed image
+    #[arg(value_enum, short, long)]
+    format: BinaryFormat,
+
+    /// Offset of the image data within the input file
+    #[arg(long, value_parser = parse_int)]
+    image_offset: u32,
+
+    /// Width of the image
+    #[arg(long)]
+    width: u32,
+
+    /// Height of the image
+    #[arg(long)]
+    height: u32,
+
+    /// Offset of the region to search for palettes. Defaults to the start of the file
+    #[arg(long, value_parser = parse_int, default_value = "0")]
+    search_offset: u32,
+
+    /// Length of the region to search for palettes. Defaults to the rest of the file
+    #[arg(long, value_parser = parse_int)]
+    search_length: Option<u32>,
+
+    /// Alignment of the offsets to try, in bytes
+    #[arg(long, default_value_t = DEFAULT_TLUT_ALIGNMENT)]
+    alignment: usize,
+
+    /// Number of top ranked candidates to report
+    #[arg(long, default_value_t = 10)]
+    top: usize,
+
+    /// Directory to write a preview PNG of the image with each reported candidate
+    #[arg(long)]
+    preview_dir: Option<String>,
+}
+
+// MARK: - Handlers
+
+pub fn handle_find_tlut(args: &FindTlutArgs) -> Result<()> {
+    let data = fs::read(&args.input)?;
+
+    let image_type = args
+        .format
+        .as_native()
+        .ok_or(Error::PaletteConversionError)?;
+    let image_size = image_type.get_size();
+
+    let image_start = args.image_offset as usize;
+    let image_end =
+        image_start + (args.width * args.height * image_size.get_bpp()).div_ceil(8) as usize;
+    let image_data = data
+        .get(image_start..image_end)
+        .ok_or_else(|| anyhow::anyhow!("image data is outside of the input"))?;
+    let image = NativeImage::read(image_data, image_type, args.width, args.height)?;
+
+    let search_start = args.search_offset as usize;
+    let search_end = match args.search_length {
+        Some(length) => search_start + length as usize,
+        None => data.len(),
+    };
+    let region = data
+        .get(search_start..search_end)
+        .ok_or_else(|| anyhow::anyhow!("search region is outside of the input"))?;
+
+    let candidates = find_tluts(region, &image, args.alignment)?;
+
+    for candidate in candidates.iter().take(args.top) {
+        let offset = search_start + candidate.offset;
+        println!(
+            "{offset:#X} {:?}: score {:.3} (palette {:.3}, image {:.3})",
+            candidate.mode, candidate.score, candidate.plausibility, candidate.image_score
+        );
+
+        if let Some(preview_dir) = &args.preview_dir {
+            let palette = parse_tlut(&data[offset..], image_size, candidate.mode)?;
+            let file_name = format!("{offset:X}.{:?}.png", candidate.mode).to_lowercase();
+            let path = PathBuf::from(preview_dir).join(file_name);
+            let mut output_writer = BufWriter::new(File::create(path)?);
+            image.as_png(&mut output_writer, Some(&palette))?;
+        }
+    }
+
+    Ok(())
+}
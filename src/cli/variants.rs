@@ -0,0 +1,134 @@
+use crate::cli::defines::BinaryFormat;
+use anyhow::Result;
+use clap::{Args, Subcommand};
+use pigment64::palette::variants::{extract_variants, render_variants};
+use pigment64::{Error, NativeImage, PNGImage};
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::path::Path;
+
+// MARK: - Args
+
+#[derive(Subcommand, Debug)]
+pub enum VariantsCommands {
+    /// Renders a color-indexed image once per palette
+    Render {
+        #[clap(flatten)]
+        args: RenderArgs,
+    },
+    /// Extracts the indices shared by palette-swapped PNGs, and the palette of each
+    Extract {
+        #[clap(flatten)]
+        args: ExtractArgs,
+    },
+}
+
+#[derive(Args, Debug)]
+pub struct RenderArgs {
+    /// Path to the binary input file
+    input: String,
+
+    /// Palette binary files, one per variant
+    #[arg(short, long, required = true, num_args = 1..)]
+    palettes: Vec<String>,
+
+    /// Width of the binary image
+    #[arg(long)]
+    width: u32,
+
+    /// Height of the binary image. Defaults to as many rows as the input contains
+    #[arg(long)]
+    height: Option<u32>,
+
+    /// Input format
+    #[arg(value_enum, short, long)]
+    format: BinaryFormat,
+
+    /// Prefix of the PNGs, which are named "<PREFIX>.<PALETTE>.png" after the palette file name
+    /// without its extension. Defaults to the input file name
+    #[arg(short, long)]
+    output: Option<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct ExtractArgs {
+    /// Paths to the PNG variants
+    #[arg(required = true)]
+    inputs: Vec<String>,
+
+    /// Output format
+    #[arg(value_enum, short, long)]
+    format: BinaryFormat,
+
+    /// Output file for the shared index data
+    #[arg(short, long)]
+    output: String,
+}
+
+// MARK: - Handlers
+
+pub fn handle_variants(command: &VariantsCommands) -> Result<()> {
+    match command {
+        VariantsCommands::Render { args } => handle_render(args),
+        VariantsCommands::Extract { args } => handle_extract(args),
+    }
+}
+
+fn handle_render(args: &RenderArgs) -> Result<()> {
+    let image_type = args
+        .format
+        .as_native()
+        .ok_or(Error::PaletteConversionError)?;
+
+    let data = fs::read(&args.input)?;
+    let row_bits = args.width * image_type.get_size().get_bpp();
+    let height = args
+        .height
+        .unwrap_or((data.len() as u32 * 8) / row_bits.max(1));
+    let image = NativeImage::read(data.as_slice(), image_type, args.width, height)?;
+
+    let tluts = args
+        .palettes
+        .iter()
+        .map(fs::read)
+        .collect::<Result<Vec<_>, _>>()?;
+    let prefix = args.output.clone().unwrap_or_else(|| args.input.clone());
+
+    for (palette, png) in args.palettes.iter().zip(render_variants(&image, &tluts)?) {
+        let name = Path::new(palette)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_else(|| palette.clone());
+        fs::write(format!("{prefix}.{name}.png"), png)?;
+    }
+
+    Ok(())
+}
+
+fn handle_extract(args: &ExtractArgs) -> Result<()> {
+    let image_type = args
+        .format
+        .as_native()
+        .ok_or(Error::PaletteConversionError)?;
+
+    let images = args
+        .inputs
+        .iter()
+        .map(|input| Ok(PNGImage::read(BufReader::new(File::open(input)?))?))
+        .collect::<Result<Vec<_>>>()?;
+
+    let variants = extract_variants(&images, image_type)?;
+    fs::write(&args.output, &variants.data)?;
+
+    for (variant, input) in args.inputs.iter().enumerate() {
+        fs::write(format!("{input}.tlut.bin"), variants.tlut(variant))?;
+    }
+
+    println!(
+        "Extracted {} variants sharing {} palette entries",
+        args.inputs.len(),
+        variants.palettes[0].len()
+    );
+
+    Ok(())
+}
@@ -0,0 +1,135 @@
+use crate::cli::defines::{BinaryFormat, parse_int};
+use anyhow::{Result, bail};
+use clap::Args;
+use pigment64::verify::{
+    Reference, VerifyResult, verify_image, verify_palette, write_highlight_png,
+};
+use pigment64::{NativeImage, PNGImage};
+use std::fs::{self, File};
+use std::io::BufWriter;
+
+// MARK: - Args
+
+#[derive(Args, Debug)]
+pub struct VerifyArgs {
+    /// Path to the PNG input file
+    input: String,
+
+    /// Format the PNG is encoded as
+    #[arg(value_enum, short, long)]
+    format: BinaryFormat,
+
+    /// Path to the binary the PNG was extracted from
+    #[arg(long, required_unless_present = "crc32", conflicts_with = "crc32")]
+    reference: Option<String>,
+
+    /// Expected CRC-32 of the encoded data, used instead of a reference binary
+    #[arg(long, value_parser = parse_int)]
+    crc32: Option<u32>,
+
+    /// Flip the image on the x axis before encoding
+    #[arg(long)]
+    flip_x: bool,
+
+    /// Flip the image on the y axis before encoding
+    #[arg(long)]
+    flip_y: bool,
+
+    /// Swap words in odd rows after encoding
+    #[arg(long)]
+    word_swap: bool,
+
+    /// Write a PNG highlighting the differing texels to this path on mismatch
+    #[arg(long)]
+    diff: Option<String>,
+}
+
+// MARK: - Handlers
+
+pub fn handle_verify(args: &VerifyArgs) -> Result<()> {
+    let png_bytes = fs::read(&args.input)?;
+    let reference_bytes = args.reference.as_ref().map(fs::read).transpose()?;
+    let reference = match (&reference_bytes, args.crc32) {
+        (Some(bytes), _) => Reference::Binary(bytes),
+        (None, Some(crc32)) => Reference::Crc32(crc32),
+        (None, None) => unreachable!("clap requires a reference or a checksum"),
+    };
+
+    let mut png = PNGImage::read(png_bytes.as_slice())?;
+    if args.flip_x || args.flip_y {
+        png = png.flip(args.flip_x, args.flip_y);
+    }
+
+    let image_type = args.format.as_native();
+    let report = match image_type {
+        Some(image_type) => verify_image(&png, image_type, args.word_swap, reference)?,
+        None => verify_palette(&png_bytes, reference)?,
+    };
+
+    match report.result {
+        VerifyResult::Match => {
+            println!("{}: OK", args.input);
+            return Ok(());
+        }
+        VerifyResult::SizeMismatch { expected, actual } => {
+            println!(
+                "{}: encoded to {actual} bytes, expected {expected}",
+                args.input
+            );
+        }
+        VerifyResult::ChecksumMismatch { expected, actual } => {
+            println!(
+                "{}: CRC-32 is {actual:08X}, expected {expected:08X}",
+                args.input
+            );
+        }
+        VerifyResult::ValueMismatch(mismatch) => {
+            let width = match args.format.get_size().map(|size| size.get_bpp()) {
+                Some(bpp) => bpp.div_ceil(4) as usize,
+                None => 4,
+            };
+            let location = match mismatch.position {
+                Some((x, y)) => format!("texel ({x}, {y})"),
+                None => format!("palette entry {}", mismatch.index),
+            };
+            println!(
+                "{}: {} differs, expected 0x{:0width$X} but encoded 0x{:0width$X} ({} differing in total)",
+                args.input, location, mismatch.expected, mismatch.actual, mismatch.count
+            );
+        }
+    }
+
+    // A diff image can only be drawn when both sides hold the same texels
+    let same_size = reference_bytes
+        .as_ref()
+        .filter(|expected| expected.len() == report.encoded.len());
+
+    if let (Some(path), Some(image_type), Some(expected)) = (&args.diff, image_type, same_size) {
+        let mut expected = NativeImage {
+            format: image_type,
+            width: png.width(),
+            height: png.height(),
+            data: expected.clone(),
+        };
+        let mut actual = NativeImage {
+            data: report.encoded.clone(),
+            ..expected
+        };
+        if args.word_swap {
+            expected.swap_word_rows();
+            actual.swap_word_rows();
+        }
+
+        let mut writer = BufWriter::new(File::create(path)?);
+        write_highlight_png(
+            &mut writer,
+            image_type,
+            png.width(),
+            png.height(),
+            &expected.data,
+            &actual.data,
+        )?;
+    }
+
+    bail!("{} does not match its reference", args.input)
+}
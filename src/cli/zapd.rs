@@ -0,0 +1,87 @@
+use anyhow::Result;
+use clap::{Args, Subcommand};
+use pigment64::PNGImage;
+use pigment64::assets::zapd::parse_zapd_xml;
+use std::fs::{self, File};
+use std::io::{BufReader, Write};
+use std::path::PathBuf;
+
+// MARK: - Args
+
+#[derive(Subcommand, Debug)]
+pub enum ZapdCommands {
+    /// Extracts every texture described by a ZAPD XML file to PNGs
+    Extract {
+        #[clap(flatten)]
+        args: ZapdArgs,
+    },
+    /// Rebuilds the binary files described by a ZAPD XML file from their PNGs
+    Build {
+        #[clap(flatten)]
+        args: ZapdArgs,
+
+        /// Directory containing the PNGs. Defaults to the output directory
+        #[arg(long)]
+        png_dir: Option<String>,
+    },
+}
+
+#[derive(Args, Debug)]
+pub struct ZapdArgs {
+    /// Path to the ZAPD XML file
+    xml: String,
+
+    /// Directory containing the binary files named by the XML's <File> elements
+    #[arg(short, long)]
+    input_dir: String,
+
+    /// Directory to write the PNGs or rebuilt binary files to
+    #[arg(short, long)]
+    output_dir: String,
+}
+
+// MARK: - Handlers
+
+pub fn handle_zapd(command: &ZapdCommands) -> Result<()> {
+    match command {
+        ZapdCommands::Extract { args } => handle_extract(args),
+        ZapdCommands::Build { args, png_dir } => {
+            handle_build(args, png_dir.as_ref().unwrap_or(&args.output_dir))
+        }
+    }
+}
+
+fn handle_extract(args: &ZapdArgs) -> Result<()> {
+    let files = parse_zapd_xml(&fs::read_to_string(&args.xml)?)?;
+    let output_dir = PathBuf::from(&args.output_dir);
+    fs::create_dir_all(&output_dir)?;
+
+    for file in &files {
+        let binary = fs::read(PathBuf::from(&args.input_dir).join(&file.name))?;
+
+        for (file_name, png) in file.extract(&binary)? {
+            File::create(output_dir.join(file_name))?.write_all(&png)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn handle_build(args: &ZapdArgs, png_dir: &str) -> Result<()> {
+    let files = parse_zapd_xml(&fs::read_to_string(&args.xml)?)?;
+    let output_dir = PathBuf::from(&args.output_dir);
+    fs::create_dir_all(&output_dir)?;
+
+    for file in &files {
+        let mut binary = fs::read(PathBuf::from(&args.input_dir).join(&file.name))?;
+
+        file.reassemble(&mut binary, |texture| {
+            let png_file = File::open(PathBuf::from(png_dir).join(texture.file_name()))?;
+            PNGImage::read(BufReader::new(png_file))
+        })?;
+
+        fs::write(output_dir.join(&file.name), binary)?;
+    }
+
+    Ok(())
+}
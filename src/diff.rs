@@ -0,0 +1,169 @@
+//! Compares two textures texel by texel.
+
+use crate::analysis::preview_rgba;
+use crate::color::Color;
+use crate::image::canvas::Canvas;
+use crate::verify::texel_value;
+use crate::{Error, NativeImage};
+use std::io::Write;
+
+/// Spacing between the panels of a diff image.
+const PANEL_GAP: u32 = 2;
+
+/// The differences between two textures of the same dimensions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextureDiff {
+    pub width: u32,
+    pub height: u32,
+    /// Whether each texel differs, in row-major order.
+    pub differing: Vec<bool>,
+    /// Largest error of any RGBA channel of each texel, in row-major order.
+    pub errors: Vec<u8>,
+    /// Number of differing texels.
+    pub differing_texels: usize,
+    /// Largest error of any RGBA channel of any texel.
+    pub max_channel_error: u8,
+    /// Mean squared error over every RGBA channel.
+    pub mse: f64,
+    /// Peak signal-to-noise ratio in decibels, infinite for identical textures.
+    pub psnr: f64,
+    expected_rgba: Vec<u8>,
+    actual_rgba: Vec<u8>,
+}
+
+/// Compares two textures of the same dimensions.
+///
+/// Both textures are decoded with [`NativeImage::decode`] to measure channel errors, using their
+/// palette if they are color-indexed. When both share a format, texels are compared by their
+/// native values, so that a different palette index is reported even if it points to the same
+/// color. Color-indexed textures without a palette are compared by index.
+pub fn diff_images(
+    expected: &NativeImage,
+    expected_tlut: Option<&[u8]>,
+    actual: &NativeImage,
+    actual_tlut: Option<&[u8]>,
+) -> Result<TextureDiff, Error> {
+    if (expected.width, expected.height) != (actual.width, actual.height) {
+        return Err(Error::DimensionMismatch {
+            expected: (expected.width, expected.height),
+            actual: (actual.width, actual.height),
+        });
+    }
+
+    let width = expected.width;
+    let height = expected.height;
+    let expected_rgba = decode_rgba(expected, expected_tlut)?;
+    let actual_rgba = decode_rgba(actual, actual_tlut)?;
+
+    let texel_count = (width * height) as usize;
+    let bpp = expected.format.get_size().get_bpp();
+    let same_format = expected.format == actual.format;
+
+    let mut differing = Vec::with_capacity(texel_count);
+    let mut errors = Vec::with_capacity(texel_count);
+    let mut squared_error = 0u64;
+
+    for (index, (a, b)) in expected_rgba
+        .chunks_exact(4)
+        .zip(actual_rgba.chunks_exact(4))
+        .enumerate()
+    {
+        let channel_errors = a.iter().zip(b).map(|(a, b)| a.abs_diff(*b));
+        squared_error += channel_errors
+            .clone()
+            .map(|error| (error as u64).pow(2))
+            .sum::<u64>();
+        errors.push(channel_errors.max().unwrap_or(0));
+
+        differing.push(if same_format {
+            texel_value(&expected.data, index, bpp) != texel_value(&actual.data, index, bpp)
+        } else {
+            a != b
+        });
+    }
+
+    let mse = squared_error as f64 / (texel_count * 4).max(1) as f64;
+
+    Ok(TextureDiff {
+        width,
+        height,
+        differing_texels: differing.iter().filter(|d| **d).count(),
+        max_channel_error: errors.iter().copied().max().unwrap_or(0),
+        differing,
+        errors,
+        mse,
+        psnr: psnr(mse),
+        expected_rgba,
+        actual_rgba,
+    })
+}
+
+impl TextureDiff {
+    /// Returns the coordinates of the first differing texel, if any.
+    pub fn first_difference(&self) -> Option<(u32, u32)> {
+        let index = self.differing.iter().position(|d| *d)? as u32;
+        Some((index % self.width, index / self.width))
+    }
+
+    /// Writes a PNG showing the expected texture, the actual texture and a map of the
+    /// differences side by side.
+    ///
+    /// In the map, matching texels are shown faded and differing ones from yellow to red with
+    /// increasing channel error.
+    pub fn write_png<W: Write>(&self, writer: &mut W) -> Result<(), Error> {
+        let mut canvas = Canvas::new(
+            self.width * 3 + PANEL_GAP * 2,
+            self.height,
+            Color::TRANSPARENT,
+        );
+
+        canvas.blit(0, 0, self.width, self.height, &self.expected_rgba);
+        canvas.blit(
+            self.width + PANEL_GAP,
+            0,
+            self.width,
+            self.height,
+            &self.actual_rgba,
+        );
+
+        let map_x = (self.width + PANEL_GAP) * 2;
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let index = (y * self.width + x) as usize;
+
+                let color = if self.differing[index] {
+                    let error = self.errors[index].max(1);
+                    Color::RGB(0xFF, 0xFF - error, 0x00)
+                } else {
+                    let p = &self.expected_rgba[index * 4..index * 4 + 4];
+                    let fade = |c: u8| (c / 4) + 0x60;
+                    Color::RGB(fade(p[0]), fade(p[1]), fade(p[2]))
+                };
+                canvas.set_pixel(map_x + x, y, color);
+            }
+        }
+
+        canvas.write_png(writer)
+    }
+}
+
+/// Returns the peak signal-to-noise ratio in decibels of 8-bit channels with the given mean
+/// squared error.
+pub(crate) fn psnr(mse: f64) -> f64 {
+    if mse == 0.0 {
+        f64::INFINITY
+    } else {
+        10.0 * (255.0f64.powi(2) / mse).log10()
+    }
+}
+
+fn decode_rgba(image: &NativeImage, tlut: Option<&[u8]>) -> Result<Vec<u8>, Error> {
+    match tlut {
+        Some(tlut) => {
+            let mut rgba = Vec::with_capacity((image.width * image.height * 4) as usize);
+            image.decode(&mut rgba, Some(tlut))?;
+            Ok(rgba)
+        }
+        None => preview_rgba(&image.data, image.format, image.width, image.height),
+    }
+}
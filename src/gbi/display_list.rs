@@ -0,0 +1,335 @@
+use crate::gbi::{
+    G_LOADBLOCK, G_LOADTILE, G_LOADTLUT, G_MDSFT_TEXTLUT, G_SETTILE, G_SETTILESIZE, G_SETTIMG,
+    G_TX_LOADTILE, Microcode, SegmentTable,
+};
+use crate::{Error, ImageFormat, ImageSize, ImageType, NativeImage, TextureLUT};
+use byteorder::{BigEndian, ByteOrder};
+use std::collections::HashMap;
+
+/// Maximum nesting of `G_DL` calls, matching the F3DEX2 display list stack.
+const MAX_DL_DEPTH: usize = 18;
+/// Upper bound on the commands executed, to stop on display lists that never end.
+const MAX_COMMANDS: usize = 0x10000;
+/// TMEM address, in 64-bit words, of the first palette entry.
+const TLUT_TMEM_BASE: u32 = 0x100;
+
+/// A texture referenced by a display list, along with its palette if it is color-indexed.
+pub struct DisplayListTexture {
+    /// Segmented address of the texel data.
+    pub address: u32,
+    pub image: NativeImage,
+    /// TLUT mode set in the other mode when the texture was drawn.
+    pub tlut_mode: TextureLUT,
+    /// Segmented address of the first palette entry used by the texture.
+    pub tlut_address: Option<u32>,
+    /// Raw palette entries used by the texture, as stored in memory, up to the first entry that
+    /// was not loaded.
+    pub tlut: Option<Vec<u8>>,
+}
+
+/// Result of walking a display list.
+#[derive(Default)]
+pub struct DisplayListTextures {
+    pub textures: Vec<DisplayListTexture>,
+    /// Segmented addresses which were referenced but could not be read with the segment table.
+    pub unresolved: Vec<u32>,
+}
+
+#[derive(Copy, Clone, Default)]
+struct TextureImage {
+    address: u32,
+    size: u8,
+    width: u32,
+}
+
+#[derive(Copy, Clone, Default)]
+struct Tile {
+    format: u8,
+    size: u8,
+    tmem: u32,
+    palette: u32,
+}
+
+/// Where a block of TMEM was loaded from.
+#[derive(Copy, Clone)]
+struct TmemSource {
+    address: u32,
+    /// Row stride of the source image in bytes, or `None` if it was loaded as a single block.
+    stride: Option<u32>,
+}
+
+struct Walker<'a> {
+    data: &'a [u8],
+    segments: &'a SegmentTable,
+    microcode: Microcode,
+    timg: TextureImage,
+    tiles: [Tile; 8],
+    tmem: HashMap<u32, TmemSource>,
+    /// Source address of each of the 256 palette entries in TMEM.
+    palette: [Option<u32>; 256],
+    tlut_mode: TextureLUT,
+    result: DisplayListTextures,
+}
+
+/// Walks the display list at `address` and collects every texture it loads and draws with.
+///
+/// Textures are emitted when a render tile's size is set with `G_SETTILESIZE`, using the format
+/// of that tile and the data previously loaded into its TMEM address with `G_LOADBLOCK` or
+/// `G_LOADTILE`. Palettes are tracked through `G_LOADTLUT` and the tile's palette index, and the
+/// TLUT mode through `G_SETOTHERMODE_H`. Nested display lists are followed, and a texture used
+/// several times is only reported once.
+pub fn extract_textures(
+    data: &[u8],
+    segments: &SegmentTable,
+    microcode: Microcode,
+    address: u32,
+) -> Result<DisplayListTextures, Error> {
+    let mut walker = Walker {
+        data,
+        segments,
+        microcode,
+        timg: TextureImage::default(),
+        tiles: [Tile::default(); 8],
+        tmem: HashMap::new(),
+        palette: [None; 256],
+        tlut_mode: TextureLUT::None,
+        result: DisplayListTextures::default(),
+    };
+
+    walker.walk(address)?;
+    Ok(walker.result)
+}
+
+impl Walker<'_> {
+    fn walk(&mut self, address: u32) -> Result<(), Error> {
+        let mut stack = vec![address];
+        let mut executed = 0;
+
+        while let Some(pc) = stack.last_mut() {
+            let address = *pc;
+            let Some(next) = address.checked_add(8) else {
+                self.unresolved(address);
+                stack.pop();
+                continue;
+            };
+            *pc = next;
+
+            executed += 1;
+            if executed > MAX_COMMANDS {
+                break;
+            }
+
+            let Some(command) = self
+                .segments
+                .resolve(address)
+                .and_then(|offset| self.data.get(offset..offset + 8))
+            else {
+                self.unresolved(address);
+                stack.pop();
+                continue;
+            };
+
+            let w0 = BigEndian::read_u32(&command[0..4]);
+            let w1 = BigEndian::read_u32(&command[4..8]);
+            let opcode = (w0 >> 24) as u8;
+
+            if opcode == self.microcode.g_enddl() {
+                stack.pop();
+            } else if opcode == self.microcode.g_dl() {
+                let branch = (w0 >> 16) & 0xFF == 1;
+                if branch {
+                    stack.pop();
+                }
+                if stack.len() < MAX_DL_DEPTH {
+                    stack.push(w1);
+                }
+            } else if opcode == self.microcode.g_setothermode_h() {
+                self.set_other_mode_h(w0, w1);
+            } else {
+                match opcode {
+                    G_SETTIMG => {
+                        self.timg = TextureImage {
+                            address: w1,
+                            size: ((w0 >> 19) & 0x03) as u8,
+                            width: (w0 & 0xFFF) + 1,
+                        };
+                    }
+                    G_SETTILE => {
+                        let tile = ((w1 >> 24) & 0x07) as usize;
+                        self.tiles[tile] = Tile {
+                            format: ((w0 >> 21) & 0x07) as u8,
+                            size: ((w0 >> 19) & 0x03) as u8,
+                            tmem: w0 & 0x1FF,
+                            palette: (w1 >> 20) & 0x0F,
+                        };
+                    }
+                    G_LOADBLOCK => {
+                        let tile = self.tiles[((w1 >> 24) & 0x07) as usize];
+                        self.tmem.insert(
+                            tile.tmem,
+                            TmemSource {
+                                address: self.timg.address,
+                                stride: None,
+                            },
+                        );
+                    }
+                    G_LOADTILE => {
+                        let tile = self.tiles[((w1 >> 24) & 0x07) as usize];
+                        let uls = (w0 >> 14) & 0x3FF;
+                        let ult = (w0 >> 2) & 0x3FF;
+                        let bits = bits_per_texel(self.timg.size);
+                        let offset = ((ult * self.timg.width + uls) * bits) / 8;
+
+                        match self.timg.address.checked_add(offset) {
+                            Some(address) => {
+                                self.tmem.insert(
+                                    tile.tmem,
+                                    TmemSource {
+                                        address,
+                                        stride: Some((self.timg.width * bits) / 8),
+                                    },
+                                );
+                            }
+                            None => self.unresolved(self.timg.address),
+                        }
+                    }
+                    G_LOADTLUT => {
+                        let tile = self.tiles[((w1 >> 24) & 0x07) as usize];
+                        let count = ((w1 >> 14) & 0x3FF) + 1;
+                        // TMEM holds one palette entry per 64-bit word
+                        let first = tile.tmem.saturating_sub(TLUT_TMEM_BASE);
+
+                        for i in 0..count {
+                            if let Some(entry) = self.palette.get_mut((first + i) as usize) {
+                                *entry = self.timg.address.checked_add(i * 2);
+                            }
+                        }
+                    }
+                    G_SETTILESIZE => {
+                        let tile = ((w1 >> 24) & 0x07) as u8;
+                        if tile != G_TX_LOADTILE {
+                            let uls = (w0 >> 12) & 0xFFF;
+                            let ult = w0 & 0xFFF;
+                            let lrs = (w1 >> 12) & 0xFFF;
+                            let lrt = w1 & 0xFFF;
+                            let width = (lrs.saturating_sub(uls) >> 2) + 1;
+                            let height = (lrt.saturating_sub(ult) >> 2) + 1;
+
+                            self.emit_texture(self.tiles[tile as usize], width, height);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn set_other_mode_h(&mut self, w0: u32, w1: u32) {
+        let (shift, length) = match self.microcode {
+            Microcode::F3dex => ((w0 >> 8) & 0xFF, w0 & 0xFF),
+            Microcode::F3dex2 => {
+                let length = (w0 & 0xFF) + 1;
+                (32u32.saturating_sub(((w0 >> 8) & 0xFF) + length), length)
+            }
+        };
+
+        if shift <= G_MDSFT_TEXTLUT && shift + length >= G_MDSFT_TEXTLUT + 2 {
+            self.tlut_mode = match (w1 >> G_MDSFT_TEXTLUT) & 0x03 {
+                2 => TextureLUT::Rgba16,
+                3 => TextureLUT::Ia16,
+                _ => TextureLUT::None,
+            };
+        }
+    }
+
+    fn emit_texture(&mut self, tile: Tile, width: u32, height: u32) {
+        let Some(source) = self.tmem.get(&tile.tmem).copied() else {
+            return;
+        };
+
+        let format = ImageFormat::try_from(tile.format)
+            .ok()
+            .zip(ImageSize::try_from(tile.size).ok())
+            .and_then(|(format, size)| ImageType::from_format_and_size(format, size));
+        let Some(format) = format else {
+            return;
+        };
+
+        let (tlut_address, tlut) = if format.get_format() == ImageFormat::Ci {
+            let (first, count) = match format {
+                ImageType::Ci4 => (tile.palette as usize * 16, 16),
+                _ => (0, 256),
+            };
+            // Palettes are often only partly loaded, so keep the entries up to the first missing one
+            let entries = &self.palette[first..first + count];
+            let tlut = entries
+                .iter()
+                .map_while(|entry| entry.and_then(|address| self.read(address, 2)))
+                .collect::<Vec<&[u8]>>()
+                .concat();
+            (entries[0], Some(tlut).filter(|tlut| !tlut.is_empty()))
+        } else {
+            (None, None)
+        };
+
+        let already_seen = self.result.textures.iter().any(|texture| {
+            texture.address == source.address
+                && texture.image.format == format
+                && texture.tlut_address == tlut_address
+        });
+        if already_seen {
+            return;
+        }
+
+        let bits = format.get_size().get_bpp();
+        let row_bytes = (width * bits).div_ceil(8);
+        let data = match source.stride {
+            Some(stride) if stride != row_bytes => (0..height)
+                .map(|row| {
+                    let address = source.address.checked_add(row * stride)?;
+                    self.read(address, row_bytes)
+                })
+                .collect::<Option<Vec<&[u8]>>>()
+                .map(|rows| rows.concat()),
+            _ => self
+                .read(source.address, row_bytes * height)
+                .map(|data| data.to_vec()),
+        };
+
+        let Some(data) = data else {
+            self.unresolved(source.address);
+            return;
+        };
+
+        self.result.textures.push(DisplayListTexture {
+            address: source.address,
+            image: NativeImage {
+                format,
+                width,
+                height,
+                data,
+            },
+            tlut_mode: self.tlut_mode,
+            tlut_address,
+            tlut,
+        });
+    }
+
+    fn read(&self, address: u32, length: u32) -> Option<&[u8]> {
+        let offset = self.segments.resolve(address)?;
+        self.data.get(offset..offset + length as usize)
+    }
+
+    fn unresolved(&mut self, address: u32) {
+        if !self.result.unresolved.contains(&address) {
+            self.result.unresolved.push(address);
+        }
+    }
+}
+
+/// Returns the bits per texel of a `G_IM_SIZ_*` value.
+fn bits_per_texel(size: u8) -> u32 {
+    4 << size
+}
@@ -0,0 +1,215 @@
+use crate::analysis::quality::encode_format;
+use crate::color::Color;
+use crate::gbi::tile::{TileAxis, TileDescriptor};
+use crate::gbi::{G_TX_LOADTILE, G_TX_RENDERTILE, tmem_capacity, tmem_layout};
+use crate::image::canvas::{pad_rows, row_width};
+use crate::image::quantize::nearest_color;
+use crate::{Error, ImageFormat, ImageSize, ImageType, PNGImage};
+
+/// Most levels a chain can have, as each needs a tile and the load tile is reserved.
+pub const MAX_MIPMAP_LEVELS: usize = G_TX_LOADTILE as usize;
+
+/// How each level is computed from the previous one.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum MipmapFilter {
+    /// Averages each 2x2 block of texels.
+    #[default]
+    Box,
+    /// Keeps the top-left texel of each 2x2 block.
+    Nearest,
+}
+
+/// One level of a mipmap chain.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MipmapLevel {
+    pub width: u32,
+    pub height: u32,
+    /// Native texel data of the level, with the rows of 4-bit levels padded to a whole byte.
+    pub data: Vec<u8>,
+    /// Tile descriptor sampling this level from TMEM.
+    pub tile: TileDescriptor,
+}
+
+/// A mipmap chain encoded and laid out for `G_TL_LOD` texture filtering.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mipmaps {
+    pub format: ImageType,
+    pub levels: Vec<MipmapLevel>,
+    /// Every level as laid out in TMEM: one after the other, each row padded to 64 bits and odd
+    /// rows word swapped. This can be loaded at TMEM address 0 with a single `G_LOADBLOCK`
+    /// without a `dxt` value.
+    pub tmem: Vec<u8>,
+    /// RGBA16 palette shared by every level of a color-indexed chain.
+    pub tlut: Option<Vec<u8>>,
+}
+
+/// Generates a mipmap chain from `png` and lays it out in TMEM.
+///
+/// Levels halve the dimensions of the previous one until either reaches 1 or `max_levels`
+/// (itself capped to [`MAX_MIPMAP_LEVELS`]) levels were generated. Level `n` is sampled by tile
+/// `G_TX_RENDERTILE + n` with its coordinates shifted right by `n`. The chain must fit in the
+/// TMEM available to `format`, and color-indexed chains share a palette built from the first
+/// level.
+pub fn build_mipmaps(
+    png: &PNGImage,
+    format: ImageType,
+    filter: MipmapFilter,
+    max_levels: usize,
+) -> Result<Mipmaps, Error> {
+    let (width, height) = (png.width(), png.height());
+    if !width.is_power_of_two() || !height.is_power_of_two() {
+        return Err(Error::InvalidMipmapDimensions(width, height));
+    }
+    if matches!(format.get_size(), ImageSize::Bits1 | ImageSize::Bits32) {
+        return Err(Error::UnsupportedTmemFormat(format));
+    }
+
+    let (encoded, tlut) =
+        encode_levels(png, format, filter, max_levels.clamp(1, MAX_MIPMAP_LEVELS))?;
+
+    let mut levels = Vec::new();
+    let mut tmem = Vec::new();
+
+    for (level, (width, height, data)) in encoded.into_iter().enumerate() {
+        let (rows, line) = tmem_layout(&data, format, width, height);
+        let address = tmem.len() as u32 / 8;
+        tmem.extend_from_slice(&rows);
+
+        let axis = |size: u32| TileAxis {
+            mirror: false,
+            clamp: false,
+            mask: size.trailing_zeros() as u8,
+            shift: level as u8,
+        };
+
+        levels.push(MipmapLevel {
+            width,
+            height,
+            data,
+            tile: TileDescriptor {
+                tile: G_TX_RENDERTILE + level as u8,
+                format,
+                line,
+                tmem: address,
+                palette: 0,
+                s: axis(width),
+                t: axis(height),
+                uls: 0,
+                ult: 0,
+                lrs: width - 1,
+                lrt: height - 1,
+            },
+        });
+    }
+
+    let available = tmem_capacity(format);
+    if tmem.len() as u32 > available {
+        return Err(Error::TmemOverflow {
+            required: tmem.len() as u32,
+            available,
+        });
+    }
+
+    Ok(Mipmaps {
+        format,
+        levels,
+        tmem,
+        tlut,
+    })
+}
+
+/// Width, height and texels of each level of a chain, and the palette they share.
+pub(crate) type EncodedLevels = (Vec<(u32, u32, Vec<u8>)>, Option<Vec<u8>>);
+
+/// Encodes `png` followed by levels halving the dimensions of the previous one, until either
+/// reaches 1 or `max_levels` levels were encoded, returning the size and texels of each level.
+///
+/// Rows of 4-bit levels are padded to a whole byte. Color-indexed levels share an RGBA16 palette
+/// built from the first level, which is returned along with them. Unlike [`build_mipmaps`], neither the dimensions nor the size of the chain
+/// are checked.
+pub(crate) fn encode_levels(
+    png: &PNGImage,
+    format: ImageType,
+    filter: MipmapFilter,
+    max_levels: usize,
+) -> Result<EncodedLevels, Error> {
+    let mut rgba_levels = vec![(png.width(), png.height(), png.as_rgba8())];
+    while rgba_levels.len() < max_levels {
+        let (width, height, rgba) = &rgba_levels[rgba_levels.len() - 1];
+        if *width == 1 || *height == 1 {
+            break;
+        }
+        rgba_levels.push(downscale(rgba, *width, *height, filter));
+    }
+
+    // Color-indexed levels are all mapped to the palette of the first one
+    let palette = if format.get_format() == ImageFormat::Ci {
+        let entries = format.get_size().get_tlut_size().unwrap_or_default();
+        png.quantize(entries).palette()
+    } else {
+        None
+    };
+
+    let levels = rgba_levels
+        .into_iter()
+        .map(|(width, height, rgba)| {
+            // Native 4-bit encoders pack texels across rows, so odd widths get a padding column
+            let padded = row_width(format, width);
+            let data = match &palette {
+                Some(palette) => {
+                    let indices: Vec<u8> = rgba
+                        .chunks_exact(4)
+                        .map(|p| nearest_color(palette, Color::RGBA(p[0], p[1], p[2], p[3])) as u8)
+                        .collect();
+                    let indices = pad_rows(&indices, width, 1, padded);
+                    let mut data = Vec::new();
+                    PNGImage::from_indexed(padded, height, indices, palette)
+                        .as_native(&mut data, format)?;
+                    data
+                }
+                None => {
+                    let pixels = pad_rows(&rgba, width, 4, padded);
+                    encode_format(&PNGImage::from_rgba8(padded, height, pixels), format)?.0
+                }
+            };
+            Ok((width, height, data))
+        })
+        .collect::<Result<_, Error>>()?;
+
+    let tlut = palette.map(|palette| palette.iter().flat_map(|c| c.rgba16()).collect());
+    Ok((levels, tlut))
+}
+
+/// Halves the dimensions of RGBA8 pixels, down to a minimum of 1.
+fn downscale(rgba: &[u8], width: u32, height: u32, filter: MipmapFilter) -> (u32, u32, Vec<u8>) {
+    let new_width = (width / 2).max(1);
+    let new_height = (height / 2).max(1);
+    let pixel = |x: u32, y: u32| {
+        let index = ((y.min(height - 1) * width + x.min(width - 1)) * 4) as usize;
+        &rgba[index..index + 4]
+    };
+
+    let mut output = Vec::with_capacity((new_width * new_height * 4) as usize);
+    for y in 0..new_height {
+        for x in 0..new_width {
+            let (sx, sy) = (x * 2, y * 2);
+            match filter {
+                MipmapFilter::Nearest => output.extend_from_slice(pixel(sx, sy)),
+                MipmapFilter::Box => {
+                    let block = [
+                        pixel(sx, sy),
+                        pixel(sx + 1, sy),
+                        pixel(sx, sy + 1),
+                        pixel(sx + 1, sy + 1),
+                    ];
+                    for channel in 0..4 {
+                        let sum: u32 = block.iter().map(|p| p[channel] as u32).sum();
+                        output.push(((sum + 2) / 4) as u8);
+                    }
+                }
+            }
+        }
+    }
+
+    (new_width, new_height, output)
+}
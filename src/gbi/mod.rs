@@ -0,0 +1,135 @@
+//! Support for the Graphics Binary Interface (GBI) used by the N64 RSP microcodes.
+
+pub mod display_list;
+pub mod mipmap;
+pub mod s2dex;
+pub mod split;
+pub mod sprite;
+pub mod tile;
+
+use crate::{ImageFormat, ImageType};
+
+/// Identifies the microcode family a display list was built for, as opcodes differ between them.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Microcode {
+    /// Fast3D and F3DEX, including their LX and NoN variants.
+    F3dex,
+    /// F3DEX2 and its variants.
+    F3dex2,
+}
+
+impl Microcode {
+    pub(crate) fn g_dl(&self) -> u8 {
+        match self {
+            Microcode::F3dex => 0x06,
+            Microcode::F3dex2 => 0xDE,
+        }
+    }
+
+    pub(crate) fn g_enddl(&self) -> u8 {
+        match self {
+            Microcode::F3dex => 0xB8,
+            Microcode::F3dex2 => 0xDF,
+        }
+    }
+
+    pub(crate) fn g_setothermode_h(&self) -> u8 {
+        match self {
+            Microcode::F3dex => 0xBA,
+            Microcode::F3dex2 => 0xE3,
+        }
+    }
+}
+
+// RDP commands, shared by every microcode.
+pub(crate) const G_LOADTLUT: u8 = 0xF0;
+pub(crate) const G_SETTILESIZE: u8 = 0xF2;
+pub(crate) const G_LOADBLOCK: u8 = 0xF3;
+pub(crate) const G_LOADTILE: u8 = 0xF4;
+pub(crate) const G_SETTILE: u8 = 0xF5;
+pub(crate) const G_SETTIMG: u8 = 0xFD;
+
+/// The tile descriptor reserved for loading texture data into TMEM.
+pub const G_TX_LOADTILE: u8 = 7;
+/// The tile descriptor used for rendering by most display lists.
+pub const G_TX_RENDERTILE: u8 = 0;
+/// Shift of the texture LUT mode within the high word of the RDP other mode.
+pub(crate) const G_MDSFT_TEXTLUT: u32 = 14;
+
+/// Size of the RDP texture memory in bytes.
+pub const TMEM_SIZE: u32 = 4096;
+
+/// Returns the number of TMEM bytes available for texels of the given format.
+///
+/// Color-indexed textures only get the lower half, as their palette is loaded into the upper one.
+pub fn tmem_capacity(format: ImageType) -> u32 {
+    match format.get_format() {
+        ImageFormat::Ci => TMEM_SIZE / 2,
+        _ => TMEM_SIZE,
+    }
+}
+
+/// Returns the number of TMEM bytes a texture occupies, with every row padded to 64 bits.
+pub fn tmem_footprint(format: ImageType, width: u32, height: u32) -> u32 {
+    let row_bytes = (width * format.get_size().get_bpp()).div_ceil(8);
+    row_bytes.next_multiple_of(8) * height
+}
+
+/// Lays out native texel data the way it is stored in TMEM, with every row padded to 64 bits and
+/// the 32-bit words of odd rows swapped.
+///
+/// Returns the data along with the size of a row in 64-bit words.
+pub(crate) fn tmem_layout(
+    data: &[u8],
+    format: ImageType,
+    width: u32,
+    height: u32,
+) -> (Vec<u8>, u32) {
+    let row_bytes = (width * format.get_size().get_bpp()).div_ceil(8) as usize;
+    let line = row_bytes.div_ceil(8);
+
+    let mut output = Vec::with_capacity(line * 8 * height as usize);
+    for (y, row) in data.chunks(row_bytes).take(height as usize).enumerate() {
+        let mut padded = row.to_vec();
+        padded.resize(line * 8, 0);
+        if y % 2 == 1 {
+            for words in padded.chunks_exact_mut(8) {
+                words.rotate_left(4);
+            }
+        }
+        output.extend_from_slice(&padded);
+    }
+
+    (output, line as u32)
+}
+
+/// Maps the 16 RSP segments to offsets within a buffer holding their data.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct SegmentTable {
+    segments: [Option<usize>; 16],
+}
+
+impl Default for SegmentTable {
+    /// Segment 0 maps to the start of the buffer, all other segments are unmapped.
+    fn default() -> Self {
+        let mut segments = [None; 16];
+        segments[0] = Some(0);
+        Self { segments }
+    }
+}
+
+impl SegmentTable {
+    /// Maps `segment` to the given offset within the buffer.
+    pub fn set(&mut self, segment: u8, offset: usize) {
+        self.segments[(segment & 0x0F) as usize] = Some(offset);
+    }
+
+    /// Converts a segmented address into an offset within the buffer, if its segment is mapped.
+    ///
+    /// The top nibble of the address is ignored so that KSEG0 addresses resolve via segment 0.
+    pub fn resolve(&self, address: u32) -> Option<usize> {
+        let segment = ((address >> 24) & 0x0F) as usize;
+        let base = self.segments[segment]?;
+        Some(base + (address & 0x00FF_FFFF) as usize)
+    }
+}
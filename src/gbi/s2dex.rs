@@ -0,0 +1,411 @@
+//! Computes the object structures of the S2DEX microcode, as defined by `gs2dex.h`.
+//!
+//! Values are computed the way the `GS_*` macros and `guS2DInitBg` would, so the structures can
+//! be defined statically. Fixed-point fields are stored already shifted.
+
+use crate::gbi::tmem_capacity;
+use crate::{Error, ImageSize, ImageType};
+use std::fmt::Write;
+
+/// Most texels an object can span along either axis, as positions are stored in 10 integer bits.
+pub const MAX_OBJECT_SIZE: u32 = 1023;
+
+/// Fractional bits of the `dxt` value of `G_LOADBLOCK`.
+const G_TX_DXT_FRAC: u32 = 11;
+/// TMEM address of the palette in 64-bit words, each of which holds one palette entry.
+const TLUT_TMEM: u16 = 256;
+
+/// How a texture is loaded into TMEM.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum ObjLoad {
+    /// A single `G_LOADBLOCK`, which requires rows that are a multiple of 64 bits.
+    #[default]
+    Block,
+    /// A `G_LOADTILE`, which can load a rectangle out of a wider image.
+    Tile,
+}
+
+/// A `uObjTxtr`, loading a texture or a palette with `gSPObjLoadTxtr`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ObjTxtr {
+    /// `uObjTxtrBlock_t`
+    Block { tmem: u16, tsize: u16, tline: u16 },
+    /// `uObjTxtrTile_t`
+    Tile {
+        tmem: u16,
+        twidth: u16,
+        theight: u16,
+    },
+    /// `uObjTxtrTLUT_t`
+    Tlut { phead: u16, pnum: u16 },
+}
+
+/// A `uObjSprite`, drawing a texture loaded in TMEM.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ObjSprite {
+    pub format: ImageType,
+    /// Position on screen (s10.2).
+    pub obj_x: i16,
+    pub obj_y: i16,
+    /// Scale (u5.10).
+    pub scale_w: u16,
+    pub scale_h: u16,
+    /// Size of the texture (u10.5).
+    pub image_w: u16,
+    pub image_h: u16,
+    /// Size of a texture row in 64-bit words.
+    pub image_stride: u16,
+    /// TMEM address of the texture in 64-bit words.
+    pub image_adrs: u16,
+    pub image_pal: u8,
+    pub image_flags: u8,
+}
+
+/// A `uObjBg` (`uObjBg_t`), drawing a background with `gSPBgRectCopy` or `gSPBgRect1Cyc`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ObjBg {
+    pub format: ImageType,
+    pub load: ObjLoad,
+    /// Top-left texel of the image shown in the frame (u10.5).
+    pub image_x: u16,
+    pub image_y: u16,
+    /// Size of the image (u10.2).
+    pub image_w: u16,
+    pub image_h: u16,
+    /// Position of the frame on screen (s10.2).
+    pub frame_x: i16,
+    pub frame_y: i16,
+    /// Size of the frame (u10.2).
+    pub frame_w: u16,
+    pub frame_h: u16,
+    pub image_pal: u16,
+    pub image_flip: u16,
+    pub tmem_w: u16,
+    pub tmem_h: u16,
+    pub tmem_load_sh: u16,
+    pub tmem_load_th: u16,
+    pub tmem_size_w: u16,
+    pub tmem_size: u16,
+}
+
+impl ObjLoad {
+    /// Returns the name of the `G_BGLT_*` constant for this load.
+    pub fn bg_name(&self) -> &'static str {
+        match self {
+            ObjLoad::Block => "G_BGLT_LOADBLOCK",
+            ObjLoad::Tile => "G_BGLT_LOADTILE",
+        }
+    }
+}
+
+impl ObjTxtr {
+    /// Loads a whole texture at TMEM word address `tmem`, like `GS_TB_TSIZE` and `GS_TB_TLINE`.
+    pub fn block(format: ImageType, width: u32, height: u32, tmem: u16) -> Result<Self, Error> {
+        check_texture(format, width, height, tmem)?;
+        let line = aligned_line(format, width)?;
+
+        Ok(ObjTxtr::Block {
+            tmem,
+            tsize: (line * height - 1) as u16,
+            tline: calc_dxt(line) as u16,
+        })
+    }
+
+    /// Loads a texture at TMEM word address `tmem` with a tile, like `GS_TT_TWIDTH` and
+    /// `GS_TT_THEIGHT`.
+    pub fn tile(format: ImageType, width: u32, height: u32, tmem: u16) -> Result<Self, Error> {
+        check_texture(format, width, height, tmem)?;
+
+        Ok(ObjTxtr::Tile {
+            tmem,
+            twidth: ((pix_to_tmem(format, width) << 2) - 1) as u16,
+            theight: ((height << 2) - 1) as u16,
+        })
+    }
+
+    /// Loads `entries` palette entries starting at entry `head` of the palette area of TMEM, like
+    /// `GS_PAL_HEAD` and `GS_PAL_NUM`.
+    pub fn tlut(head: u16, entries: u16) -> Result<Self, Error> {
+        let end = head as usize + entries as usize;
+        if entries == 0 || end > TLUT_TMEM as usize {
+            return Err(Error::PaletteOverflow {
+                colors: end,
+                max: TLUT_TMEM as usize,
+            });
+        }
+
+        Ok(ObjTxtr::Tlut {
+            phead: head + TLUT_TMEM,
+            pnum: entries - 1,
+        })
+    }
+
+    /// Returns the C initializer of a `uObjTxtr` named `name` loading the data at `image`.
+    ///
+    /// The status flag is set to the address of the data, so that S2DEX skips loading data that
+    /// is already in TMEM.
+    pub fn to_c(&self, name: &str, image: &str) -> String {
+        let (kind, fields) = match *self {
+            ObjTxtr::Block { tmem, tsize, tline } => (
+                "G_OBJLT_TXTRBLOCK",
+                [
+                    (tmem, "TMEM address"),
+                    (tsize, "Texture size"),
+                    (tline, "Texture line"),
+                ],
+            ),
+            ObjTxtr::Tile {
+                tmem,
+                twidth,
+                theight,
+            } => (
+                "G_OBJLT_TXTRTILE",
+                [
+                    (tmem, "TMEM address"),
+                    (twidth, "Texture width"),
+                    (theight, "Texture height"),
+                ],
+            ),
+            ObjTxtr::Tlut { phead, pnum } => (
+                "G_OBJLT_TLUT",
+                [
+                    (phead, "Palette head"),
+                    (pnum, "Palette entries - 1"),
+                    (0, "Zero"),
+                ],
+            ),
+        };
+
+        let mut lines = vec![
+            (format!("{kind},"), "Type"),
+            (format!("(u64 *){image},"), "Image"),
+        ];
+        lines.extend(fields.map(|(value, comment)| (format!("{value},"), comment)));
+        lines.extend([
+            ("0,".to_string(), "Status ID"),
+            (format!("(u32){image},"), "Status flag"),
+            ("0xFFFFFFFF,".to_string(), "Status mask"),
+        ]);
+
+        initializer("uObjTxtr", name, &lines)
+    }
+}
+
+impl ObjSprite {
+    /// Draws a texture of `width` by `height` texels at TMEM word address `tmem`, at the top-left
+    /// of the screen and without scaling.
+    pub fn new(format: ImageType, width: u32, height: u32, tmem: u16) -> Result<Self, Error> {
+        check_texture(format, width, height, tmem)?;
+
+        Ok(ObjSprite {
+            format,
+            obj_x: 0,
+            obj_y: 0,
+            scale_w: 1 << 10,
+            scale_h: 1 << 10,
+            image_w: (width << 5) as u16,
+            image_h: (height << 5) as u16,
+            image_stride: pix_to_tmem(format, width) as u16,
+            image_adrs: tmem,
+            image_pal: 0,
+            image_flags: 0,
+        })
+    }
+
+    /// Returns the C initializer of a `uObjSprite` named `name`.
+    pub fn to_c(&self, name: &str) -> Result<String, Error> {
+        let (format, size) = gbi_names(self.format)?;
+
+        Ok(initializer(
+            "uObjSprite",
+            name,
+            &[
+                (format!("{},", self.obj_x), "objX (s10.2)"),
+                (format!("{},", self.scale_w), "scaleW (u5.10)"),
+                (format!("{},", self.image_w), "imageW (u10.5)"),
+                ("0,".to_string(), "paddingX"),
+                (format!("{},", self.obj_y), "objY (s10.2)"),
+                (format!("{},", self.scale_h), "scaleH (u5.10)"),
+                (format!("{},", self.image_h), "imageH (u10.5)"),
+                ("0,".to_string(), "paddingY"),
+                (format!("{},", self.image_stride), "imageStride"),
+                (format!("{},", self.image_adrs), "imageAdrs"),
+                (format!("{format},"), "imageFmt"),
+                (format!("{size},"), "imageSiz"),
+                (format!("{},", self.image_pal), "imagePal"),
+                (format!("{},", self.image_flags), "imageFlags"),
+            ],
+        ))
+    }
+}
+
+impl ObjBg {
+    /// Shows an image of `width` by `height` texels in a frame of the same size at the top-left
+    /// of the screen, computing the TMEM fields the way `guS2DInitBg` does.
+    ///
+    /// Rows of the image must be a multiple of 64 bits.
+    pub fn new(format: ImageType, width: u32, height: u32, load: ObjLoad) -> Result<Self, Error> {
+        Self::with_frame(format, width, height, load, (0, 0, width, height))
+    }
+
+    /// Shows an image of `width` by `height` texels in a frame at `(x, y)` of `width` by `height`
+    /// pixels, given as `(x, y, width, height)`.
+    pub fn with_frame(
+        format: ImageType,
+        width: u32,
+        height: u32,
+        load: ObjLoad,
+        frame: (i16, i16, u32, u32),
+    ) -> Result<Self, Error> {
+        let (frame_x, frame_y, frame_width, frame_height) = frame;
+        check_dimensions(width.max(frame_width), height.max(frame_height))?;
+        gbi_names(format)?;
+
+        let line = aligned_line(format, width)?;
+        let available = tmem_capacity(format) / 8;
+        let (tmem_w, tmem_size_w) = match load {
+            ObjLoad::Block => (line, line * 2),
+            // guS2DInitBg truncates the frame width to whole words before adding the extra one
+            ObjLoad::Tile => (frame_width * format.get_size().get_bpp() / 64 + 1, line * 2),
+        };
+        if tmem_w > available {
+            return Err(Error::TmemOverflow {
+                required: tmem_w * 8,
+                available: available * 8,
+            });
+        }
+        let tmem_h = (available / tmem_w) << 2;
+        let tmem_size = tmem_size_w * tmem_h;
+        let (tmem_load_sh, tmem_load_th) = match load {
+            ObjLoad::Block => (tmem_size / 2 - 1, calc_dxt(tmem_w)),
+            ObjLoad::Tile => (tmem_w * 16 - 1, tmem_h - 1),
+        };
+
+        Ok(ObjBg {
+            format,
+            load,
+            image_x: 0,
+            image_y: 0,
+            image_w: (width << 2) as u16,
+            image_h: (height << 2) as u16,
+            frame_x: frame_x << 2,
+            frame_y: frame_y << 2,
+            frame_w: (frame_width << 2) as u16,
+            frame_h: (frame_height << 2) as u16,
+            image_pal: 0,
+            image_flip: 0,
+            tmem_w: tmem_w as u16,
+            tmem_h: tmem_h as u16,
+            tmem_load_sh: tmem_load_sh as u16,
+            tmem_load_th: tmem_load_th as u16,
+            tmem_size_w: tmem_size_w as u16,
+            tmem_size: tmem_size as u16,
+        })
+    }
+
+    /// Returns the C initializer of a `uObjBg` named `name` showing the data at `image`.
+    pub fn to_c(&self, name: &str, image: &str) -> Result<String, Error> {
+        let (format, size) = gbi_names(self.format)?;
+
+        Ok(initializer(
+            "uObjBg",
+            name,
+            &[
+                (format!("{},", self.image_x), "imageX (u10.5)"),
+                (format!("{},", self.image_w), "imageW (u10.2)"),
+                (format!("{},", self.frame_x), "frameX (s10.2)"),
+                (format!("{},", self.frame_w), "frameW (u10.2)"),
+                (format!("{},", self.image_y), "imageY (u10.5)"),
+                (format!("{},", self.image_h), "imageH (u10.2)"),
+                (format!("{},", self.frame_y), "frameY (s10.2)"),
+                (format!("{},", self.frame_h), "frameH (u10.2)"),
+                (format!("(u64 *){image},"), "imagePtr"),
+                (format!("{},", self.load.bg_name()), "imageLoad"),
+                (format!("{format},"), "imageFmt"),
+                (format!("{size},"), "imageSiz"),
+                (format!("{},", self.image_pal), "imagePal"),
+                (format!("{},", self.image_flip), "imageFlip"),
+                (format!("{},", self.tmem_w), "tmemW"),
+                (format!("{},", self.tmem_h), "tmemH"),
+                (format!("{},", self.tmem_load_sh), "tmemLoadSH"),
+                (format!("{},", self.tmem_load_th), "tmemLoadTH"),
+                (format!("{},", self.tmem_size_w), "tmemSizeW"),
+                (format!("{},", self.tmem_size), "tmemSize"),
+            ],
+        ))
+    }
+}
+
+// MARK: - Helpers
+
+/// Converts a number of texels to 64-bit words, like `GS_PIX2TMEM` but rounding up.
+fn pix_to_tmem(format: ImageType, pixels: u32) -> u32 {
+    (pixels * format.get_size().get_bpp()).div_ceil(64)
+}
+
+/// Returns the `dxt` value of rows of `line` 64-bit words, like `GS_CALC_DXT`.
+fn calc_dxt(line: u32) -> u32 {
+    ((1 << G_TX_DXT_FRAC) - 1) / line + 1
+}
+
+/// Returns the size of a row in 64-bit words, if rows are a multiple of 64 bits.
+fn aligned_line(format: ImageType, width: u32) -> Result<u32, Error> {
+    let bits = width * format.get_size().get_bpp();
+    if bits == 0 || bits % 64 != 0 {
+        return Err(Error::UnalignedTextureRow { width, format });
+    }
+
+    Ok(bits / 64)
+}
+
+fn check_dimensions(width: u32, height: u32) -> Result<(), Error> {
+    if width == 0 || height == 0 || width > MAX_OBJECT_SIZE || height > MAX_OBJECT_SIZE {
+        return Err(Error::ObjectTooLarge { width, height });
+    }
+
+    Ok(())
+}
+
+/// Checks that a texture loaded at `tmem` fits in the TMEM available to its format.
+fn check_texture(format: ImageType, width: u32, height: u32, tmem: u16) -> Result<(), Error> {
+    check_dimensions(width, height)?;
+    gbi_names(format)?;
+
+    let required = (tmem as u32 + pix_to_tmem(format, width) * height) * 8;
+    let available = tmem_capacity(format);
+    if required > available {
+        return Err(Error::TmemOverflow {
+            required,
+            available,
+        });
+    }
+
+    Ok(())
+}
+
+/// Returns the `G_IM_FMT_*` and `G_IM_SIZ_*` names of a format S2DEX can load.
+fn gbi_names(format: ImageType) -> Result<(&'static str, &'static str), Error> {
+    match format.get_size() {
+        ImageSize::Bits4 | ImageSize::Bits8 | ImageSize::Bits16 => Ok((
+            format.get_format().gbi_name(),
+            format.get_size().gbi_name().unwrap_or_default(),
+        )),
+        _ => Err(Error::UnsupportedTmemFormat(format)),
+    }
+}
+
+/// Formats a structure initializer with one commented field per line.
+fn initializer(kind: &str, name: &str, fields: &[(String, &str)]) -> String {
+    let column = fields
+        .iter()
+        .map(|(value, _)| value.len())
+        .max()
+        .unwrap_or(0);
+
+    let mut source = format!("{kind} {name} = {{\n");
+    for (value, comment) in fields {
+        let _ = writeln!(source, "    {value:column$} /* {comment} */");
+    }
+    source.push_str("};\n");
+    source
+}
@@ -0,0 +1,129 @@
+use crate::analysis::quality::encode_format;
+use crate::gbi::{tmem_capacity, tmem_footprint};
+use crate::image::canvas::{crop, pad_rows, row_width};
+use crate::image::quantize::quantize;
+use crate::{Error, ImageFormat, ImageSize, ImageType, PNGImage};
+
+/// A rectangle of an image encoded on its own so it can be loaded into TMEM.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextureTile {
+    /// Position of the tile's top-left texel in the source image.
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    /// Native texel data of the tile. Rows of 4-bit tiles start on a byte, so the last byte of each
+    /// row of an odd-width tile holds a padding texel.
+    pub data: Vec<u8>,
+}
+
+/// An image split into tiles that each fit in TMEM.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SplitImage {
+    pub format: ImageType,
+    /// Tiles in row-major order.
+    pub tiles: Vec<TextureTile>,
+    /// Number of tiles in each row.
+    pub columns: u32,
+    pub rows: u32,
+    /// RGBA16 palette shared by every tile of a color-indexed image.
+    pub tlut: Option<Vec<u8>>,
+}
+
+/// Splits `png` into tiles that each fit in the TMEM available to `format`.
+///
+/// Tiles are at most `max_width` texels wide (the image width by default) and as tall as TMEM
+/// allows, so a wide image is cut into horizontal strips. Neighbouring tiles share `overlap`
+/// rows and columns, so that bilinear filtering near a seam samples the texels of the next tile.
+/// Color-indexed tiles share a palette built from the whole image.
+pub fn split_image(
+    png: &PNGImage,
+    format: ImageType,
+    max_width: Option<u32>,
+    overlap: u32,
+) -> Result<SplitImage, Error> {
+    if matches!(format.get_size(), ImageSize::Bits1) {
+        return Err(Error::UnsupportedTmemFormat(format));
+    }
+
+    let (width, height) = (png.width(), png.height());
+    let tile_width = max_width.unwrap_or(width).clamp(1, width);
+    let available = tmem_capacity(format);
+    let row_size = tmem_footprint(format, tile_width, 1);
+    let tile_height = (available / row_size).min(height);
+
+    // Every tile but the last of a row or column must advance past the overlap
+    let columns_step = tile_width.saturating_sub(overlap);
+    let rows_step = tile_height.saturating_sub(overlap);
+    if (tile_width < width && columns_step == 0) || (tile_height < height && rows_step == 0) {
+        return Err(Error::TmemOverflow {
+            required: row_size * (overlap + 1),
+            available,
+        });
+    }
+
+    let rgba = png.as_rgba8();
+    let quantized = match format.get_format() {
+        ImageFormat::Ci => {
+            let entries = format.get_size().get_tlut_size().unwrap_or_default();
+            Some(quantize(&rgba, entries))
+        }
+        _ => None,
+    };
+
+    let xs = tile_origins(width, tile_width, columns_step);
+    let ys = tile_origins(height, tile_height, rows_step);
+
+    let mut tiles = Vec::with_capacity(xs.len() * ys.len());
+    for &y in &ys {
+        for &x in &xs {
+            let w = tile_width.min(width - x);
+            let h = tile_height.min(height - y);
+
+            // Native 4-bit encoders pack texels across rows, so odd widths get a padding column
+            let padded = row_width(format, w);
+            let data = match &quantized {
+                Some(quantized) => {
+                    let indices = crop(&quantized.indices, width, 1, x, y, w, h);
+                    let indices = pad_rows(&indices, w, 1, padded);
+                    let mut data = Vec::new();
+                    PNGImage::from_indexed(padded, h, indices, &quantized.palette)
+                        .as_native(&mut data, format)?;
+                    data
+                }
+                None => {
+                    let pixels = crop(&rgba, width, 4, x, y, w, h);
+                    let pixels = pad_rows(&pixels, w, 4, padded);
+                    encode_format(&PNGImage::from_rgba8(padded, h, pixels), format)?.0
+                }
+            };
+
+            tiles.push(TextureTile {
+                x,
+                y,
+                width: w,
+                height: h,
+                data,
+            });
+        }
+    }
+
+    let tlut = quantized.map(|q| q.palette.iter().flat_map(|c| c.rgba16()).collect());
+
+    Ok(SplitImage {
+        format,
+        tiles,
+        columns: xs.len() as u32,
+        rows: ys.len() as u32,
+        tlut,
+    })
+}
+
+/// Returns the start of each tile along an axis of `size` texels.
+fn tile_origins(size: u32, tile_size: u32, step: u32) -> Vec<u32> {
+    let mut origins = vec![0];
+    while origins[origins.len() - 1] + tile_size < size {
+        origins.push(origins[origins.len() - 1] + step);
+    }
+    origins
+}
@@ -0,0 +1,158 @@
+//! Emits the `Sprite` and `Bitmap` structures drawn by the `sp` library of libultra.
+
+use crate::gbi::split::SplitImage;
+use crate::image::canvas::row_width;
+use crate::{Error, ImageFormat};
+use std::fmt::Write;
+
+/// Attribute flags of a `Sprite`, combined in its `attr` field.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum SpriteAttribute {
+    Transparent,
+    Cutout,
+    Hidden,
+    Z,
+    Scale,
+    FastCopy,
+    Overlap,
+    TexShift,
+    FracPos,
+    TexShuf,
+    Extern,
+}
+
+impl SpriteAttribute {
+    /// Returns the name of the `SP_*` constant for this attribute.
+    pub fn c_name(&self) -> &'static str {
+        match self {
+            SpriteAttribute::Transparent => "SP_TRANSPARENT",
+            SpriteAttribute::Cutout => "SP_CUTOUT",
+            SpriteAttribute::Hidden => "SP_HIDDEN",
+            SpriteAttribute::Z => "SP_Z",
+            SpriteAttribute::Scale => "SP_SCALE",
+            SpriteAttribute::FastCopy => "SP_FASTCOPY",
+            SpriteAttribute::Overlap => "SP_OVERLAP",
+            SpriteAttribute::TexShift => "SP_TEXSHIFT",
+            SpriteAttribute::FracPos => "SP_FRACPOS",
+            SpriteAttribute::TexShuf => "SP_TEXSHUF",
+            SpriteAttribute::Extern => "SP_EXTERN",
+        }
+    }
+}
+
+/// Returns the name of the texel array of a bitmap referenced by [`sp_sprite_source`].
+pub fn sp_bitmap_name(name: &str, index: usize) -> String {
+    format!("{name}_tex_{index}")
+}
+
+/// Returns the name of the palette referenced by [`sp_sprite_source`] for color-indexed sprites.
+pub fn sp_tlut_name(name: &str) -> String {
+    format!("{name}_tlut")
+}
+
+/// Returns the C definitions of a `Sprite` drawing `image` with the `sp` library: one `Bitmap`
+/// per tile, the display list buffer filled in by `spDraw` and the `Sprite` itself.
+///
+/// Tiles are drawn left to right in rows, so `image` must come from
+/// [`split_image`](crate::gbi::split::split_image) without overlap. The texels of each tile and
+/// the palette of a color-indexed image are referenced by the names returned by
+/// [`sp_bitmap_name`] and [`sp_tlut_name`], and must be defined before this code.
+pub fn sp_sprite_source(
+    name: &str,
+    image: &SplitImage,
+    attributes: &[SpriteAttribute],
+) -> Result<String, Error> {
+    let size = image
+        .format
+        .get_size()
+        .gbi_name()
+        .ok_or(Error::UnsupportedTmemFormat(image.format))?;
+    let format = image.format.get_format().gbi_name();
+
+    let width = image.tiles.iter().map(|t| t.x + t.width).max().unwrap_or(0);
+    let height = image
+        .tiles
+        .iter()
+        .map(|t| t.y + t.height)
+        .max()
+        .unwrap_or(0);
+    let bitmap_height = image.tiles.first().map_or(0, |tile| tile.height);
+    let count = format!("NUM_{}_BMS", name.to_uppercase());
+
+    let mut source = format!("static Bitmap {name}_bitmaps[] = {{\n");
+    for (index, tile) in image.tiles.iter().enumerate() {
+        let _ = writeln!(
+            source,
+            "    {{ {}, {}, 0, 0, {}, {}, 0 }},",
+            tile.width,
+            row_width(image.format, tile.width),
+            sp_bitmap_name(name, index),
+            tile.height
+        );
+    }
+    source.push_str("};\n\n");
+
+    let _ = writeln!(
+        source,
+        "#define {count} (sizeof({name}_bitmaps) / sizeof(Bitmap))\n"
+    );
+    let _ = writeln!(source, "static Gfx {name}_dl[NUM_DL({count})];\n");
+
+    let attributes = match attributes {
+        [] => "0".to_string(),
+        attributes => attributes
+            .iter()
+            .map(SpriteAttribute::c_name)
+            .collect::<Vec<_>>()
+            .join(" | "),
+    };
+    let (tlut_entries, tlut) = match &image.tlut {
+        Some(tlut) if image.format.get_format() == ImageFormat::Ci => {
+            (tlut.len() / 2, format!("(int *){}", sp_tlut_name(name)))
+        }
+        _ => (0, "NULL".to_string()),
+    };
+
+    let fields = [
+        ("0, 0,".to_string(), "Position: x, y"),
+        (format!("{width}, {height},"), "Size in texels: x, y"),
+        ("1.0f, 1.0f,".to_string(), "Scale: x, y"),
+        ("0, 0,".to_string(), "Explosion spacing: x, y"),
+        (format!("{attributes},"), "Attributes"),
+        ("0,".to_string(), "Depth (Z)"),
+        ("255, 255, 255, 255,".to_string(), "Primitive color: RGBA"),
+        (
+            format!("0, {tlut_entries}, {tlut},"),
+            "Palette: start index, length, address",
+        ),
+        ("0, 1,".to_string(), "Bitmap index: start, step"),
+        (format!("{count},"), "Number of bitmaps"),
+        (
+            format!("NUM_DL({count}),"),
+            "Number of display list locations",
+        ),
+        (
+            format!("{bitmap_height}, {bitmap_height},"),
+            "Bitmap height: used, physical",
+        ),
+        (format!("{format},"), "Bitmap format"),
+        (format!("{size},"), "Bitmap texel size"),
+        (format!("{name}_bitmaps,"), "Bitmaps"),
+        (format!("{name}_dl,"), "Display list memory"),
+        ("NULL,".to_string(), "Next display list pointer"),
+        ("0, 0,".to_string(), "Fractional texture offsets: s, t"),
+    ];
+    let column = fields
+        .iter()
+        .map(|(value, _)| value.len())
+        .max()
+        .unwrap_or(0);
+
+    let _ = writeln!(source, "Sprite {name}_sprite = {{");
+    for (value, comment) in fields {
+        let _ = writeln!(source, "    {value:column$} /* {comment} */");
+    }
+    source.push_str("};\n");
+
+    Ok(source)
+}
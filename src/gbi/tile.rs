@@ -0,0 +1,137 @@
+use crate::gbi::{G_SETTILE, G_SETTILESIZE, G_TX_LOADTILE, G_TX_RENDERTILE};
+use crate::{Error, ImageType};
+
+/// Number of fractional bits of the texture coordinates given to `G_SETTILESIZE`.
+const G_TEXTURE_IMAGE_FRAC: u32 = 2;
+
+/// Wrapping settings of a tile along one texture axis.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct TileAxis {
+    pub mirror: bool,
+    pub clamp: bool,
+    /// Number of coordinate bits kept when wrapping, zero to disable wrapping.
+    pub mask: u8,
+    /// Amount the coordinates are shifted right by, as used for mipmap levels.
+    pub shift: u8,
+}
+
+/// An RDP tile descriptor, as set with `G_SETTILE` and `G_SETTILESIZE`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct TileDescriptor {
+    pub tile: u8,
+    pub format: ImageType,
+    /// Size of a row in TMEM, in 64-bit words.
+    pub line: u32,
+    /// TMEM address of the first texel, in 64-bit words.
+    pub tmem: u32,
+    pub palette: u8,
+    pub s: TileAxis,
+    pub t: TileAxis,
+    /// Coordinates of the top-left texel covered by the tile.
+    pub uls: u32,
+    pub ult: u32,
+    /// Coordinates of the bottom-right texel covered by the tile.
+    pub lrs: u32,
+    pub lrt: u32,
+}
+
+impl TileAxis {
+    fn cm(&self) -> u32 {
+        (self.mirror as u32) | (self.clamp as u32) << 1
+    }
+
+    fn cm_name(&self) -> &'static str {
+        match (self.mirror, self.clamp) {
+            (false, false) => "G_TX_NOMIRROR | G_TX_WRAP",
+            (true, false) => "G_TX_MIRROR | G_TX_WRAP",
+            (false, true) => "G_TX_NOMIRROR | G_TX_CLAMP",
+            (true, true) => "G_TX_MIRROR | G_TX_CLAMP",
+        }
+    }
+}
+
+impl TileDescriptor {
+    /// Returns the two words of the `G_SETTILE` command setting up this tile.
+    pub fn set_tile(&self) -> Result<[u32; 2], Error> {
+        let size = self.rdp_size()?;
+        let w0 = (G_SETTILE as u32) << 24
+            | (self.format.get_format() as u32) << 21
+            | size << 19
+            | (self.line & 0x1FF) << 9
+            | (self.tmem & 0x1FF);
+        let w1 = (self.tile as u32 & 0x07) << 24
+            | (self.palette as u32 & 0x0F) << 20
+            | self.t.cm() << 18
+            | (self.t.mask as u32 & 0x0F) << 14
+            | (self.t.shift as u32 & 0x0F) << 10
+            | self.s.cm() << 8
+            | (self.s.mask as u32 & 0x0F) << 4
+            | (self.s.shift as u32 & 0x0F);
+
+        Ok([w0, w1])
+    }
+
+    /// Returns the two words of the `G_SETTILESIZE` command setting the size of this tile.
+    pub fn set_tile_size(&self) -> [u32; 2] {
+        let w0 = (G_SETTILESIZE as u32) << 24
+            | ((self.uls << G_TEXTURE_IMAGE_FRAC) & 0xFFF) << 12
+            | ((self.ult << G_TEXTURE_IMAGE_FRAC) & 0xFFF);
+        let w1 = (self.tile as u32 & 0x07) << 24
+            | ((self.lrs << G_TEXTURE_IMAGE_FRAC) & 0xFFF) << 12
+            | ((self.lrt << G_TEXTURE_IMAGE_FRAC) & 0xFFF);
+
+        [w0, w1]
+    }
+
+    /// Returns the `gsDPSetTile` and `gsDPSetTileSize` macros setting up this tile, one per line.
+    pub fn to_c_macros(&self) -> Result<String, Error> {
+        let size = self
+            .format
+            .get_size()
+            .gbi_name()
+            .ok_or(Error::UnsupportedTmemFormat(self.format))?;
+        let tile = tile_name(self.tile);
+        let coordinate = |value: u32| match value {
+            0 => "0".to_string(),
+            value => format!("{value} << G_TEXTURE_IMAGE_FRAC"),
+        };
+
+        Ok(format!(
+            "gsDPSetTile({}, {}, {}, 0x{:03X}, {}, {}, {}, {}, {}, {}, {}, {}),\n\
+             gsDPSetTileSize({}, {}, {}, {}, {}),",
+            self.format.get_format().gbi_name(),
+            size,
+            self.line,
+            self.tmem,
+            tile,
+            self.palette,
+            self.t.cm_name(),
+            self.t.mask,
+            self.t.shift,
+            self.s.cm_name(),
+            self.s.mask,
+            self.s.shift,
+            tile,
+            coordinate(self.uls),
+            coordinate(self.ult),
+            coordinate(self.lrs),
+            coordinate(self.lrt),
+        ))
+    }
+
+    fn rdp_size(&self) -> Result<u32, Error> {
+        self.format
+            .get_size()
+            .gbi_name()
+            .map(|_| self.format.get_size() as u32)
+            .ok_or(Error::UnsupportedTmemFormat(self.format))
+    }
+}
+
+fn tile_name(tile: u8) -> String {
+    match tile {
+        G_TX_RENDERTILE => "G_TX_RENDERTILE".to_string(),
+        G_TX_LOADTILE => "G_TX_LOADTILE".to_string(),
+        tile => format!("G_TX_RENDERTILE + {}", tile - G_TX_RENDERTILE),
+    }
+}
@@ -0,0 +1,191 @@
+use crate::analysis::quality::encode_format;
+use crate::gbi::{tmem_capacity, tmem_footprint};
+use crate::{Error, ImageSize, ImageType, PNGImage};
+
+/// How images are laid out in an atlas.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct AtlasOptions {
+    /// Transparent texels left between neighbouring images.
+    pub padding: u32,
+    /// Number of times the edge texels of each image are repeated around it, so that filtering
+    /// does not bleed neighbouring images in.
+    pub extrude: u32,
+    /// Rounds both dimensions of the atlas up to powers of two, as required to wrap or mirror it.
+    pub power_of_two: bool,
+    /// Only accepts layouts that fit in the TMEM available to the format.
+    pub fit_tmem: bool,
+}
+
+/// Where an image was placed in an atlas, excluding its extruded edges.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct AtlasEntry {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Several images packed in a single texture.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Atlas {
+    pub format: ImageType,
+    pub width: u32,
+    pub height: u32,
+    /// Native texel data of the atlas.
+    pub data: Vec<u8>,
+    /// Position of each input image, in the order they were given.
+    pub entries: Vec<AtlasEntry>,
+    /// RGBA16 palette shared by every image of a color-indexed atlas.
+    pub tlut: Option<Vec<u8>>,
+}
+
+/// Packs `images` into a single texture of the given format.
+///
+/// Images are sorted by height and placed on shelves, trying every atlas width (rows are kept a
+/// multiple of 64 bits) and keeping the layout of smallest area. Color-indexed atlases are
+/// quantized as a whole, so every image shares the same palette.
+pub fn build_atlas(
+    images: &[PNGImage],
+    format: ImageType,
+    options: AtlasOptions,
+) -> Result<Atlas, Error> {
+    if images.is_empty() {
+        return Err(Error::EmptyAtlas);
+    }
+    if options.fit_tmem && matches!(format.get_size(), ImageSize::Bits1) {
+        return Err(Error::UnsupportedTmemFormat(format));
+    }
+
+    let border = options.extrude * 2;
+    let cells: Vec<(u32, u32)> = images
+        .iter()
+        .map(|image| (image.width() + border, image.height() + border))
+        .collect();
+
+    let mut order: Vec<usize> = (0..cells.len()).collect();
+    order.sort_by_key(|&i| (std::cmp::Reverse(cells[i].1), std::cmp::Reverse(cells[i].0)));
+
+    let align = (64 / format.get_size().get_bpp()).max(1);
+    let min_width = cells.iter().map(|c| c.0).max().unwrap_or(1);
+    let total_width: u32 = cells.iter().map(|c| c.0 + options.padding).sum();
+
+    let candidates: Vec<u32> = if options.power_of_two {
+        let mut widths = vec![min_width.next_power_of_two().max(align)];
+        while widths[widths.len() - 1] < total_width {
+            widths.push(widths[widths.len() - 1] * 2);
+        }
+        widths
+    } else {
+        (min_width.next_multiple_of(align)..=total_width.next_multiple_of(align))
+            .step_by(align as usize)
+            .collect()
+    };
+
+    let available = tmem_capacity(format);
+    let mut best: Option<(u32, u32)> = None;
+    let mut smallest_footprint = u32::MAX;
+
+    for width in candidates {
+        let (_, height) = shelf_pack(&cells, &order, width, options.padding);
+        let height = if options.power_of_two {
+            height.next_power_of_two()
+        } else {
+            height
+        };
+
+        if options.fit_tmem {
+            let footprint = tmem_footprint(format, width, height);
+            smallest_footprint = smallest_footprint.min(footprint);
+            if footprint > available {
+                continue;
+            }
+        }
+
+        let better = match best {
+            Some((w, h)) => {
+                let (area, best_area) = (width * height, w * h);
+                area < best_area || (area == best_area && width.max(height) < w.max(h))
+            }
+            None => true,
+        };
+        if better {
+            best = Some((width, height));
+        }
+    }
+
+    let Some((width, height)) = best else {
+        return Err(Error::TmemOverflow {
+            required: smallest_footprint,
+            available,
+        });
+    };
+    let (positions, _) = shelf_pack(&cells, &order, width, options.padding);
+
+    let mut rgba = vec![0u8; (width * height * 4) as usize];
+    let mut entries = Vec::with_capacity(images.len());
+
+    for (image, &(cell_x, cell_y)) in images.iter().zip(&positions) {
+        let x = cell_x + options.extrude;
+        let y = cell_y + options.extrude;
+        let pixels = image.as_rgba8();
+        let extrude = options.extrude as i64;
+
+        for dy in -extrude..image.height() as i64 + extrude {
+            for dx in -extrude..image.width() as i64 + extrude {
+                let sx = dx.clamp(0, image.width() as i64 - 1) as u32;
+                let sy = dy.clamp(0, image.height() as i64 - 1) as u32;
+                let source = ((sy * image.width() + sx) * 4) as usize;
+                let target_x = (x as i64 + dx) as u32;
+                let target_y = (y as i64 + dy) as u32;
+                let target = ((target_y * width + target_x) * 4) as usize;
+                rgba[target..target + 4].copy_from_slice(&pixels[source..source + 4]);
+            }
+        }
+
+        entries.push(AtlasEntry {
+            x,
+            y,
+            width: image.width(),
+            height: image.height(),
+        });
+    }
+
+    let (data, tlut) = encode_format(&PNGImage::from_rgba8(width, height, rgba), format)?;
+
+    Ok(Atlas {
+        format,
+        width,
+        height,
+        data,
+        entries,
+        tlut,
+    })
+}
+
+/// Places cells left to right on shelves as tall as their first cell, in the given order, with
+/// `padding` texels between neighbouring cells.
+///
+/// Returns the position of each cell, indexed like `cells`, and the height of the layout.
+fn shelf_pack(
+    cells: &[(u32, u32)],
+    order: &[usize],
+    width: u32,
+    padding: u32,
+) -> (Vec<(u32, u32)>, u32) {
+    let mut positions = vec![(0, 0); cells.len()];
+    let (mut x, mut y, mut shelf_height) = (0, 0, 0);
+
+    for &index in order {
+        let (cell_width, cell_height) = cells[index];
+        if x > 0 && x + cell_width > width {
+            x = 0;
+            y += shelf_height + padding;
+            shelf_height = 0;
+        }
+        positions[index] = (x, y);
+        x += cell_width + padding;
+        shelf_height = shelf_height.max(cell_height);
+    }
+
+    (positions, y + shelf_height)
+}
@@ -0,0 +1,159 @@
+use crate::color::Color;
+use crate::{Error, ImageSize, ImageType};
+use std::io::Write;
+
+/// Glyphs for the built-in 3x5 bitmap font, one bit per pixel, row-major starting at the top-left.
+#[rustfmt::skip]
+const GLYPHS: &[(char, u16)] = &[
+    ('0', 0b111_101_101_101_111), ('1', 0b010_110_010_010_111), ('2', 0b111_001_111_100_111),
+    ('3', 0b111_001_111_001_111), ('4', 0b101_101_111_001_001), ('5', 0b111_100_111_001_111),
+    ('6', 0b111_100_111_101_111), ('7', 0b111_001_010_010_010), ('8', 0b111_101_111_101_111),
+    ('9', 0b111_101_111_001_111), ('A', 0b010_101_111_101_101), ('B', 0b110_101_110_101_110),
+    ('C', 0b011_100_100_100_011), ('D', 0b110_101_101_101_110), ('E', 0b111_100_110_100_111),
+    ('F', 0b111_100_110_100_100), ('G', 0b011_100_101_101_011), ('H', 0b101_101_111_101_101),
+    ('I', 0b111_010_010_010_111), ('J', 0b001_001_001_101_010), ('K', 0b101_101_110_101_101),
+    ('L', 0b100_100_100_100_111), ('M', 0b101_111_111_101_101), ('N', 0b110_101_101_101_101),
+    ('O', 0b010_101_101_101_010), ('P', 0b110_101_110_100_100), ('Q', 0b010_101_101_110_011),
+    ('R', 0b110_101_110_101_101), ('S', 0b011_100_010_001_110), ('T', 0b111_010_010_010_010),
+    ('U', 0b101_101_101_101_111), ('V', 0b101_101_101_101_010), ('W', 0b101_101_111_111_101),
+    ('X', 0b101_101_010_101_101), ('Y', 0b101_101_010_010_010), ('Z', 0b111_001_010_100_111),
+    ('.', 0b000_000_000_000_010), ('-', 0b000_000_111_000_000), (':', 0b000_010_000_010_000),
+    ('#', 0b101_111_101_111_101), ('/', 0b001_001_010_100_100),
+];
+
+pub(crate) const GLYPH_WIDTH: u32 = 3;
+pub(crate) const GLYPH_HEIGHT: u32 = 5;
+
+/// A simple RGBA8 drawing surface used to compose preview images such as contact sheets.
+pub(crate) struct Canvas {
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<u8>,
+}
+
+impl Canvas {
+    pub fn new(width: u32, height: u32, background: Color) -> Self {
+        let data = [background.r, background.g, background.b, background.a]
+            .repeat((width * height) as usize);
+        Self {
+            width,
+            height,
+            data,
+        }
+    }
+
+    pub fn set_pixel(&mut self, x: u32, y: u32, color: Color) {
+        if x >= self.width || y >= self.height {
+            return;
+        }
+
+        let index = ((y * self.width + x) * 4) as usize;
+        self.data[index..index + 4].copy_from_slice(&[color.r, color.g, color.b, color.a]);
+    }
+
+    pub fn fill_rect(&mut self, x: u32, y: u32, width: u32, height: u32, color: Color) {
+        for dy in 0..height {
+            for dx in 0..width {
+                self.set_pixel(x + dx, y + dy, color);
+            }
+        }
+    }
+
+    /// Copies an RGBA8 image onto the canvas, cropping anything that falls outside of it.
+    pub fn blit(&mut self, x: u32, y: u32, width: u32, height: u32, rgba: &[u8]) {
+        for sy in 0..height {
+            for sx in 0..width {
+                let index = ((sy * width + sx) * 4) as usize;
+                let Some(pixel) = rgba.get(index..index + 4) else {
+                    return;
+                };
+                self.set_pixel(
+                    x + sx,
+                    y + sy,
+                    Color::RGBA(pixel[0], pixel[1], pixel[2], pixel[3]),
+                );
+            }
+        }
+    }
+
+    /// Draws upper-case text with the built-in 3x5 font. Unknown characters are left blank.
+    pub fn draw_text(&mut self, x: u32, y: u32, text: &str, color: Color) {
+        for (i, c) in text.chars().enumerate() {
+            let c = c.to_ascii_uppercase();
+            let Some(&(_, bits)) = GLYPHS.iter().find(|(glyph, _)| *glyph == c) else {
+                continue;
+            };
+
+            let origin_x = x + i as u32 * (GLYPH_WIDTH + 1);
+            for row in 0..GLYPH_HEIGHT {
+                for col in 0..GLYPH_WIDTH {
+                    let bit = 14 - (row * GLYPH_WIDTH + col);
+                    if bits & (1 << bit) != 0 {
+                        self.set_pixel(origin_x + col, y + row, color);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Writes the canvas as an RGBA8 PNG to the given writer.
+    pub fn write_png<W: Write>(&self, writer: &mut W) -> Result<(), Error> {
+        let mut encoder = png::Encoder::new(writer, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.data)?;
+
+        Ok(())
+    }
+}
+
+/// Copies a rectangle out of pixels that are `bytes_per_pixel` bytes each.
+pub(crate) fn crop(
+    data: &[u8],
+    stride: u32,
+    bytes_per_pixel: usize,
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+) -> Vec<u8> {
+    (y..y + height)
+        .flat_map(|row| {
+            let start = (row * stride + x) as usize * bytes_per_pixel;
+            &data[start..start + width as usize * bytes_per_pixel]
+        })
+        .copied()
+        .collect()
+}
+
+/// Returns the width in texels rows of a `format` image are stored at, rounding 4-bit rows up to
+/// a whole byte.
+pub(crate) fn row_width(format: ImageType, width: u32) -> u32 {
+    match format.get_size() {
+        ImageSize::Bits4 => width.next_multiple_of(2),
+        _ => width,
+    }
+}
+
+/// Widens rows of `width` pixels to `padded` pixels by repeating their last pixel.
+pub(crate) fn pad_rows(data: &[u8], width: u32, bytes_per_pixel: usize, padded: u32) -> Vec<u8> {
+    if padded == width {
+        return data.to_vec();
+    }
+
+    let row_size = width as usize * bytes_per_pixel;
+    data.chunks_exact(row_size)
+        .flat_map(|row| {
+            let last = &row[row_size - bytes_per_pixel..];
+            row.iter()
+                .chain(
+                    last.iter()
+                        .cycle()
+                        .take((padded - width) as usize * bytes_per_pixel),
+                )
+                .copied()
+        })
+        .collect()
+}
@@ -0,0 +1,336 @@
+//! Animated textures, stored as a sequence of frames sharing their size and format.
+//!
+//! Frames come from the frames of an APNG or from a strip PNG, where they are laid out side by
+//! side. Color-indexed frames share a single palette, so an animation only needs one TLUT load.
+
+use crate::analysis::quality::encode_format;
+use crate::color::Color;
+use crate::image::canvas::{crop, pad_rows, row_width};
+use crate::palette::shared::build_shared_palette;
+use crate::{Error, ImageFormat, ImageSize, ImageType, NativeImage, PNGImage};
+use std::io::Write;
+
+/// How the frames of a strip PNG are laid out.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum StripLayout {
+    /// Frames are side by side, from left to right.
+    #[default]
+    Horizontal,
+    /// Frames are stacked, from top to bottom.
+    Vertical,
+}
+
+/// An animated texture.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Flipbook {
+    pub format: ImageType,
+    /// Size of each frame.
+    pub width: u32,
+    pub height: u32,
+    /// Native texel data of each frame, in playback order, with 4-bit rows padded to a whole byte.
+    pub frames: Vec<Vec<u8>>,
+    /// RGBA16 palette shared by every frame of a color-indexed flipbook.
+    pub tlut: Option<Vec<u8>>,
+}
+
+/// Splits a strip PNG into `frames` frames of equal size.
+///
+/// Frames of an indexed PNG keep its palette, others are RGBA8.
+pub fn split_strip(
+    png: &PNGImage,
+    layout: StripLayout,
+    frames: u32,
+) -> Result<Vec<PNGImage>, Error> {
+    let length = match layout {
+        StripLayout::Horizontal => png.width(),
+        StripLayout::Vertical => png.height(),
+    };
+    if frames == 0 || length % frames != 0 {
+        return Err(Error::UnevenStrip { length, frames });
+    }
+
+    let (width, height) = match layout {
+        StripLayout::Horizontal => (png.width() / frames, png.height()),
+        StripLayout::Vertical => (png.width(), png.height() / frames),
+    };
+    let origin = |frame: u32| match layout {
+        StripLayout::Horizontal => (frame * width, 0),
+        StripLayout::Vertical => (0, frame * height),
+    };
+
+    let images = match (png.indices(), png.palette()) {
+        (Some(indices), Some(palette)) => (0..frames)
+            .map(|frame| {
+                let (x, y) = origin(frame);
+                let indices = crop(&indices, png.width(), 1, x, y, width, height);
+                PNGImage::from_indexed(width, height, indices, &palette)
+            })
+            .collect(),
+        _ => {
+            let rgba = png.as_rgba8();
+            (0..frames)
+                .map(|frame| {
+                    let (x, y) = origin(frame);
+                    let pixels = crop(&rgba, png.width(), 4, x, y, width, height);
+                    PNGImage::from_rgba8(width, height, pixels)
+                })
+                .collect()
+        }
+    };
+
+    Ok(images)
+}
+
+impl Flipbook {
+    /// Encodes every frame as `format`.
+    ///
+    /// Color-indexed frames share a palette built by [`build_shared_palette`], so the palettes
+    /// of indexed frames are merged rather than requantized.
+    pub fn from_frames(frames: &[PNGImage], format: ImageType) -> Result<Self, Error> {
+        let first = frames.first().ok_or(Error::EmptyFlipbook)?;
+        let (width, height) = (first.width(), first.height());
+        if let Some(frame) = frames
+            .iter()
+            .find(|frame| (frame.width(), frame.height()) != (width, height))
+        {
+            return Err(Error::DimensionMismatch {
+                expected: (width, height),
+                actual: (frame.width(), frame.height()),
+            });
+        }
+
+        let (frames, tlut) = if format.get_format() == ImageFormat::Ci {
+            let shared = build_shared_palette(frames, format)?;
+            let data = shared
+                .images
+                .iter()
+                .map(|image| {
+                    let mut data = Vec::new();
+                    pad_frame(image, format).as_native(&mut data, format)?;
+                    Ok(data)
+                })
+                .collect::<Result<Vec<_>, Error>>()?;
+            (data, Some(shared.tlut()))
+        } else {
+            let data = frames
+                .iter()
+                .map(|frame| Ok(encode_format(&pad_frame(frame, format), format)?.0))
+                .collect::<Result<Vec<_>, Error>>()?;
+            (data, None)
+        };
+
+        Ok(Flipbook {
+            format,
+            width,
+            height,
+            frames,
+            tlut,
+        })
+    }
+
+    /// Splits native texel data holding consecutive frames of `width`x`height` texels.
+    pub fn from_native(
+        format: ImageType,
+        width: u32,
+        height: u32,
+        data: &[u8],
+        tlut: Option<Vec<u8>>,
+    ) -> Result<Self, Error> {
+        let frame_size = ((width * format.get_size().get_bpp()).div_ceil(8) * height) as usize;
+        if data.is_empty() || frame_size == 0 || data.len() % frame_size != 0 {
+            return Err(Error::UnevenFrames {
+                size: data.len(),
+                frame_size,
+            });
+        }
+
+        Ok(Flipbook {
+            format,
+            width,
+            height,
+            frames: data.chunks_exact(frame_size).map(<[u8]>::to_vec).collect(),
+            tlut,
+        })
+    }
+
+    /// Returns the texel data of every frame, one after the other.
+    pub fn data(&self) -> Vec<u8> {
+        self.frames.concat()
+    }
+
+    /// Returns a frame as a native image.
+    pub fn frame(&self, index: usize) -> Option<NativeImage> {
+        self.frames.get(index).map(|data| NativeImage {
+            format: self.format,
+            width: self.width,
+            height: self.height,
+            data: data.clone(),
+        })
+    }
+
+    /// Decodes every frame to a PNG image.
+    ///
+    /// Color-indexed frames are indexed PNGs using the shared palette, others are RGBA8.
+    pub fn decode_frames(&self) -> Result<Vec<PNGImage>, Error> {
+        let palette = match (self.format.get_format(), &self.tlut) {
+            (ImageFormat::Ci, Some(tlut)) => Some(
+                tlut.chunks_exact(2)
+                    .map(|c| Color::from_u16(u16::from_be_bytes([c[0], c[1]])))
+                    .collect::<Vec<_>>(),
+            ),
+            (ImageFormat::Ci, None) => return Err(Error::MissingTlut),
+            _ => None,
+        };
+
+        self.frames
+            .iter()
+            .map(|data| match &palette {
+                Some(palette) => {
+                    let indices = self.indices(data);
+                    if let Some(&index) = indices.iter().find(|&&i| i as usize >= palette.len()) {
+                        return Err(Error::PaletteIndexOutOfRange {
+                            index,
+                            entries: palette.len(),
+                        });
+                    }
+                    Ok(PNGImage::from_indexed(
+                        self.width,
+                        self.height,
+                        indices,
+                        palette,
+                    ))
+                }
+                None => {
+                    // 4-bit rows decode to their padded width, so the padding column is cropped
+                    let padded = row_width(self.format, self.width);
+                    let native = NativeImage {
+                        format: self.format,
+                        width: padded,
+                        height: self.height,
+                        data: data.clone(),
+                    };
+                    let mut rgba = Vec::new();
+                    native.decode(&mut rgba, None)?;
+                    let rgba = crop(&rgba, padded, 4, 0, 0, self.width, self.height);
+                    Ok(PNGImage::from_rgba8(self.width, self.height, rgba))
+                }
+            })
+            .collect()
+    }
+
+    /// Writes the flipbook as an APNG looping forever, showing each frame for
+    /// `delay_num / delay_den` seconds.
+    pub fn write_apng<W: Write>(
+        &self,
+        writer: &mut W,
+        delay_num: u16,
+        delay_den: u16,
+    ) -> Result<(), Error> {
+        let frames = self.decode_frames()?;
+        let first = frames.first().ok_or(Error::EmptyFlipbook)?;
+
+        let mut encoder = png::Encoder::new(writer, self.width, self.height);
+        encoder.set_animated(frames.len() as u32, 0)?;
+        encoder.set_frame_delay(delay_num, delay_den)?;
+        match first.palette() {
+            Some(palette) => {
+                encoder.set_color(png::ColorType::Indexed);
+                encoder.set_palette(
+                    palette
+                        .iter()
+                        .flat_map(|c| [c.r, c.g, c.b])
+                        .collect::<Vec<_>>(),
+                );
+                encoder.set_trns(palette.iter().map(|c| c.a).collect::<Vec<_>>());
+            }
+            None => encoder.set_color(png::ColorType::Rgba),
+        }
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let mut writer = encoder.write_header()?;
+        for frame in &frames {
+            match frame.indices() {
+                Some(indices) => writer.write_image_data(&indices)?,
+                None => writer.write_image_data(&frame.as_rgba8())?,
+            }
+        }
+        writer.finish()?;
+
+        Ok(())
+    }
+
+    /// Writes the flipbook as a strip PNG with the frames laid out as `layout`.
+    pub fn write_strip<W: Write>(&self, writer: &mut W, layout: StripLayout) -> Result<(), Error> {
+        let frames = self.decode_frames()?;
+        let count = frames.len() as u32;
+        let (width, height) = match layout {
+            StripLayout::Horizontal => (self.width * count, self.height),
+            StripLayout::Vertical => (self.width, self.height * count),
+        };
+
+        let palette = frames.first().and_then(PNGImage::palette);
+        let (pixels, bytes_per_pixel): (Vec<Vec<u8>>, usize) = match &palette {
+            Some(_) => (frames.iter().filter_map(PNGImage::indices).collect(), 1),
+            None => (frames.iter().map(PNGImage::as_rgba8).collect(), 4),
+        };
+
+        // Horizontal strips interleave the rows of every frame, vertical strips concatenate them
+        let row_size = self.width as usize * bytes_per_pixel;
+        let data = match layout {
+            StripLayout::Horizontal => (0..self.height as usize)
+                .flat_map(|y| {
+                    pixels
+                        .iter()
+                        .flat_map(move |frame| &frame[y * row_size..(y + 1) * row_size])
+                })
+                .copied()
+                .collect(),
+            StripLayout::Vertical => pixels.concat(),
+        };
+
+        let strip = match &palette {
+            Some(palette) => PNGImage::from_indexed(width, height, data, palette),
+            None => PNGImage::from_rgba8(width, height, data),
+        };
+        strip.as_png(writer)
+    }
+
+    /// Returns the palette index of each texel of a color-indexed frame.
+    fn indices(&self, data: &[u8]) -> Vec<u8> {
+        match self.format.get_size() {
+            ImageSize::Bits4 => {
+                let row_bytes = self.width.div_ceil(2) as usize;
+                data.chunks_exact(row_bytes)
+                    .flat_map(|row| {
+                        (0..self.width as usize).map(move |x| (row[x / 2] >> (4 - x % 2 * 4)) & 0xF)
+                    })
+                    .collect()
+            }
+            _ => data.to_vec(),
+        }
+    }
+}
+
+/// Pads the rows of a frame to the width `format` stores them at, repeating their last pixel, as
+/// native 4-bit encoders pack texels across rows.
+fn pad_frame(frame: &PNGImage, format: ImageType) -> PNGImage {
+    let (width, height) = (frame.width(), frame.height());
+    let padded = row_width(format, width);
+    if padded == width {
+        return frame.clone();
+    }
+
+    match (frame.indices(), frame.palette()) {
+        (Some(indices), Some(palette)) => PNGImage::from_indexed(
+            padded,
+            height,
+            pad_rows(&indices, width, 1, padded),
+            &palette,
+        ),
+        _ => PNGImage::from_rgba8(
+            padded,
+            height,
+            pad_rows(&frame.as_rgba8(), width, 4, padded),
+        ),
+    }
+}
@@ -0,0 +1,318 @@
+//! Support for the `.sprite` files of the libdragon homebrew SDK, as produced by its `mksprite`
+//! tool.
+//!
+//! A sprite starts with an 8-byte header holding its size, `tex_format_t` and slices, followed by
+//! the texels. Palettes and mipmaps are described by an extended header stored after the texels,
+//! which points to the absolute file offset of each. Sprites are written with the version 4
+//! extended header current libdragon requires, and read with any version since they all start
+//! with the same palette and level of detail fields.
+
+use crate::gbi::mipmap::{MipmapFilter, encode_levels};
+use crate::image::native_image::parse_tlut_entries;
+use crate::{Error, ImageFormat, ImageSize, ImageType, NativeImage, PNGImage, TextureLUT};
+use std::io::{Read, Write};
+
+/// Bits of the header flags holding the `tex_format_t` of the sprite.
+const SPRITE_FLAGS_TEXFORMAT: u8 = 0x1F;
+/// Header flag set when the extended header is present.
+const SPRITE_FLAGS_EXT: u8 = 0x80;
+/// Size of the header before the texels.
+const SPRITE_HEADER_SIZE: usize = 8;
+/// Size of the version 4 extended header, `sizeof(sprite_ext_t)`.
+const SPRITE_EXT_SIZE: usize = 124;
+/// Version of the extended header written by `mksprite`.
+const SPRITE_EXT_VERSION: u16 = 4;
+/// Offset of the flags in the extended header, after the palette and level of detail fields.
+const SPRITE_EXT_FLAGS: usize = 8 + MAX_SPRITE_LODS * 8;
+/// Bits of the extended header flags holding the number of levels of detail.
+const SPRITE_FLAG_NUMLODS: u16 = 0x0007;
+/// Most levels of detail the extended header describes, besides the sprite itself.
+pub const MAX_SPRITE_LODS: usize = 7;
+
+/// A lower level of detail of a sprite.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpriteLod {
+    pub format: ImageType,
+    pub width: u32,
+    pub height: u32,
+    /// Native texel data of the level.
+    pub data: Vec<u8>,
+}
+
+/// A libdragon sprite.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LibdragonSprite {
+    pub format: ImageType,
+    pub width: u32,
+    pub height: u32,
+    /// Native texel data of the sprite.
+    pub data: Vec<u8>,
+    /// Number of horizontal slices the sprite is split into by `rdp_load_texture_stride`.
+    pub hslices: u8,
+    /// Number of vertical slices the sprite is split into by `rdp_load_texture_stride`.
+    pub vslices: u8,
+    /// RGBA16 TLUT of a color-indexed sprite, shared by its levels of detail.
+    pub tlut: Option<Vec<u8>>,
+    /// Lower levels of detail, each half the size of the previous one.
+    pub lods: Vec<SpriteLod>,
+}
+
+/// Returns the libdragon `tex_format_t` value of an image type.
+pub fn tex_format(format: ImageType) -> Result<u8, Error> {
+    match format {
+        ImageType::I1 => Err(Error::UnsupportedSpriteFormat(format)),
+        _ => Ok((format.get_format() as u8) << 2 | format.get_size() as u8),
+    }
+}
+
+/// Returns the image type of a libdragon `tex_format_t` value.
+pub fn from_tex_format(value: u8) -> Result<ImageType, Error> {
+    let format = ImageFormat::try_from(value >> 2);
+    let size = ImageSize::try_from(value & 0x3);
+
+    match (format, size) {
+        (Ok(format), Ok(size)) => {
+            ImageType::from_format_and_size(format, size).filter(|format| *format != ImageType::I1)
+        }
+        _ => None,
+    }
+    .ok_or_else(|| Error::InvalidSprite(format!("unsupported texture format {value}")))
+}
+
+impl LibdragonSprite {
+    /// Encodes a PNG as a sprite with `mipmap_levels` levels of detail besides itself.
+    ///
+    /// Levels of detail halve the dimensions of the previous one with a box filter until either
+    /// reaches 1, and color-indexed levels share the palette of the sprite. Like `mksprite`,
+    /// they don't need to fit in TMEM together.
+    pub fn from_png(
+        png: &PNGImage,
+        format: ImageType,
+        mipmap_levels: usize,
+    ) -> Result<Self, Error> {
+        tex_format(format)?;
+
+        // Every level is encoded with its rows padded to a whole byte, as `TEX_FORMAT_PIX2BYTES`
+        // expects
+        let levels = mipmap_levels.min(MAX_SPRITE_LODS) + 1;
+        let (levels, tlut) = encode_levels(png, format, MipmapFilter::Box, levels)?;
+        let mut levels = levels.into_iter();
+        let data = levels.next().map(|(_, _, data)| data).unwrap_or_default();
+        let lods = levels
+            .map(|(width, height, data)| SpriteLod {
+                format,
+                width,
+                height,
+                data,
+            })
+            .collect();
+
+        Ok(LibdragonSprite {
+            format,
+            width: png.width(),
+            height: png.height(),
+            data,
+            hslices: 1,
+            vslices: 1,
+            tlut,
+            lods,
+        })
+    }
+
+    /// Reads a sprite, including the palette and levels of detail of its extended header.
+    ///
+    /// Sprites written before `tex_format_t` was stored in the header are read as RGBA16 or
+    /// RGBA32 according to their bit depth.
+    pub fn read<R: Read>(mut reader: R) -> Result<Self, Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+
+        let header = bytes
+            .get(..SPRITE_HEADER_SIZE)
+            .ok_or_else(|| invalid("file is shorter than the sprite header"))?;
+        let width = u16::from_be_bytes([header[0], header[1]]) as u32;
+        let height = u16::from_be_bytes([header[2], header[3]]) as u32;
+        let (bitdepth, flags) = (header[4], header[5]);
+
+        let format = match (flags & SPRITE_FLAGS_TEXFORMAT, bitdepth) {
+            (0, 2) => ImageType::Rgba16,
+            (0, 4) => ImageType::Rgba32,
+            (value, _) => from_tex_format(value)?,
+        };
+
+        let size = byte_size(format, width, height);
+        let data = slice(&bytes, SPRITE_HEADER_SIZE, size)?.to_vec();
+
+        let mut sprite = LibdragonSprite {
+            format,
+            width,
+            height,
+            data,
+            hslices: header[6],
+            vslices: header[7],
+            tlut: None,
+            lods: Vec::new(),
+        };
+
+        if flags & SPRITE_FLAGS_EXT == 0 {
+            return Ok(sprite);
+        }
+
+        let ext_offset = (SPRITE_HEADER_SIZE + size).next_multiple_of(8);
+        let ext = slice(&bytes, ext_offset, SPRITE_EXT_FLAGS)?;
+
+        let palette_offset = read_u32(ext, 4) as usize;
+        if palette_offset != 0 {
+            let entries = format.get_size().get_tlut_size().unwrap_or_default();
+            let tlut = bytes
+                .get(palette_offset..)
+                .ok_or_else(|| invalid("palette is past the end of the file"))?;
+            sprite.tlut = Some(tlut[..tlut.len().min(entries * 2)].to_vec());
+        }
+
+        for lod in ext[8..].chunks_exact(8) {
+            let width = u16::from_be_bytes([lod[0], lod[1]]) as u32;
+            let height = u16::from_be_bytes([lod[2], lod[3]]) as u32;
+            if width == 0 || height == 0 {
+                break;
+            }
+
+            let position = read_u32(lod, 4);
+            let format = from_tex_format((position >> 24) as u8)?;
+            let offset = (position & 0x00FF_FFFF) as usize;
+            let data = slice(&bytes, offset, byte_size(format, width, height))?.to_vec();
+            sprite.lods.push(SpriteLod {
+                format,
+                width,
+                height,
+                data,
+            });
+        }
+
+        Ok(sprite)
+    }
+
+    /// Writes the sprite to the given writer.
+    ///
+    /// The extended header is only written for sprites with a palette or levels of detail, and
+    /// palettes are padded to the 16 or 256 entries libdragon expects.
+    pub fn write<W: Write>(&self, writer: &mut W) -> Result<(), Error> {
+        if self.lods.len() > MAX_SPRITE_LODS {
+            return Err(invalid(&format!(
+                "sprites hold at most {MAX_SPRITE_LODS} levels of detail, got {}",
+                self.lods.len()
+            )));
+        }
+
+        let extended = self.tlut.is_some() || !self.lods.is_empty();
+        let mut flags = tex_format(self.format)?;
+        if extended {
+            flags |= SPRITE_FLAGS_EXT;
+        }
+
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&dimension(self.width)?.to_be_bytes());
+        bytes.extend_from_slice(&dimension(self.height)?.to_be_bytes());
+        bytes.push((self.format.get_size().get_bpp() / 8) as u8);
+        bytes.push(flags);
+        bytes.push(self.hslices);
+        bytes.push(self.vslices);
+        bytes.extend_from_slice(&self.data);
+
+        if extended {
+            align(&mut bytes);
+            let ext_offset = bytes.len();
+            bytes.resize(ext_offset + SPRITE_EXT_SIZE, 0);
+            bytes[ext_offset..ext_offset + 2]
+                .copy_from_slice(&(SPRITE_EXT_SIZE as u16).to_be_bytes());
+            bytes[ext_offset + 2..ext_offset + 4]
+                .copy_from_slice(&SPRITE_EXT_VERSION.to_be_bytes());
+            let flags = self.lods.len() as u16 & SPRITE_FLAG_NUMLODS;
+            bytes[ext_offset + SPRITE_EXT_FLAGS..ext_offset + SPRITE_EXT_FLAGS + 2]
+                .copy_from_slice(&flags.to_be_bytes());
+
+            for (i, lod) in self.lods.iter().enumerate() {
+                align(&mut bytes);
+                let position = (tex_format(lod.format)? as u32) << 24 | bytes.len() as u32;
+                let entry = ext_offset + 8 + i * 8;
+                bytes[entry..entry + 2].copy_from_slice(&dimension(lod.width)?.to_be_bytes());
+                bytes[entry + 2..entry + 4].copy_from_slice(&dimension(lod.height)?.to_be_bytes());
+                bytes[entry + 4..entry + 8].copy_from_slice(&position.to_be_bytes());
+                bytes.extend_from_slice(&lod.data);
+            }
+
+            if let Some(tlut) = &self.tlut {
+                align(&mut bytes);
+                let position = bytes.len() as u32;
+                bytes[ext_offset + 4..ext_offset + 8].copy_from_slice(&position.to_be_bytes());
+
+                let entries = self.format.get_size().get_tlut_size().unwrap_or_default();
+                let mut tlut = tlut.clone();
+                tlut.resize(tlut.len().max(entries * 2), 0);
+                bytes.extend_from_slice(&tlut);
+            }
+        }
+
+        writer.write_all(&bytes)?;
+        Ok(())
+    }
+
+    /// Returns the texels of the sprite as a native image.
+    pub fn as_native(&self) -> NativeImage {
+        NativeImage {
+            format: self.format,
+            width: self.width,
+            height: self.height,
+            data: self.data.clone(),
+        }
+    }
+
+    /// Writes the sprite as PNG, using its palette for color-indexed sprites.
+    pub fn as_png<W: Write>(&self, writer: &mut W) -> Result<(), Error> {
+        let table = match &self.tlut {
+            Some(tlut) => Some(parse_tlut_entries(
+                tlut,
+                tlut.len() / 2,
+                TextureLUT::Rgba16,
+            )?),
+            None => None,
+        };
+
+        self.as_native().as_png(writer, table.as_deref())
+    }
+}
+
+// MARK: - Helpers
+
+fn byte_size(format: ImageType, width: u32, height: u32) -> usize {
+    ((width * format.get_size().get_bpp()).div_ceil(8) * height) as usize
+}
+
+fn dimension(value: u32) -> Result<u16, Error> {
+    u16::try_from(value).map_err(|_| invalid(&format!("dimension {value} does not fit in 16 bits")))
+}
+
+fn align(bytes: &mut Vec<u8>) {
+    bytes.resize(bytes.len().next_multiple_of(8), 0);
+}
+
+fn slice(bytes: &[u8], offset: usize, size: usize) -> Result<&[u8], Error> {
+    bytes.get(offset..offset + size).ok_or_else(|| {
+        invalid(&format!(
+            "{size} bytes at offset {offset} are past the end of the file"
+        ))
+    })
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_be_bytes([
+        bytes[offset],
+        bytes[offset + 1],
+        bytes[offset + 2],
+        bytes[offset + 3],
+    ])
+}
+
+fn invalid(reason: &str) -> Error {
+    Error::InvalidSprite(reason.to_string())
+}
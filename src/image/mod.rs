@@ -1,2 +1,7 @@
+pub mod atlas;
+pub(crate) mod canvas;
+pub mod flipbook;
+pub mod libdragon;
 pub mod native_image;
 pub mod png_image;
+pub mod quantize;
@@ -10,6 +10,18 @@ pub struct NativeImage {
     pub data: Vec<u8>,
 }
 
+/// How color-indexed images treat indices past the last entry of their color table.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum TlutIndexPolicy {
+    /// Fails with [`Error::PaletteIndexOutOfRange`].
+    #[default]
+    Error,
+    /// Uses the last entry of the color table.
+    Clamp,
+    /// Uses the given color, making stray indices easy to spot.
+    Marker(Color),
+}
+
 impl NativeImage {
     pub fn read<R: Read>(
         mut reader: R,
@@ -33,6 +45,17 @@ impl NativeImage {
         &self,
         writer: &mut W,
         tlut_color_table: Option<&[u8]>,
+    ) -> Result<(), Error> {
+        self.decode_with_policy(writer, tlut_color_table, TlutIndexPolicy::Error)
+    }
+
+    /// Decodes the image like [`Self::decode`], resolving indices past the end of
+    /// `tlut_color_table` with `policy`.
+    pub fn decode_with_policy<W: Write>(
+        &self,
+        writer: &mut W,
+        tlut_color_table: Option<&[u8]>,
+        policy: TlutIndexPolicy,
     ) -> Result<(), Error> {
         let mut cursor = Cursor::new(&self.data);
 
@@ -118,10 +141,10 @@ impl NativeImage {
                         let byte = cursor.read_u8()?;
 
                         let index = (byte >> 4) & 0x0F;
-                        writer.write_all(&get_tlut_color_at_index(tlut, index)?)?;
+                        writer.write_all(&tlut_color(tlut, index, policy)?)?;
 
                         let index = byte & 0x0F;
-                        writer.write_all(&get_tlut_color_at_index(tlut, index)?)?;
+                        writer.write_all(&tlut_color(tlut, index, policy)?)?;
                     }
                 }
             }
@@ -131,7 +154,7 @@ impl NativeImage {
                 for _y in 0..self.height {
                     for _x in 0..self.width {
                         let index = cursor.read_u8()?;
-                        writer.write_all(&get_tlut_color_at_index(tlut, index)?)?;
+                        writer.write_all(&tlut_color(tlut, index, policy)?)?;
                     }
                 }
             }
@@ -164,6 +187,17 @@ impl NativeImage {
         &self,
         writer: &mut W,
         tlut_color_table: Option<&[u8]>,
+    ) -> Result<(), Error> {
+        self.as_png_with_policy(writer, tlut_color_table, TlutIndexPolicy::Error)
+    }
+
+    /// Writes the image as PNG like [`Self::as_png`], resolving indices past the end of
+    /// `tlut_color_table` with `policy`. Marker colors are appended to the PNG palette.
+    pub fn as_png_with_policy<W: Write>(
+        &self,
+        writer: &mut W,
+        tlut_color_table: Option<&[u8]>,
+        policy: TlutIndexPolicy,
     ) -> Result<(), Error> {
         let mut data: Vec<u8> = vec![];
         let mut encoder = png::Encoder::new(writer, self.width, self.height);
@@ -181,18 +215,27 @@ impl NativeImage {
                 let tlut = tlut_color_table.ok_or(Error::MissingTlut)?;
                 let mut cursor = Cursor::new(&self.data);
                 let mut data: Vec<u8> = vec![];
+                let mut marked = false;
 
                 for _y in 0..self.height {
-                    for _x in (0..self.width).step_by(2) {
+                    for x in (0..self.width).step_by(2) {
                         let byte = cursor.read_u8()?;
-                        data.push(byte);
+                        let high = png_index(tlut, byte >> 4, policy, &mut marked)?;
+                        // The low nibble of the last byte of odd-width rows is padding
+                        let low = if x + 1 < self.width {
+                            png_index(tlut, byte & 0x0F, policy, &mut marked)?
+                        } else {
+                            0
+                        };
+                        data.push(high << 4 | low);
                     }
                 }
 
                 encoder.set_color(png::ColorType::Indexed);
                 encoder.set_depth(png::BitDepth::Four);
 
-                let (palette_data, trans_data) = split_color_table_for_png(tlut);
+                let (palette_data, trans_data) =
+                    split_color_table_for_png(&marked_table(tlut, policy, marked));
 
                 encoder.set_palette(palette_data);
                 encoder.set_trns(trans_data);
@@ -206,18 +249,20 @@ impl NativeImage {
                 let tlut = tlut_color_table.ok_or(Error::MissingTlut)?;
                 let mut cursor = Cursor::new(&self.data);
                 let mut data: Vec<u8> = vec![];
+                let mut marked = false;
 
                 for _y in 0..self.height {
                     for _x in 0..self.width {
                         let index = cursor.read_u8()?;
-                        data.push(index);
+                        data.push(png_index(tlut, index, policy, &mut marked)?);
                     }
                 }
 
                 encoder.set_color(png::ColorType::Indexed);
                 encoder.set_depth(png::BitDepth::Eight);
 
-                let (palette_data, trans_data) = split_color_table_for_png(tlut);
+                let (palette_data, trans_data) =
+                    split_color_table_for_png(&marked_table(tlut, policy, marked));
 
                 encoder.set_palette(palette_data);
                 encoder.set_trns(trans_data);
@@ -244,6 +289,31 @@ impl NativeImage {
         Ok(())
     }
 
+    /// Writes the image as PNG like [`Self::as_png`], with CI4 images using bank `bank` of
+    /// `tlut_color_table`, an RGBA8 color table of up to 256 entries.
+    pub fn as_png_with_palette_bank<W: Write>(
+        &self,
+        writer: &mut W,
+        tlut_color_table: &[u8],
+        bank: u8,
+    ) -> Result<(), Error> {
+        let size = self.format.get_size();
+        if self.format != ImageType::Ci4 {
+            if bank != 0 {
+                return Err(Error::InvalidPaletteBank { bank, size });
+            }
+            return self.as_png(writer, Some(tlut_color_table));
+        }
+
+        let start = bank as usize * 16 * 4;
+        if bank >= 16 || start >= tlut_color_table.len() {
+            return Err(Error::InvalidPaletteBank { bank, size });
+        }
+        let end = tlut_color_table.len().min(start + 16 * 4);
+
+        self.as_png(writer, Some(&tlut_color_table[start..end]))
+    }
+
     pub fn swap_word_rows(&mut self) {
         let bpp = self.format.get_size().get_bpp();
         // Use ceiling division to handle non-byte-aligned widths correctly
@@ -275,41 +345,129 @@ impl NativeImage {
 
 /// Parses a tlut into a RGBA8 color table
 pub fn parse_tlut(bytes: &[u8], size: ImageSize, mode: TextureLUT) -> Result<Vec<u8>, Error> {
-    if mode != TextureLUT::Rgba16 {
+    let tlut_size = size
+        .get_tlut_size()
+        .ok_or(Error::InvalidSizeForTlut(size))?;
+
+    parse_tlut_entries(bytes, tlut_size, mode)
+}
+
+/// Parses the first `entries` entries of a tlut into a RGBA8 color table.
+///
+/// This supports partial TLUTs, such as CI4 palettes of 8 colors. Decoding an image whose
+/// indices go past the parsed entries fails.
+pub fn parse_tlut_entries(
+    bytes: &[u8],
+    entries: usize,
+    mode: TextureLUT,
+) -> Result<Vec<u8>, Error> {
+    if mode == TextureLUT::None {
         return Err(Error::UnsupportedTlutMode(mode));
     }
+    if bytes.len() / 2 < entries {
+        return Err(Error::TlutTooShort {
+            expected: entries,
+            actual: bytes.len() / 2,
+        });
+    }
 
-    let mut output: Vec<u8> = vec![];
+    let mut output: Vec<u8> = Vec::with_capacity(entries * 4);
     let cursor = &mut Cursor::new(bytes);
 
+    for _i in 0..entries {
+        let pixel = cursor.read_u16::<BigEndian>()?;
+        output.write_all(&tlut_entry_to_rgba(pixel, mode))?;
+    }
+
+    Ok(output)
+}
+
+/// Parses bank `bank` of a tlut into a RGBA8 color table.
+///
+/// CI4 images can select one of 16 banks of 16 entries in a full 256-entry TLUT, while CI8
+/// images always use the whole TLUT, so only bank 0 is valid for them.
+pub fn parse_tlut_bank(
+    bytes: &[u8],
+    size: ImageSize,
+    mode: TextureLUT,
+    bank: u8,
+) -> Result<Vec<u8>, Error> {
     let tlut_size = size
         .get_tlut_size()
         .ok_or(Error::InvalidSizeForTlut(size))?;
+    let offset = bank as usize * tlut_size * 2;
 
-    for _i in 0..tlut_size {
-        let pixel = cursor.read_u16::<BigEndian>()?;
-        let color = Color::from_u16(pixel);
-        output.write_all(&[color.r, color.g, color.b, color.a])?;
+    if (size != ImageSize::Bits4 && bank != 0) || bank >= 16 || offset >= bytes.len() {
+        return Err(Error::InvalidPaletteBank { bank, size });
     }
 
-    Ok(output)
+    parse_tlut(&bytes[offset..], size, mode)
 }
 
-/// Reads an rgba color from a buffer starting at the given offset
-fn get_tlut_color_at_index(tlut_color_table: &[u8], index: u8) -> Result<[u8; 4], Error> {
-    let start = (index * 4) as usize;
-    let end = start + 4;
-
-    if end > tlut_color_table.len() {
-        return Err(Error::TlutIndexOutOfBounds);
+/// Converts a single TLUT entry into an RGBA8 color
+pub(crate) fn tlut_entry_to_rgba(entry: u16, mode: TextureLUT) -> [u8; 4] {
+    match mode {
+        TextureLUT::Ia16 => {
+            let intensity = (entry >> 8) as u8;
+            let alpha = (entry & 0xFF) as u8;
+            [intensity, intensity, intensity, alpha]
+        }
+        _ => {
+            let color = Color::from_u16(entry);
+            [color.r, color.g, color.b, color.a]
+        }
     }
+}
 
-    let r = tlut_color_table[start];
-    let g = tlut_color_table[start + 1];
-    let b = tlut_color_table[start + 2];
-    let a = tlut_color_table[start + 3];
+/// Reads the RGBA8 color of an index from a color table, applying `policy` to indices past its
+/// last entry
+fn tlut_color(
+    tlut_color_table: &[u8],
+    index: u8,
+    policy: TlutIndexPolicy,
+) -> Result<[u8; 4], Error> {
+    let entries = tlut_color_table.len() / 4;
+    let index = match policy {
+        _ if (index as usize) < entries => index as usize,
+        TlutIndexPolicy::Clamp if entries > 0 => entries - 1,
+        TlutIndexPolicy::Marker(color) => return Ok([color.r, color.g, color.b, color.a]),
+        _ => return Err(Error::PaletteIndexOutOfRange { index, entries }),
+    };
+
+    let start = index * 4;
+    let mut color = [0; 4];
+    color.copy_from_slice(&tlut_color_table[start..start + 4]);
+    Ok(color)
+}
+
+/// Maps an index to the palette of an indexed PNG, applying `policy` to indices past the last
+/// entry of the color table. Marker colors use the entry right after the color table, and set
+/// `marked` when they are used.
+fn png_index(
+    tlut_color_table: &[u8],
+    index: u8,
+    policy: TlutIndexPolicy,
+    marked: &mut bool,
+) -> Result<u8, Error> {
+    let entries = tlut_color_table.len() / 4;
+    match policy {
+        _ if (index as usize) < entries => Ok(index),
+        TlutIndexPolicy::Clamp if entries > 0 => Ok((entries - 1) as u8),
+        TlutIndexPolicy::Marker(_) => {
+            *marked = true;
+            Ok(entries as u8)
+        }
+        _ => Err(Error::PaletteIndexOutOfRange { index, entries }),
+    }
+}
 
-    Ok([r, g, b, a])
+/// Appends the marker color of `policy` to a color table when an index used it
+fn marked_table(tlut_color_table: &[u8], policy: TlutIndexPolicy, marked: bool) -> Vec<u8> {
+    let mut table = tlut_color_table.to_vec();
+    if let (TlutIndexPolicy::Marker(color), true) = (policy, marked) {
+        table.extend_from_slice(&[color.r, color.g, color.b, color.a]);
+    }
+    table
 }
 
 /// Splits a color table into a palette and a transparency table for png encoding
@@ -1,7 +1,9 @@
 use crate::color::Color;
+use crate::image::quantize::{nearest_color, quantize};
+use crate::palette::banks::palette_bank;
 use crate::{Error, ImageType};
 use byteorder::{BigEndian, WriteBytesExt};
-use png::{BitDepth, ColorType};
+use png::{BitDepth, BlendOp, ColorType, DisposeOp, FrameControl};
 use std::io::{Read, Write};
 
 #[derive(Debug, Clone)]
@@ -16,6 +18,10 @@ pub struct PNGImage {
     width: u32,
     /// The height of the image in pixels.
     height: u32,
+    /// The RGB palette of indexed images.
+    palette: Option<Vec<u8>>,
+    /// The transparency of each palette entry of indexed images.
+    trns: Option<Vec<u8>>,
 }
 
 #[inline]
@@ -37,9 +43,170 @@ impl PNGImage {
             bit_depth: info.bit_depth,
             width: info.width,
             height: info.height,
+            palette: reader.info().palette.as_ref().map(|p| p.to_vec()),
+            trns: reader.info().trns.as_ref().map(|t| t.to_vec()),
         })
     }
 
+    /// Reads every frame of an animated PNG, as shown by a viewer once composited.
+    ///
+    /// Frames covering the whole image without blending keep their color type and palette,
+    /// other frames are composited onto the previous ones as RGBA8. A PNG without animation is a
+    /// single frame, and a default image that is not part of the animation is skipped.
+    pub fn read_frames<R: Read>(r: R) -> Result<Vec<Self>, Error> {
+        let decoder = png::Decoder::new(r);
+        let mut reader = decoder.read_info()?;
+        let mut buf = vec![0; reader.output_buffer_size()];
+
+        let info = reader.info();
+        let (width, height) = (info.width, info.height);
+        let palette = info.palette.as_ref().map(|p| p.to_vec());
+        let trns = info.trns.as_ref().map(|t| t.to_vec());
+        let frame_count = info.animation_control.map_or(1, |a| a.num_frames);
+        if info.animation_control.is_some() && info.frame_control.is_none() {
+            reader.next_frame(&mut buf)?;
+        }
+
+        let mut canvas = vec![0; (width * height * 4) as usize];
+        let mut frames = Vec::with_capacity(frame_count as usize);
+
+        for index in 0..frame_count {
+            let output = reader.next_frame(&mut buf)?;
+            let control = reader.info().frame_control.unwrap_or(FrameControl {
+                width,
+                height,
+                ..Default::default()
+            });
+            let frame = PNGImage {
+                data: buf[..output.buffer_size()].to_vec(),
+                color_type: output.color_type,
+                bit_depth: output.bit_depth,
+                width: output.width,
+                height: output.height,
+                palette: palette.clone(),
+                trns: trns.clone(),
+            };
+
+            let previous = (control.dispose_op == DisposeOp::Previous).then(|| canvas.clone());
+            let pixels = frame.as_rgba8();
+            for y in 0..control.height {
+                for x in 0..control.width {
+                    let src = ((y * control.width + x) * 4) as usize;
+                    let dst =
+                        (((control.y_offset + y) * width + control.x_offset + x) * 4) as usize;
+                    let (Some(src), Some(dst)) =
+                        (pixels.get(src..src + 4), canvas.get_mut(dst..dst + 4))
+                    else {
+                        continue;
+                    };
+                    match control.blend_op {
+                        BlendOp::Source => dst.copy_from_slice(src),
+                        BlendOp::Over => blend_over(dst, src),
+                    }
+                }
+            }
+
+            let covers_image = control.width == width && control.height == height;
+            if covers_image && (control.blend_op == BlendOp::Source || index == 0) {
+                frames.push(frame);
+            } else {
+                frames.push(PNGImage::from_rgba8(width, height, canvas.clone()));
+            }
+
+            match (control.dispose_op, previous) {
+                (DisposeOp::Previous, Some(previous)) => canvas = previous,
+                (DisposeOp::Background, _) => {
+                    for y in control.y_offset..(control.y_offset + control.height).min(height) {
+                        let start = ((y * width + control.x_offset) * 4) as usize;
+                        let end = ((y * width + (control.x_offset + control.width).min(width)) * 4)
+                            as usize;
+                        canvas[start..end].fill(0);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(frames)
+    }
+
+    /// Creates an 8-bit RGBA image from RGBA8 pixels in row-major order.
+    pub fn from_rgba8(width: u32, height: u32, data: Vec<u8>) -> Self {
+        PNGImage {
+            data,
+            color_type: ColorType::Rgba,
+            bit_depth: BitDepth::Eight,
+            width,
+            height,
+            palette: None,
+            trns: None,
+        }
+    }
+
+    /// Creates an 8-bit indexed image from one palette index per pixel in row-major order.
+    pub fn from_indexed(width: u32, height: u32, indices: Vec<u8>, palette: &[Color]) -> Self {
+        let rgb = palette.iter().flat_map(|c| [c.r, c.g, c.b]).collect();
+        let trns = palette
+            .iter()
+            .any(|c| c.a != 0xFF)
+            .then(|| palette.iter().map(|c| c.a).collect());
+
+        PNGImage {
+            data: indices,
+            color_type: ColorType::Indexed,
+            bit_depth: BitDepth::Eight,
+            width,
+            height,
+            palette: Some(rgb),
+            trns,
+        }
+    }
+
+    /// Returns an indexed version of the image with at most `max_colors` palette entries.
+    ///
+    /// Indexed images whose palette is already small enough keep it, others are quantized with
+    /// [`quantize`]. The result always has one byte per pixel.
+    pub fn quantize(&self, max_colors: usize) -> PNGImage {
+        let palette = self.palette().unwrap_or_default();
+        if palette.len() <= max_colors {
+            if self.color_type == ColorType::Indexed && self.bit_depth == BitDepth::Eight {
+                return self.clone();
+            }
+            if let Some(indices) = self.indices() {
+                return PNGImage::from_indexed(self.width, self.height, indices, &palette);
+            }
+        }
+
+        let quantized = quantize(&self.as_rgba8(), max_colors);
+        PNGImage::from_indexed(
+            self.width,
+            self.height,
+            quantized.indices,
+            &quantized.palette,
+        )
+    }
+
+    /// Returns the palette index of each pixel of an indexed image, one byte per pixel.
+    pub fn indices(&self) -> Option<Vec<u8>> {
+        if self.color_type != ColorType::Indexed {
+            return None;
+        }
+
+        let bits = self.bit_depth as usize;
+        let row_bytes = (self.width as usize * bits).div_ceil(8);
+        Some(
+            self.data
+                .chunks_exact(row_bytes)
+                .flat_map(|row| {
+                    (0..self.width as usize).map(move |x| {
+                        let bit = x * bits;
+                        (row[bit / 8] >> (8 - bits - bit % 8)) & ((1u16 << bits) - 1) as u8
+                    })
+                })
+                .collect(),
+        )
+    }
+
     pub fn width(&self) -> u32 {
         self.width
     }
@@ -48,6 +215,22 @@ impl PNGImage {
         self.height
     }
 
+    /// Returns the palette of an indexed image. Entries without transparency are opaque.
+    pub fn palette(&self) -> Option<Vec<Color>> {
+        let palette = self.palette.as_ref()?;
+        let trns = self.trns.as_deref().unwrap_or_default();
+
+        Some(
+            palette
+                .chunks_exact(3)
+                .enumerate()
+                .map(|(i, rgb)| {
+                    Color::RGBA(rgb[0], rgb[1], rgb[2], trns.get(i).copied().unwrap_or(0xFF))
+                })
+                .collect(),
+        )
+    }
+
     pub fn flip(&self, flip_x: bool, flip_y: bool) -> PNGImage {
         let mut flipped_bytes = vec![0; self.data.len()];
         let samples = self.color_type.samples();
@@ -69,6 +252,8 @@ impl PNGImage {
             bit_depth: self.bit_depth,
             width: self.width,
             height: self.height,
+            palette: self.palette.clone(),
+            trns: self.trns.clone(),
         }
     }
 
@@ -77,11 +262,72 @@ impl PNGImage {
         let mut encoder = png::Encoder::new(writer, self.width, self.height);
         encoder.set_color(self.color_type);
         encoder.set_depth(self.bit_depth);
+        if let Some(palette) = &self.palette {
+            encoder.set_palette(palette.as_slice());
+        }
+        if let Some(trns) = &self.trns {
+            encoder.set_trns(trns.as_slice());
+        }
         let mut writer = encoder.write_header()?;
         writer.write_image_data(&self.data)?;
         Ok(())
     }
 
+    /// Returns the pixels of the image as RGBA8, whatever its color type and bit depth.
+    ///
+    /// Indexed images are expanded through their palette; 16-bit channels keep their high byte.
+    pub fn as_rgba8(&self) -> Vec<u8> {
+        let bits = self.bit_depth as usize;
+        let samples = self.color_type.samples();
+        let row_bytes = (self.width as usize * samples * bits).div_ceil(8);
+        let mut output = Vec::with_capacity((self.width * self.height * 4) as usize);
+
+        for row in self.data.chunks_exact(row_bytes).take(self.height as usize) {
+            for x in 0..self.width as usize {
+                let sample = |i: usize| -> u8 {
+                    let index = x * samples + i;
+                    match bits {
+                        8 => row[index],
+                        16 => row[index * 2],
+                        _ => {
+                            let bit = index * bits;
+                            let value = (row[bit / 8] >> (8 - bits - bit % 8)) & ((1 << bits) - 1);
+                            if self.color_type == ColorType::Indexed {
+                                value
+                            } else {
+                                (value as u32 * 255 / ((1 << bits) - 1)) as u8
+                            }
+                        }
+                    }
+                };
+
+                let pixel = match self.color_type {
+                    ColorType::Grayscale => [sample(0), sample(0), sample(0), 0xFF],
+                    ColorType::GrayscaleAlpha => [sample(0), sample(0), sample(0), sample(1)],
+                    ColorType::Rgb => [sample(0), sample(1), sample(2), 0xFF],
+                    ColorType::Rgba => [sample(0), sample(1), sample(2), sample(3)],
+                    ColorType::Indexed => {
+                        let index = sample(0) as usize;
+                        let rgb = self
+                            .palette
+                            .as_ref()
+                            .and_then(|p| p.get(index * 3..index * 3 + 3))
+                            .unwrap_or(&[0, 0, 0]);
+                        let alpha = self
+                            .trns
+                            .as_ref()
+                            .and_then(|t| t.get(index).copied())
+                            .unwrap_or(0xFF);
+                        [rgb[0], rgb[1], rgb[2], alpha]
+                    }
+                };
+                output.extend_from_slice(&pixel);
+            }
+        }
+
+        output
+    }
+
     pub fn as_native<W: Write>(&self, writer: &mut W, image_type: ImageType) -> Result<(), Error> {
         match image_type {
             ImageType::I1 => self.as_i1(writer),
@@ -137,6 +383,31 @@ impl PNGImage {
         Ok(())
     }
 
+    /// Encodes the image as CI4 indices into bank `bank` of `palette`, a combined palette of
+    /// up to 256 entries. Each pixel uses the nearest color of the bank.
+    pub fn as_ci4_with_palette_bank<W: Write>(
+        &self,
+        writer: &mut W,
+        palette: &[Color],
+        bank: u8,
+    ) -> Result<(), Error> {
+        let colors = palette_bank(palette, bank)?;
+        let rgba = self.as_rgba8();
+        let row_size = self.width as usize * 4;
+
+        for row in rgba.chunks_exact(row_size.max(1)) {
+            let indices: Vec<u8> = row
+                .chunks_exact(4)
+                .map(|p| nearest_color(colors, Color::RGBA(p[0], p[1], p[2], p[3])) as u8)
+                .collect();
+            for pair in indices.chunks(2) {
+                writer.write_u8(pair[0] << 4 | pair.get(1).copied().unwrap_or(0))?;
+            }
+        }
+
+        Ok(())
+    }
+
     pub fn as_i1<W: Write>(&self, writer: &mut W) -> Result<(), Error> {
         if let (ColorType::Grayscale, BitDepth::One) = (self.color_type, self.bit_depth) {
             writer.write_all(&self.data)?;
@@ -352,6 +623,22 @@ impl PNGImage {
     }
 }
 
+/// Composites an RGBA8 pixel over another with straight alpha.
+fn blend_over(dst: &mut [u8], src: &[u8]) {
+    let src_alpha = src[3] as u32;
+    let dst_alpha = dst[3] as u32 * (255 - src_alpha) / 255;
+    let alpha = src_alpha + dst_alpha;
+    if alpha == 0 {
+        return;
+    }
+
+    for channel in 0..3 {
+        dst[channel] =
+            ((src[channel] as u32 * src_alpha + dst[channel] as u32 * dst_alpha) / alpha) as u8;
+    }
+    dst[3] = alpha as u8;
+}
+
 pub fn create_palette_from_png<R: Read, W: Write>(r: R, writer: &mut W) -> Result<(), Error> {
     let decoder = png::Decoder::new(r);
     let reader = decoder.read_info()?;
@@ -359,21 +646,13 @@ pub fn create_palette_from_png<R: Read, W: Write>(r: R, writer: &mut W) -> Resul
 
     let rgb_data = info.palette.as_ref().ok_or(Error::MissingPngPalette)?;
 
-    let alpha_data = info.trns.as_ref();
+    // tRNS may be shorter than the palette, the remaining entries are opaque
+    let alpha_data = info.trns.as_deref().unwrap_or_default();
 
-    match alpha_data {
-        Some(alpha_data) => {
-            for (rgb, &alpha) in rgb_data.chunks_exact(3).zip(alpha_data.iter()) {
-                let color = Color::RGBA(rgb[0], rgb[1], rgb[2], alpha);
-                writer.write_u16::<BigEndian>(color.to_u16())?;
-            }
-        }
-        None => {
-            for rgb in rgb_data.chunks_exact(3) {
-                let color = Color::RGB(rgb[0], rgb[1], rgb[2]);
-                writer.write_u16::<BigEndian>(color.to_u16())?;
-            }
-        }
+    for (i, rgb) in rgb_data.chunks_exact(3).enumerate() {
+        let alpha = alpha_data.get(i).copied().unwrap_or(0xFF);
+        let color = Color::RGBA(rgb[0], rgb[1], rgb[2], alpha);
+        writer.write_u16::<BigEndian>(color.to_u16())?;
     }
 
     Ok(())
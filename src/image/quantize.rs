@@ -0,0 +1,137 @@
+//! Color quantization, for turning truecolor images into color-indexed ones.
+
+use crate::color::Color;
+use std::collections::HashMap;
+
+/// A palette along with the index of the entry chosen for each pixel.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Quantized {
+    pub palette: Vec<Color>,
+    pub indices: Vec<u8>,
+}
+
+/// Reduces RGBA8 pixels to a palette of at most `max_colors` colors (up to 256) using median cut,
+/// and maps each pixel to its nearest palette entry.
+///
+/// Colors are compared at RGBA5551 precision, the precision of the palettes read by the RDP. If
+/// the pixels hold no more distinct colors than that, the palette contains exactly those colors.
+pub fn quantize(rgba: &[u8], max_colors: usize) -> Quantized {
+    let palette = median_cut(rgba, max_colors.clamp(1, 256));
+    let indices = rgba
+        .chunks_exact(4)
+        .map(|p| nearest_color(&palette, Color::RGBA(p[0], p[1], p[2], p[3])) as u8)
+        .collect();
+
+    Quantized { palette, indices }
+}
+
+/// Builds a palette of at most `max_colors` colors for the given RGBA8 pixels.
+pub fn median_cut(rgba: &[u8], max_colors: usize) -> Vec<Color> {
+    let mut histogram: HashMap<u16, usize> = HashMap::new();
+    for p in rgba.chunks_exact(4) {
+        *histogram
+            .entry(Color::RGBA(p[0], p[1], p[2], p[3]).to_u16())
+            .or_default() += 1;
+    }
+
+    let mut colors: Vec<(u16, usize)> = histogram.into_iter().collect();
+    colors.sort_unstable();
+    let colors: Vec<([u8; 4], usize)> = colors
+        .into_iter()
+        .map(|(pixel, count)| {
+            let c = Color::from_u16(pixel);
+            ([c.r, c.g, c.b, c.a], count)
+        })
+        .collect();
+
+    if colors.len() <= max_colors {
+        return colors
+            .iter()
+            .map(|(c, _)| Color::RGBA(c[0], c[1], c[2], c[3]))
+            .collect();
+    }
+
+    let mut boxes = vec![colors];
+    while boxes.len() < max_colors {
+        // Split the box spanning the widest range of any channel
+        let widest = boxes
+            .iter()
+            .enumerate()
+            .filter(|(_, colors)| colors.len() > 1)
+            .map(|(i, colors)| {
+                let (channel, range) = widest_channel(colors);
+                (i, channel, range)
+            })
+            .max_by_key(|(_, _, range)| *range);
+        let Some((index, channel, _)) = widest else {
+            break;
+        };
+
+        let mut colors = boxes.swap_remove(index);
+        colors.sort_by_key(|(c, _)| c[channel]);
+
+        let total: usize = colors.iter().map(|(_, count)| count).sum();
+        let mut seen = 0;
+        let median = colors
+            .iter()
+            .position(|(_, count)| {
+                seen += count;
+                seen * 2 >= total
+            })
+            .unwrap_or(0);
+        let split = (median + 1).clamp(1, colors.len() - 1);
+
+        let upper = colors.split_off(split);
+        boxes.push(colors);
+        boxes.push(upper);
+    }
+
+    let mut palette: Vec<Color> = boxes.iter().map(|colors| average(colors)).collect();
+    palette.sort_by_key(|c| c.to_u16());
+    palette.dedup();
+    palette
+}
+
+/// Returns the index of the palette entry closest to `color`.
+pub fn nearest_color(palette: &[Color], color: Color) -> usize {
+    let distance = |c: &Color| {
+        [
+            c.r.abs_diff(color.r),
+            c.g.abs_diff(color.g),
+            c.b.abs_diff(color.b),
+            c.a.abs_diff(color.a),
+        ]
+        .iter()
+        .map(|d| (*d as u32).pow(2))
+        .sum::<u32>()
+    };
+
+    palette
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, c)| distance(c))
+        .map_or(0, |(i, _)| i)
+}
+
+fn widest_channel(colors: &[([u8; 4], usize)]) -> (usize, u8) {
+    (0..4)
+        .map(|channel| {
+            let min = colors.iter().map(|(c, _)| c[channel]).min().unwrap_or(0);
+            let max = colors.iter().map(|(c, _)| c[channel]).max().unwrap_or(0);
+            (channel, max - min)
+        })
+        .max_by_key(|(_, range)| *range)
+        .unwrap_or((0, 0))
+}
+
+/// Returns the average of the colors weighted by their count, at RGBA5551 precision.
+fn average(colors: &[([u8; 4], usize)]) -> Color {
+    let total: usize = colors.iter().map(|(_, count)| count).sum();
+    let channel = |i: usize| {
+        let sum: usize = colors.iter().map(|(c, count)| c[i] as usize * count).sum();
+        ((sum + total / 2) / total.max(1)) as u8
+    };
+
+    let alpha = if channel(3) >= 0x80 { 0xFF } else { 0x00 };
+    Color::from_u16(Color::RGBA(channel(0), channel(1), channel(2), alpha).to_u16())
+}
@@ -1,5 +1,11 @@
+pub mod analysis;
+pub mod assets;
 pub mod color;
+pub mod diff;
+pub mod gbi;
 pub mod image;
+pub mod palette;
+pub mod verify;
 
 pub use crate::image::native_image::NativeImage;
 pub use crate::image::png_image::{PNGImage, create_palette_from_png};
@@ -17,8 +23,6 @@ pub enum Error {
     MissingTlut,
     #[error("The specified TLUT mode is not supported: {0:?}")]
     UnsupportedTlutMode(TextureLUT),
-    #[error("TLUT index is out of bounds")]
-    TlutIndexOutOfBounds,
     #[error("I/O error: {0}")]
     Io(#[from] std::io::Error),
     #[error("PNG encoding error: {0}")]
@@ -37,6 +41,55 @@ pub enum Error {
     },
     #[error("Palette format cannot be converted to a native image format")]
     PaletteConversionError,
+    #[error("This operation requires a color-indexed image, got {0:?}")]
+    NotColorIndexed(ImageType),
+    #[error("Invalid asset description: {0}")]
+    InvalidAssetDescription(String),
+    #[error("Image dimensions differ: expected {expected:?}, got {actual:?}")]
+    DimensionMismatch {
+        expected: (u32, u32),
+        actual: (u32, u32),
+    },
+    #[error("Textures of format {0:?} cannot be laid out in TMEM")]
+    UnsupportedTmemFormat(ImageType),
+    #[error("Texture needs {required} bytes of TMEM, but only {available} are available")]
+    TmemOverflow { required: u32, available: u32 },
+    #[error("Mipmaps require power-of-two dimensions, got {0}x{1}")]
+    InvalidMipmapDimensions(u32, u32),
+    #[error("An atlas needs at least one image")]
+    EmptyAtlas,
+    #[error("Palettes hold {colors} distinct colors, but the format can only address {max}")]
+    PaletteOverflow { colors: usize, max: usize },
+    #[error("Palette bank {bank} is out of range for {size:?} images")]
+    InvalidPaletteBank { bank: u8, size: ImageSize },
+    #[error(
+        "Variants need {combinations} distinct color combinations, but the format can only address {max}"
+    )]
+    VariantsDiverge { combinations: usize, max: usize },
+    #[error("At least one variant is required")]
+    NoVariants,
+    #[error("Palette entry {index} ({color:?}) is missing from the reference palette")]
+    MissingPaletteColor { index: usize, color: color::Color },
+    #[error("Invalid palette file: {0}")]
+    InvalidPaletteFile(String),
+    #[error("TLUT holds {actual} entries, but {expected} were expected")]
+    TlutTooShort { expected: usize, actual: usize },
+    #[error("Palette index {index} is out of range for a palette of {entries} entries")]
+    PaletteIndexOutOfRange { index: u8, entries: usize },
+    #[error("Invalid libdragon sprite: {0}")]
+    InvalidSprite(String),
+    #[error("Images of format {0:?} cannot be stored in a libdragon sprite")]
+    UnsupportedSpriteFormat(ImageType),
+    #[error("Rows of {width} {format:?} texels are not a multiple of 64 bits")]
+    UnalignedTextureRow { width: u32, format: ImageType },
+    #[error("S2DEX objects are at most 1023 texels across, got {width}x{height}")]
+    ObjectTooLarge { width: u32, height: u32 },
+    #[error("A flipbook needs at least one frame")]
+    EmptyFlipbook,
+    #[error("A strip {length} pixels long cannot be split into {frames} frames")]
+    UnevenStrip { length: u32, frames: u32 },
+    #[error("{size} bytes of texels cannot be split into frames of {frame_size} bytes")]
+    UnevenFrames { size: usize, frame_size: usize },
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, TryFromPrimitive)]
@@ -68,6 +121,17 @@ impl ImageSize {
         }
     }
 
+    /// Returns the name of the `G_IM_SIZ_*` constant for this size, if the RDP supports it.
+    pub fn gbi_name(&self) -> Option<&'static str> {
+        match self {
+            ImageSize::Bits4 => Some("G_IM_SIZ_4b"),
+            ImageSize::Bits8 => Some("G_IM_SIZ_8b"),
+            ImageSize::Bits16 => Some("G_IM_SIZ_16b"),
+            ImageSize::Bits32 => Some("G_IM_SIZ_32b"),
+            ImageSize::Bits1 | ImageSize::DD => None,
+        }
+    }
+
     /// Returns the bits per pixel.
     pub fn get_bpp(&self) -> u32 {
         match self {
@@ -91,6 +155,19 @@ pub enum ImageFormat {
     I = 4,
 }
 
+impl ImageFormat {
+    /// Returns the name of the `G_IM_FMT_*` constant for this format.
+    pub fn gbi_name(&self) -> &'static str {
+        match self {
+            ImageFormat::Rgba => "G_IM_FMT_RGBA",
+            ImageFormat::Yuv => "G_IM_FMT_YUV",
+            ImageFormat::Ci => "G_IM_FMT_CI",
+            ImageFormat::Ia => "G_IM_FMT_IA",
+            ImageFormat::I => "G_IM_FMT_I",
+        }
+    }
+}
+
 /// Represents the type of image.
 ///
 /// This enum is used to specify the type of image, which determines the size and format of the
@@ -134,6 +211,23 @@ impl ImageType {
         }
     }
 
+    /// Returns the image type with the given format and size, if the combination exists.
+    pub fn from_format_and_size(format: ImageFormat, size: ImageSize) -> Option<Self> {
+        match (format, size) {
+            (ImageFormat::Ci, ImageSize::Bits4) => Some(ImageType::Ci4),
+            (ImageFormat::Ci, ImageSize::Bits8) => Some(ImageType::Ci8),
+            (ImageFormat::I, ImageSize::Bits1) => Some(ImageType::I1),
+            (ImageFormat::I, ImageSize::Bits4) => Some(ImageType::I4),
+            (ImageFormat::I, ImageSize::Bits8) => Some(ImageType::I8),
+            (ImageFormat::Ia, ImageSize::Bits4) => Some(ImageType::Ia4),
+            (ImageFormat::Ia, ImageSize::Bits8) => Some(ImageType::Ia8),
+            (ImageFormat::Ia, ImageSize::Bits16) => Some(ImageType::Ia16),
+            (ImageFormat::Rgba, ImageSize::Bits16) => Some(ImageType::Rgba16),
+            (ImageFormat::Rgba, ImageSize::Bits32) => Some(ImageType::Rgba32),
+            _ => None,
+        }
+    }
+
     /// Returns the size of the image type.
     ///
     /// This function returns the size of the image type, which represents the number of bits used
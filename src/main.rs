@@ -23,6 +23,106 @@ enum Commands {
         #[clap(flatten)]
         args: cli::binary::BinaryArgs,
     },
+    /// Decodes raw data with every format and width to find likely textures
+    Guess {
+        #[clap(flatten)]
+        args: cli::guess::GuessArgs,
+    },
+    /// Searches for palettes that fit a color-indexed image
+    FindTlut {
+        #[clap(flatten)]
+        args: cli::tlut_search::FindTlutArgs,
+    },
+    /// Extracts every texture referenced by a display list
+    DumpDl {
+        #[clap(flatten)]
+        args: cli::display_list::DisplayListArgs,
+    },
+    /// Extracts or rebuilds the textures described by a ZAPD XML file
+    Zapd {
+        #[command(subcommand)]
+        command: cli::zapd::ZapdCommands,
+    },
+    /// Extracts or rebuilds the images described by a splat YAML configuration
+    Splat {
+        #[command(subcommand)]
+        command: cli::splat::SplatCommands,
+    },
+    /// Checks that a PNG encodes to the exact bytes it was extracted from
+    Verify {
+        #[clap(flatten)]
+        args: cli::verify::VerifyArgs,
+    },
+    /// Compares two textures and reports where they differ
+    Diff {
+        #[clap(flatten)]
+        args: cli::diff::DiffArgs,
+    },
+    /// Reports the size and quality of a PNG converted to each format
+    Analyze {
+        #[clap(flatten)]
+        args: cli::analyze::AnalyzeArgs,
+    },
+    /// Generates a mipmap chain laid out in TMEM
+    Mipmap {
+        #[clap(flatten)]
+        args: cli::mipmap::MipmapArgs,
+    },
+    /// Splits an image into tiles that each fit in TMEM
+    Split {
+        #[clap(flatten)]
+        args: cli::split::SplitArgs,
+    },
+    /// Packs several images into a single texture
+    Atlas {
+        #[clap(flatten)]
+        args: cli::atlas::AtlasArgs,
+    },
+    /// Builds a single palette shared by several color-indexed images
+    SharedPalette {
+        #[clap(flatten)]
+        args: cli::shared_palette::SharedPaletteArgs,
+    },
+    /// Renders or extracts palette-swapped variants of a color-indexed image
+    Variants {
+        #[command(subcommand)]
+        command: cli::variants::VariantsCommands,
+    },
+    /// Removes, merges, sorts or reorders the palette entries of an indexed PNG
+    EditPalette {
+        #[clap(flatten)]
+        args: cli::edit_palette::EditPaletteArgs,
+    },
+    /// Converts a palette between TLUT binaries and common palette file formats
+    ConvertPalette {
+        #[clap(flatten)]
+        args: cli::convert_palette::ConvertPaletteArgs,
+    },
+    /// Renders a palette as a grid of swatches
+    PaletteToPng {
+        #[clap(flatten)]
+        args: cli::palette_png::PalettePngArgs,
+    },
+    /// Converts between PNGs and libdragon sprites
+    Sprite {
+        #[command(subcommand)]
+        command: cli::sprite::SpriteCommands,
+    },
+    /// Generates the libultra `Sprite` and `Bitmap` structures drawing an image
+    SpSprite {
+        #[clap(flatten)]
+        args: cli::sp_sprite::SpSpriteArgs,
+    },
+    /// Generates the S2DEX objects drawing an image as a sprite or background
+    S2dex {
+        #[clap(flatten)]
+        args: cli::s2dex::S2dexArgs,
+    },
+    /// Converts between animated or strip PNGs and native frame sequences
+    Flipbook {
+        #[command(subcommand)]
+        command: cli::flipbook::FlipbookCommands,
+    },
 }
 
 fn main() -> Result<()> {
@@ -35,6 +135,66 @@ fn main() -> Result<()> {
         Commands::ToBin { args } => {
             cli::binary::handle_binary(args)?;
         }
+        Commands::Guess { args } => {
+            cli::guess::handle_guess(args)?;
+        }
+        Commands::FindTlut { args } => {
+            cli::tlut_search::handle_find_tlut(args)?;
+        }
+        Commands::DumpDl { args } => {
+            cli::display_list::handle_display_list(args)?;
+        }
+        Commands::Zapd { command } => {
+            cli::zapd::handle_zapd(command)?;
+        }
+        Commands::Splat { command } => {
+            cli::splat::handle_splat(command)?;
+        }
+        Commands::Verify { args } => {
+            cli::verify::handle_verify(args)?;
+        }
+        Commands::Diff { args } => {
+            cli::diff::handle_diff(args)?;
+        }
+        Commands::Analyze { args } => {
+            cli::analyze::handle_analyze(args)?;
+        }
+        Commands::Mipmap { args } => {
+            cli::mipmap::handle_mipmap(args)?;
+        }
+        Commands::Split { args } => {
+            cli::split::handle_split(args)?;
+        }
+        Commands::Atlas { args } => {
+            cli::atlas::handle_atlas(args)?;
+        }
+        Commands::SharedPalette { args } => {
+            cli::shared_palette::handle_shared_palette(args)?;
+        }
+        Commands::Variants { command } => {
+            cli::variants::handle_variants(command)?;
+        }
+        Commands::EditPalette { args } => {
+            cli::edit_palette::handle_edit_palette(args)?;
+        }
+        Commands::ConvertPalette { args } => {
+            cli::convert_palette::handle_convert_palette(args)?;
+        }
+        Commands::PaletteToPng { args } => {
+            cli::palette_png::handle_palette_png(args)?;
+        }
+        Commands::Sprite { command } => {
+            cli::sprite::handle_sprite(command)?;
+        }
+        Commands::SpSprite { args } => {
+            cli::sp_sprite::handle_sp_sprite(args)?;
+        }
+        Commands::S2dex { args } => {
+            cli::s2dex::handle_s2dex(args)?;
+        }
+        Commands::Flipbook { command } => {
+            cli::flipbook::handle_flipbook(command)?;
+        }
     }
 
     Ok(())
@@ -0,0 +1,52 @@
+use crate::color::Color;
+use crate::{Error, ImageSize};
+
+/// Number of entries in a palette bank, the palette of a CI4 image.
+pub const PALETTE_BANK_SIZE: usize = 16;
+
+/// Number of banks in a full TLUT, selected by the palette field of a tile.
+pub const PALETTE_BANK_COUNT: usize = 16;
+
+/// Returns the entries of bank `bank` of `palette`.
+///
+/// The last bank may hold fewer than [`PALETTE_BANK_SIZE`] entries if `palette` is short.
+pub fn palette_bank(palette: &[Color], bank: u8) -> Result<&[Color], Error> {
+    let start = bank as usize * PALETTE_BANK_SIZE;
+    if bank as usize >= PALETTE_BANK_COUNT || start >= palette.len() {
+        return Err(Error::InvalidPaletteBank {
+            bank,
+            size: ImageSize::Bits4,
+        });
+    }
+
+    Ok(&palette[start..palette.len().min(start + PALETTE_BANK_SIZE)])
+}
+
+/// Combines CI4 palettes into a single TLUT, one bank per palette in the order given.
+///
+/// Every bank but the last is padded to [`PALETTE_BANK_SIZE`] entries with transparent black.
+pub fn combine_banks(palettes: &[Vec<Color>]) -> Result<Vec<Color>, Error> {
+    if palettes.len() > PALETTE_BANK_COUNT {
+        return Err(Error::InvalidPaletteBank {
+            bank: palettes.len() as u8 - 1,
+            size: ImageSize::Bits4,
+        });
+    }
+
+    let mut combined = Vec::with_capacity(palettes.len() * PALETTE_BANK_SIZE);
+    for (bank, palette) in palettes.iter().enumerate() {
+        if palette.len() > PALETTE_BANK_SIZE {
+            return Err(Error::PaletteOverflow {
+                colors: palette.len(),
+                max: PALETTE_BANK_SIZE,
+            });
+        }
+
+        combined.extend_from_slice(palette);
+        if bank + 1 < palettes.len() {
+            combined.resize((bank + 1) * PALETTE_BANK_SIZE, Color::RGBA(0, 0, 0, 0));
+        }
+    }
+
+    Ok(combined)
+}
@@ -0,0 +1,176 @@
+use crate::color::Color;
+use crate::{Error, PNGImage};
+use std::collections::HashMap;
+
+/// Order palette entries can be sorted in.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PaletteOrder {
+    /// From darkest to brightest.
+    Luminance,
+    /// Grays first, then around the color wheel from red, each hue from darkest to brightest.
+    Hue,
+}
+
+/// Removes the palette entries no pixel uses.
+pub fn remove_unused(png: &PNGImage) -> Result<PNGImage, Error> {
+    let (indices, palette) = indexed(png)?;
+
+    let mut used = vec![false; palette.len()];
+    for &index in &indices {
+        used[index as usize] = true;
+    }
+
+    let kept: Vec<usize> = (0..palette.len()).filter(|&i| used[i]).collect();
+    let mut mapping = vec![0; palette.len()];
+    for (new, &old) in kept.iter().enumerate() {
+        mapping[old] = new;
+    }
+
+    let palette: Vec<Color> = kept.iter().map(|&i| palette[i]).collect();
+    Ok(remap(png, &indices, &mapping, &palette))
+}
+
+/// Merges palette entries that are identical at RGBA5551 precision, keeping the first of each.
+pub fn merge_duplicates(png: &PNGImage) -> Result<PNGImage, Error> {
+    let (indices, palette) = indexed(png)?;
+
+    let mut lookup: HashMap<u16, usize> = HashMap::new();
+    let mut merged = Vec::new();
+    let mapping: Vec<usize> = palette
+        .iter()
+        .map(|color| {
+            *lookup.entry(color.to_u16()).or_insert_with(|| {
+                merged.push(*color);
+                merged.len() - 1
+            })
+        })
+        .collect();
+
+    Ok(remap(png, &indices, &mapping, &merged))
+}
+
+/// Sorts the palette entries, keeping fully transparent entries first.
+pub fn sort_palette(png: &PNGImage, order: PaletteOrder) -> Result<PNGImage, Error> {
+    let (indices, palette) = indexed(png)?;
+
+    let mut sorted: Vec<usize> = (0..palette.len()).collect();
+    sorted.sort_by(|&a, &b| {
+        let (a, b) = (palette[a], palette[b]);
+        (a.a != 0)
+            .cmp(&(b.a != 0))
+            .then_with(|| sort_key(a, order).total_cmp(&sort_key(b, order)))
+    });
+
+    let mut mapping = vec![0; palette.len()];
+    for (new, &old) in sorted.iter().enumerate() {
+        mapping[old] = new;
+    }
+
+    let palette: Vec<Color> = sorted.iter().map(|&i| palette[i]).collect();
+    Ok(remap(png, &indices, &mapping, &palette))
+}
+
+/// Swaps entry `index` with entry 0 and makes it fully transparent, as many engines treat
+/// index 0 as the transparent color.
+pub fn set_transparent_index(png: &PNGImage, index: u8) -> Result<PNGImage, Error> {
+    let (indices, mut palette) = indexed(png)?;
+    if index as usize >= palette.len() {
+        return Err(Error::PaletteIndexOutOfRange {
+            index,
+            entries: palette.len(),
+        });
+    }
+    let index = index as usize;
+
+    palette.swap(0, index);
+    palette[0].a = 0;
+
+    let mut mapping: Vec<usize> = (0..palette.len()).collect();
+    mapping.swap(0, index);
+
+    Ok(remap(png, &indices, &mapping, &palette))
+}
+
+/// Reorders the palette to match `reference`, such as the TLUT of the original texture.
+///
+/// Every used entry is matched to the first reference entry with the same RGBA5551 value, and
+/// the image takes `reference` as its palette. This fails with [`Error::MissingPaletteColor`] if
+/// a used entry has no match.
+pub fn match_reference(png: &PNGImage, reference: &[Color]) -> Result<PNGImage, Error> {
+    let (indices, palette) = indexed(png)?;
+
+    let mut lookup: HashMap<u16, usize> = HashMap::new();
+    for (index, color) in reference.iter().enumerate() {
+        lookup.entry(color.to_u16()).or_insert(index);
+    }
+
+    let mut used = vec![false; palette.len()];
+    for &index in &indices {
+        used[index as usize] = true;
+    }
+
+    let mut mapping = vec![0; palette.len()];
+    for (index, color) in palette.iter().enumerate() {
+        match lookup.get(&color.to_u16()) {
+            Some(&matched) => mapping[index] = matched,
+            None if used[index] => {
+                return Err(Error::MissingPaletteColor {
+                    index,
+                    color: *color,
+                });
+            }
+            None => {}
+        }
+    }
+
+    Ok(remap(png, &indices, &mapping, reference))
+}
+
+/// Returns the indices and palette of an indexed image, checking every index is in range.
+fn indexed(png: &PNGImage) -> Result<(Vec<u8>, Vec<Color>), Error> {
+    let indices = png.indices().ok_or(Error::MissingPngPalette)?;
+    let palette = png.palette().ok_or(Error::MissingPngPalette)?;
+
+    if let Some(&index) = indices
+        .iter()
+        .find(|&&index| index as usize >= palette.len())
+    {
+        return Err(Error::PaletteIndexOutOfRange {
+            index,
+            entries: palette.len(),
+        });
+    }
+
+    Ok((indices, palette))
+}
+
+/// Creates a copy of `png` with each index `i` replaced by `mapping[i]` into `palette`.
+fn remap(png: &PNGImage, indices: &[u8], mapping: &[usize], palette: &[Color]) -> PNGImage {
+    let indices = indices.iter().map(|&i| mapping[i as usize] as u8).collect();
+    PNGImage::from_indexed(png.width(), png.height(), indices, palette)
+}
+
+fn sort_key(color: Color, order: PaletteOrder) -> f32 {
+    let (r, g, b) = (color.r as f32, color.g as f32, color.b as f32);
+    let luminance = (r * 0.2126 + g * 0.7152 + b * 0.0722) / 255.0;
+
+    match order {
+        PaletteOrder::Luminance => luminance,
+        PaletteOrder::Hue => {
+            let max = r.max(g).max(b);
+            let delta = max - r.min(g).min(b);
+            let hue = if delta == 0.0 {
+                // Grays sort before every hue
+                -1.0
+            } else if max == r {
+                ((g - b) / delta).rem_euclid(6.0)
+            } else if max == g {
+                (b - r) / delta + 2.0
+            } else {
+                (r - g) / delta + 4.0
+            };
+            // Hues are whole sextants apart at most, so luminance only breaks ties
+            (hue * 60.0).round() + luminance
+        }
+    }
+}
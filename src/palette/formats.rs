@@ -0,0 +1,328 @@
+use crate::color::Color;
+use crate::image::native_image::tlut_entry_to_rgba;
+use crate::palette::swatch::{SWATCH_CELL_SIZE, read_swatches, render_swatches};
+use crate::{Error, PNGImage, TextureLUT};
+use std::fmt::Write;
+use std::path::Path;
+
+/// Number of colors in an Adobe color table.
+const ACT_COLORS: usize = 256;
+
+/// File formats palettes can be read from and written to.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PaletteFileFormat {
+    /// N64 TLUT of big-endian RGBA5551 entries.
+    Rgba16,
+    /// N64 TLUT of big-endian IA88 entries.
+    Ia16,
+    /// GIMP palette (`.gpl`).
+    Gpl,
+    /// JASC palette (`.pal`), as used by Paint Shop Pro and Aseprite.
+    Jasc,
+    /// Adobe color table (`.act`).
+    Act,
+    /// Indexed PNG whose palette is the palette, or any other PNG with one entry per pixel.
+    Png,
+    /// PNG of a swatch grid drawn by
+    /// [`render_swatches`](crate::palette::swatch::render_swatches).
+    Swatch,
+}
+
+impl PaletteFileFormat {
+    /// Guesses the format of a palette file from its extension, defaulting to an RGBA16 TLUT.
+    pub fn from_path(path: &Path) -> Self {
+        let extension = path
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_lowercase());
+
+        match extension.as_deref() {
+            Some("gpl") => PaletteFileFormat::Gpl,
+            Some("pal") => PaletteFileFormat::Jasc,
+            Some("act") => PaletteFileFormat::Act,
+            Some("png") => PaletteFileFormat::Png,
+            _ => PaletteFileFormat::Rgba16,
+        }
+    }
+}
+
+/// Reads the colors of a palette file.
+///
+/// Formats without transparency return opaque colors, except for the transparent index of
+/// Adobe color tables.
+pub fn read_palette(bytes: &[u8], format: PaletteFileFormat) -> Result<Vec<Color>, Error> {
+    match format {
+        PaletteFileFormat::Rgba16 => Ok(read_tlut(bytes, TextureLUT::Rgba16)),
+        PaletteFileFormat::Ia16 => Ok(read_tlut(bytes, TextureLUT::Ia16)),
+        PaletteFileFormat::Gpl => read_gpl(&text(bytes)?),
+        PaletteFileFormat::Jasc => read_jasc(&text(bytes)?),
+        PaletteFileFormat::Act => read_act(bytes),
+        PaletteFileFormat::Png => {
+            let png = PNGImage::read(bytes)?;
+            Ok(match png.palette() {
+                Some(palette) => palette,
+                None => png
+                    .as_rgba8()
+                    .chunks_exact(4)
+                    .map(|p| Color::RGBA(p[0], p[1], p[2], p[3]))
+                    .collect(),
+            })
+        }
+        PaletteFileFormat::Swatch => read_swatches(&PNGImage::read(bytes)?),
+    }
+}
+
+/// Writes colors as a palette file.
+///
+/// GIMP palettes only include alpha when a color is not opaque, JASC palettes drop it, and Adobe
+/// color tables keep the first fully transparent color as their transparent index.
+pub fn write_palette(colors: &[Color], format: PaletteFileFormat) -> Result<Vec<u8>, Error> {
+    match format {
+        PaletteFileFormat::Rgba16 => Ok(colors.iter().flat_map(|c| c.rgba16()).collect()),
+        PaletteFileFormat::Ia16 => Ok(colors
+            .iter()
+            .flat_map(|c| [c.rgb_to_intensity(), c.a])
+            .collect()),
+        PaletteFileFormat::Gpl => Ok(write_gpl(colors).into_bytes()),
+        PaletteFileFormat::Jasc => Ok(write_jasc(colors).into_bytes()),
+        PaletteFileFormat::Act => write_act(colors),
+        PaletteFileFormat::Png => {
+            let width = colors.len().clamp(1, 16) as u32;
+            let height = colors.len().div_ceil(16).max(1) as u32;
+            let indices = (0..width * height)
+                .map(|i| {
+                    if (i as usize) < colors.len() {
+                        i as u8
+                    } else {
+                        0
+                    }
+                })
+                .collect();
+
+            let mut png = Vec::new();
+            PNGImage::from_indexed(width, height, indices, colors).as_png(&mut png)?;
+            Ok(png)
+        }
+        PaletteFileFormat::Swatch => {
+            let mut png = Vec::new();
+            render_swatches(colors, SWATCH_CELL_SIZE).as_png(&mut png)?;
+            Ok(png)
+        }
+    }
+}
+
+/// Pads a palette with transparent black up to `entries` entries, such as 16 or 256 for a full
+/// CI4 or CI8 TLUT.
+pub fn pad_palette(colors: &[Color], entries: usize) -> Result<Vec<Color>, Error> {
+    if colors.len() > entries {
+        return Err(Error::PaletteOverflow {
+            colors: colors.len(),
+            max: entries,
+        });
+    }
+
+    let mut padded = colors.to_vec();
+    padded.resize(entries, Color::TRANSPARENT);
+    Ok(padded)
+}
+
+// MARK: - TLUT
+
+fn read_tlut(bytes: &[u8], mode: TextureLUT) -> Vec<Color> {
+    bytes
+        .chunks_exact(2)
+        .map(|entry| {
+            let [r, g, b, a] = tlut_entry_to_rgba(u16::from_be_bytes([entry[0], entry[1]]), mode);
+            Color::RGBA(r, g, b, a)
+        })
+        .collect()
+}
+
+// MARK: - GIMP
+
+fn read_gpl(text: &str) -> Result<Vec<Color>, Error> {
+    let mut lines = text.lines();
+    if !lines.next().is_some_and(|line| {
+        line.trim_start_matches('\u{feff}')
+            .starts_with("GIMP Palette")
+    }) {
+        return Err(invalid("missing \"GIMP Palette\" header"));
+    }
+
+    let mut channels = 3;
+    let mut colors = Vec::new();
+
+    for line in lines {
+        let line = line.trim();
+        if let Some(value) = line.strip_prefix("Channels:") {
+            channels = match value.trim() {
+                "RGB" => 3,
+                "RGBA" => 4,
+                other => return Err(invalid(&format!("unsupported channels \"{other}\""))),
+            };
+            continue;
+        }
+        if line.is_empty()
+            || line.starts_with('#')
+            || line.starts_with("Name:")
+            || line.starts_with("Columns:")
+        {
+            continue;
+        }
+
+        let values = parse_components(line.split_whitespace().take(channels), line)?;
+        if values.len() < 3 {
+            return Err(invalid(&format!("incomplete color \"{line}\"")));
+        }
+        colors.push(Color::RGBA(
+            values[0],
+            values[1],
+            values[2],
+            values.get(3).copied().unwrap_or(0xFF),
+        ));
+    }
+
+    Ok(colors)
+}
+
+fn write_gpl(colors: &[Color]) -> String {
+    let alpha = colors.iter().any(|c| c.a != 0xFF);
+    let mut text = String::from("GIMP Palette\n");
+    if alpha {
+        text.push_str("Channels: RGBA\n");
+    }
+    text.push_str("Name: pigment64\nColumns: 16\n#\n");
+
+    for (index, c) in colors.iter().enumerate() {
+        if alpha {
+            let _ = writeln!(
+                text,
+                "{:3} {:3} {:3} {:3}\tIndex {index}",
+                c.r, c.g, c.b, c.a
+            );
+        } else {
+            let _ = writeln!(text, "{:3} {:3} {:3}\tIndex {index}", c.r, c.g, c.b);
+        }
+    }
+
+    text
+}
+
+// MARK: - JASC
+
+fn read_jasc(text: &str) -> Result<Vec<Color>, Error> {
+    let mut lines = text.lines().map(str::trim);
+    if lines.next().map(|line| line.trim_start_matches('\u{feff}')) != Some("JASC-PAL") {
+        return Err(invalid("missing \"JASC-PAL\" header"));
+    }
+    lines.next();
+
+    let count: usize = lines
+        .next()
+        .and_then(|line| line.parse().ok())
+        .ok_or_else(|| invalid("missing color count"))?;
+
+    let colors = lines
+        .filter(|line| !line.is_empty())
+        .take(count)
+        .map(|line| {
+            let values = parse_components(line.split_whitespace(), line)?;
+            match values[..] {
+                [r, g, b] => Ok(Color::RGB(r, g, b)),
+                [r, g, b, a] => Ok(Color::RGBA(r, g, b, a)),
+                _ => Err(invalid(&format!("invalid color \"{line}\""))),
+            }
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if colors.len() != count {
+        return Err(invalid(&format!(
+            "expected {count} colors, found {}",
+            colors.len()
+        )));
+    }
+
+    Ok(colors)
+}
+
+fn write_jasc(colors: &[Color]) -> String {
+    let mut text = format!("JASC-PAL\r\n0100\r\n{}\r\n", colors.len());
+    for c in colors {
+        let _ = write!(text, "{} {} {}\r\n", c.r, c.g, c.b);
+    }
+    text
+}
+
+// MARK: - Adobe
+
+fn read_act(bytes: &[u8]) -> Result<Vec<Color>, Error> {
+    if bytes.len() < ACT_COLORS * 3 {
+        return Err(invalid(&format!(
+            "expected at least {} bytes, found {}",
+            ACT_COLORS * 3,
+            bytes.len()
+        )));
+    }
+
+    let mut colors: Vec<Color> = bytes[..ACT_COLORS * 3]
+        .chunks_exact(3)
+        .map(|rgb| Color::RGB(rgb[0], rgb[1], rgb[2]))
+        .collect();
+
+    // The optional trailer holds the number of colors and the transparent index
+    if let Some(trailer) = bytes.get(ACT_COLORS * 3..ACT_COLORS * 3 + 4) {
+        let count = u16::from_be_bytes([trailer[0], trailer[1]]) as usize;
+        let transparent = u16::from_be_bytes([trailer[2], trailer[3]]) as usize;
+        if let Some(color) = colors.get_mut(transparent) {
+            color.a = 0;
+        }
+        if (1..=ACT_COLORS).contains(&count) {
+            colors.truncate(count);
+        }
+    }
+
+    Ok(colors)
+}
+
+fn write_act(colors: &[Color]) -> Result<Vec<u8>, Error> {
+    if colors.len() > ACT_COLORS {
+        return Err(Error::PaletteOverflow {
+            colors: colors.len(),
+            max: ACT_COLORS,
+        });
+    }
+
+    let mut bytes: Vec<u8> = colors.iter().flat_map(|c| [c.r, c.g, c.b]).collect();
+    bytes.resize(ACT_COLORS * 3, 0);
+
+    let transparent = colors
+        .iter()
+        .position(|c| c.a == 0)
+        .map_or(0xFFFF, |index| index as u16);
+    bytes.extend_from_slice(&(colors.len() as u16).to_be_bytes());
+    bytes.extend_from_slice(&transparent.to_be_bytes());
+
+    Ok(bytes)
+}
+
+// MARK: - Helpers
+
+fn text(bytes: &[u8]) -> Result<String, Error> {
+    String::from_utf8(bytes.to_vec()).map_err(|_| invalid("palette is not valid UTF-8"))
+}
+
+fn parse_components<'a>(
+    values: impl Iterator<Item = &'a str>,
+    line: &str,
+) -> Result<Vec<u8>, Error> {
+    values
+        .map(|value| {
+            value
+                .parse::<u8>()
+                .map_err(|_| invalid(&format!("invalid color \"{line}\"")))
+        })
+        .collect()
+}
+
+fn invalid(reason: &str) -> Error {
+    Error::InvalidPaletteFile(reason.to_string())
+}
@@ -0,0 +1,6 @@
+pub mod banks;
+pub mod edit;
+pub mod formats;
+pub mod shared;
+pub mod swatch;
+pub mod variants;
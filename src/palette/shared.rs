@@ -0,0 +1,107 @@
+use crate::color::Color;
+use crate::image::quantize::{median_cut, nearest_color};
+use crate::{Error, ImageFormat, ImageType, PNGImage};
+use std::collections::HashMap;
+
+/// Several color-indexed images sharing a single palette.
+#[derive(Debug, Clone)]
+pub struct SharedPalette {
+    pub palette: Vec<Color>,
+    /// The input images as 8-bit indexed images referencing `palette`, in the order given.
+    pub images: Vec<PNGImage>,
+}
+
+impl SharedPalette {
+    /// Returns the palette as RGBA16 TLUT data.
+    pub fn tlut(&self) -> Vec<u8> {
+        self.palette.iter().flat_map(|c| c.rgba16()).collect()
+    }
+}
+
+/// Builds a palette shared by every image for the color-indexed `format`.
+///
+/// If every image is indexed, their palettes are merged: the colors used by each image are
+/// appended in palette order, skipping those already present at RGBA5551 precision, and the
+/// indices are remapped to the merged palette. This fails with [`Error::PaletteOverflow`] if
+/// the merged palette holds more colors than `format` can address. Otherwise, the images are
+/// quantized jointly.
+pub fn build_shared_palette(
+    images: &[PNGImage],
+    format: ImageType,
+) -> Result<SharedPalette, Error> {
+    if format.get_format() != ImageFormat::Ci {
+        return Err(Error::NotColorIndexed(format));
+    }
+    let max_colors = format.get_size().get_tlut_size().unwrap_or_default();
+
+    let indexed: Option<Vec<(Vec<u8>, Vec<Color>)>> = images
+        .iter()
+        .map(|image| Some((image.indices()?, image.palette()?)))
+        .collect();
+
+    let Some(indexed) = indexed else {
+        let rgba: Vec<u8> = images.iter().flat_map(|image| image.as_rgba8()).collect();
+        let palette = median_cut(&rgba, max_colors);
+        let images = images
+            .iter()
+            .map(|image| {
+                let indices = image
+                    .as_rgba8()
+                    .chunks_exact(4)
+                    .map(|p| nearest_color(&palette, Color::RGBA(p[0], p[1], p[2], p[3])) as u8)
+                    .collect();
+                PNGImage::from_indexed(image.width(), image.height(), indices, &palette)
+            })
+            .collect();
+
+        return Ok(SharedPalette { palette, images });
+    };
+
+    let mut palette: Vec<Color> = Vec::new();
+    let mut lookup: HashMap<u16, usize> = HashMap::new();
+    let mut remapped = Vec::with_capacity(images.len());
+
+    for (indices, image_palette) in indexed {
+        let mut used = vec![false; image_palette.len()];
+        for &index in &indices {
+            match used.get_mut(index as usize) {
+                Some(used) => *used = true,
+                None => {
+                    return Err(Error::PaletteIndexOutOfRange {
+                        index,
+                        entries: image_palette.len(),
+                    });
+                }
+            }
+        }
+
+        let mut mapping = vec![0; image_palette.len()];
+        for (index, color) in image_palette.iter().enumerate() {
+            if used[index] {
+                mapping[index] = *lookup.entry(color.to_u16()).or_insert_with(|| {
+                    palette.push(*color);
+                    palette.len() - 1
+                });
+            }
+        }
+
+        remapped.push(indices.iter().map(|&i| mapping[i as usize] as u8).collect());
+    }
+
+    if palette.len() > max_colors {
+        return Err(Error::PaletteOverflow {
+            colors: palette.len(),
+            max: max_colors,
+        });
+    }
+
+    let images = images
+        .iter()
+        .zip(remapped)
+        .map(|(image, indices)| {
+            PNGImage::from_indexed(image.width(), image.height(), indices, &palette)
+        })
+        .collect();
+
+    Ok(SharedPalette { palette, images })
+}
@@ -0,0 +1,61 @@
+use crate::color::Color;
+use crate::{Error, PNGImage};
+
+/// Number of swatches in each row of a swatch grid, the size of a CI4 palette.
+pub const SWATCH_COLUMNS: u32 = 16;
+/// Default width and height of a swatch, in pixels.
+pub const SWATCH_CELL_SIZE: u32 = 16;
+
+/// Renders colors as a grid of square swatches, 16 per row, in palette order.
+///
+/// Each swatch is `cell_size` texels wide. The top-left half of a swatch holds its exact color,
+/// while the bottom-right half is drawn fully opaque, so that translucent entries stand out as
+/// split squares. Cells past the last color are left fully transparent.
+pub fn render_swatches(colors: &[Color], cell_size: u32) -> PNGImage {
+    let cell_size = cell_size.max(1);
+    let rows = (colors.len() as u32).div_ceil(SWATCH_COLUMNS).max(1);
+    let width = SWATCH_COLUMNS * cell_size;
+    let height = rows * cell_size;
+
+    let mut rgba = vec![0u8; (width * height * 4) as usize];
+    for (index, color) in colors.iter().enumerate() {
+        let cell_x = index as u32 % SWATCH_COLUMNS * cell_size;
+        let cell_y = index as u32 / SWATCH_COLUMNS * cell_size;
+
+        for y in 0..cell_size {
+            for x in 0..cell_size {
+                let alpha = if x + y < cell_size { color.a } else { 0xFF };
+                let offset = (((cell_y + y) * width + cell_x + x) * 4) as usize;
+                rgba[offset..offset + 4].copy_from_slice(&[color.r, color.g, color.b, alpha]);
+            }
+        }
+    }
+
+    PNGImage::from_rgba8(width, height, rgba)
+}
+
+/// Reads back the colors of a swatch grid rendered by [`render_swatches`].
+///
+/// The size of the swatches is the width of the image divided by 16, and every cell is read, so
+/// the colors always fill whole rows.
+pub fn read_swatches(png: &PNGImage) -> Result<Vec<Color>, Error> {
+    let (width, height) = (png.width(), png.height());
+    let cell_size = width / SWATCH_COLUMNS;
+    if cell_size == 0 || width % SWATCH_COLUMNS != 0 || height % cell_size != 0 {
+        return Err(Error::InvalidPaletteFile(format!(
+            "{width}x{height} is not a grid of {SWATCH_COLUMNS} square swatches per row"
+        )));
+    }
+
+    let rgba = png.as_rgba8();
+    let colors = (0..height / cell_size)
+        .flat_map(|row| (0..SWATCH_COLUMNS).map(move |column| (column, row)))
+        .map(|(column, row)| {
+            let offset = ((row * cell_size * width + column * cell_size) * 4) as usize;
+            let p = &rgba[offset..offset + 4];
+            Color::RGBA(p[0], p[1], p[2], p[3])
+        })
+        .collect();
+
+    Ok(colors)
+}
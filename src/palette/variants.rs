@@ -0,0 +1,147 @@
+use crate::color::Color;
+use crate::image::native_image::parse_tlut_entries;
+use crate::{Error, ImageFormat, ImageType, NativeImage, PNGImage, TextureLUT};
+use std::collections::HashMap;
+
+/// Color-indexed data shared by several palette-swapped variants of an image.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PaletteVariants {
+    pub format: ImageType,
+    pub width: u32,
+    pub height: u32,
+    /// Native index data shared by every variant.
+    pub data: Vec<u8>,
+    /// Palette of each variant, in the order the images were given.
+    pub palettes: Vec<Vec<Color>>,
+}
+
+impl PaletteVariants {
+    /// Returns the palette of variant `variant` as RGBA16 TLUT data.
+    pub fn tlut(&self, variant: usize) -> Vec<u8> {
+        self.palettes[variant]
+            .iter()
+            .flat_map(|c| c.rgba16())
+            .collect()
+    }
+}
+
+/// Renders a color-indexed image with each of the given RGBA16 TLUTs, returning one PNG each.
+///
+/// TLUTs may be shorter than a full 16 or 256 entry palette, as long as they cover every index.
+pub fn render_variants(image: &NativeImage, tluts: &[Vec<u8>]) -> Result<Vec<Vec<u8>>, Error> {
+    if image.format.get_format() != ImageFormat::Ci {
+        return Err(Error::NotColorIndexed(image.format));
+    }
+
+    let tlut_size = image.format.get_size().get_tlut_size().unwrap_or_default();
+    tluts
+        .iter()
+        .map(|tlut| {
+            // TLUTs written by `extract_variants` only hold the colors they use
+            let entries = (tlut.len() / 2).min(tlut_size);
+            let colors = parse_tlut_entries(tlut, entries, TextureLUT::Rgba16)?;
+            let mut png = Vec::new();
+            image.as_png(&mut png, Some(&colors))?;
+            Ok(png)
+        })
+        .collect()
+}
+
+/// Finds the index data shared by several palette-swapped variants of an image.
+///
+/// Indexed images with identical indices keep their own palettes. Otherwise, every distinct
+/// combination of colors the variants have at a texel (at RGBA5551 precision) becomes an index,
+/// which fails with [`Error::VariantsDiverge`] if the variants need more indices than `format`
+/// can address.
+pub fn extract_variants(images: &[PNGImage], format: ImageType) -> Result<PaletteVariants, Error> {
+    if format.get_format() != ImageFormat::Ci {
+        return Err(Error::NotColorIndexed(format));
+    }
+    let Some(first) = images.first() else {
+        return Err(Error::NoVariants);
+    };
+
+    let (width, height) = (first.width(), first.height());
+    for image in images {
+        if (image.width(), image.height()) != (width, height) {
+            return Err(Error::DimensionMismatch {
+                expected: (width, height),
+                actual: (image.width(), image.height()),
+            });
+        }
+    }
+
+    let max_colors = format.get_size().get_tlut_size().unwrap_or_default();
+    let (indices, palettes) = match shared_indices(images, max_colors) {
+        Some(shared) => shared,
+        None => combine_colors(images, max_colors)?,
+    };
+
+    let mut data = Vec::new();
+    PNGImage::from_indexed(width, height, indices, &palettes[0]).as_native(&mut data, format)?;
+
+    Ok(PaletteVariants {
+        format,
+        width,
+        height,
+        data,
+        palettes,
+    })
+}
+
+/// Returns the indices and palettes of indexed images that all have the same indices.
+fn shared_indices(images: &[PNGImage], max_colors: usize) -> Option<(Vec<u8>, Vec<Vec<Color>>)> {
+    let indices = images[0].indices()?;
+    let mut palettes = Vec::with_capacity(images.len());
+
+    for image in images {
+        let palette = image.palette()?;
+        if image.indices()? != indices || palette.len() > max_colors {
+            return None;
+        }
+        palettes.push(palette);
+    }
+
+    Some((indices, palettes))
+}
+
+/// Assigns an index to each distinct combination of colors the images have at a texel.
+fn combine_colors(
+    images: &[PNGImage],
+    max_colors: usize,
+) -> Result<(Vec<u8>, Vec<Vec<Color>>), Error> {
+    let pixels: Vec<Vec<u8>> = images.iter().map(|image| image.as_rgba8()).collect();
+    let texels = pixels[0].len() / 4;
+
+    let mut lookup: HashMap<Vec<u16>, usize> = HashMap::new();
+    let mut palettes: Vec<Vec<Color>> = vec![Vec::new(); images.len()];
+    let mut indices = Vec::with_capacity(texels);
+
+    for texel in 0..texels {
+        let colors: Vec<Color> = pixels
+            .iter()
+            .map(|p| {
+                let p = &p[texel * 4..texel * 4 + 4];
+                Color::RGBA(p[0], p[1], p[2], p[3])
+            })
+            .collect();
+        let key = colors.iter().map(|c| c.to_u16()).collect();
+
+        let index = *lookup.entry(key).or_insert_with(|| {
+            for (palette, color) in palettes.iter_mut().zip(&colors) {
+                palette.push(*color);
+            }
+            palettes[0].len() - 1
+        });
+        indices.push(index as u8);
+    }
+
+    if lookup.len() > max_colors {
+        return Err(Error::VariantsDiverge {
+            combinations: lookup.len(),
+            max: max_colors,
+        });
+    }
+
+    Ok((indices, palettes))
+}
@@ -0,0 +1,215 @@
+//! Checks that images re-encode to the exact bytes they were extracted from.
+
+use crate::analysis::preview_rgba;
+use crate::color::Color;
+use crate::image::canvas::Canvas;
+use crate::{Error, ImageType, NativeImage, PNGImage, create_palette_from_png};
+use std::io::Write;
+
+/// What the encoded data is compared against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reference<'a> {
+    /// The original bytes.
+    Binary(&'a [u8]),
+    /// The CRC-32 (IEEE) checksum of the original bytes.
+    Crc32(u32),
+}
+
+/// The first texel, or palette entry, that differs from the reference.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Mismatch {
+    /// Index of the texel or palette entry.
+    pub index: usize,
+    /// Coordinates of the texel. `None` when verifying a palette.
+    pub position: Option<(u32, u32)>,
+    /// The native value found in the reference.
+    pub expected: u32,
+    /// The native value produced by encoding the PNG.
+    pub actual: u32,
+    /// Total number of differing texels or palette entries.
+    pub count: usize,
+}
+
+/// Outcome of comparing encoded data against its reference.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerifyResult {
+    Match,
+    SizeMismatch { expected: usize, actual: usize },
+    ChecksumMismatch { expected: u32, actual: u32 },
+    ValueMismatch(Mismatch),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifyReport {
+    /// The bytes the PNG encoded to.
+    pub encoded: Vec<u8>,
+    pub result: VerifyResult,
+}
+
+impl VerifyReport {
+    pub fn is_match(&self) -> bool {
+        self.result == VerifyResult::Match
+    }
+}
+
+/// Encodes `png` as `image_type` with [`PNGImage::as_native`] and compares it to `reference`.
+///
+/// If `word_swap` is set the encoded data has its odd rows word swapped first, like the
+/// reference is expected to be. Texel positions are always reported in image space.
+pub fn verify_image(
+    png: &PNGImage,
+    image_type: ImageType,
+    word_swap: bool,
+    reference: Reference,
+) -> Result<VerifyReport, Error> {
+    let mut image = NativeImage {
+        format: image_type,
+        width: png.width(),
+        height: png.height(),
+        data: Vec::new(),
+    };
+    png.as_native(&mut image.data, image_type)?;
+
+    if word_swap {
+        image.swap_word_rows();
+    }
+
+    let result = match reference {
+        Reference::Crc32(expected) => compare_checksum(&image.data, expected),
+        Reference::Binary(expected) if expected.len() != image.data.len() => {
+            VerifyResult::SizeMismatch {
+                expected: expected.len(),
+                actual: image.data.len(),
+            }
+        }
+        Reference::Binary(expected) => {
+            // Compare in image space, the swap is its own inverse
+            let mut expected = NativeImage {
+                data: expected.to_vec(),
+                ..image
+            };
+            let mut actual = NativeImage {
+                data: image.data.clone(),
+                ..image
+            };
+            if word_swap {
+                expected.swap_word_rows();
+                actual.swap_word_rows();
+            }
+
+            let bpp = image_type.get_size().get_bpp();
+            let texel_count = (image.width * image.height) as usize;
+            first_mismatch(&expected.data, &actual.data, bpp, texel_count)
+                .map(|mismatch| Mismatch {
+                    position: Some((
+                        mismatch.index as u32 % image.width,
+                        mismatch.index as u32 / image.width,
+                    )),
+                    ..mismatch
+                })
+                .map_or(VerifyResult::Match, VerifyResult::ValueMismatch)
+        }
+    };
+
+    Ok(VerifyReport {
+        encoded: image.data,
+        result,
+    })
+}
+
+/// Extracts the palette of an indexed PNG with [`create_palette_from_png`] and compares it to
+/// `reference`.
+pub fn verify_palette(png_bytes: &[u8], reference: Reference) -> Result<VerifyReport, Error> {
+    let mut encoded = Vec::new();
+    create_palette_from_png(png_bytes, &mut encoded)?;
+
+    let result = match reference {
+        Reference::Crc32(expected) => compare_checksum(&encoded, expected),
+        Reference::Binary(expected) if expected.len() != encoded.len() => {
+            VerifyResult::SizeMismatch {
+                expected: expected.len(),
+                actual: encoded.len(),
+            }
+        }
+        Reference::Binary(expected) => first_mismatch(expected, &encoded, 16, encoded.len() / 2)
+            .map_or(VerifyResult::Match, VerifyResult::ValueMismatch),
+    };
+
+    Ok(VerifyReport { encoded, result })
+}
+
+/// Writes a PNG highlighting the texels that differ between two native images of the same
+/// format and size.
+///
+/// Matching texels are shown faded, differing ones in bright magenta. Color-indexed images are
+/// shown as a grayscale ramp of their indices.
+pub fn write_highlight_png<W: Write>(
+    writer: &mut W,
+    image_type: ImageType,
+    width: u32,
+    height: u32,
+    expected: &[u8],
+    actual: &[u8],
+) -> Result<(), Error> {
+    let bpp = image_type.get_size().get_bpp();
+    let preview = preview_rgba(actual, image_type, width, height)?;
+    let mut canvas = Canvas::new(width, height, Color::TRANSPARENT);
+
+    for y in 0..height {
+        for x in 0..width {
+            let index = (y * width + x) as usize;
+            let differs = texel_value(expected, index, bpp) != texel_value(actual, index, bpp);
+
+            let color = if differs {
+                Color::RGB(0xFF, 0x00, 0xFF)
+            } else {
+                let p = &preview[index * 4..index * 4 + 4];
+                let fade = |c: u8| (c / 4) + 0x60;
+                Color::RGB(fade(p[0]), fade(p[1]), fade(p[2]))
+            };
+            canvas.set_pixel(x, y, color);
+        }
+    }
+
+    canvas.write_png(writer)
+}
+
+/// Reads the native value of the texel at `index` from data with the given bits per texel.
+pub(crate) fn texel_value(data: &[u8], index: usize, bpp: u32) -> Option<u32> {
+    let bpp = bpp as usize;
+    let bit = index * bpp;
+
+    if bpp < 8 {
+        let byte = *data.get(bit / 8)?;
+        Some(((byte >> (8 - bpp - bit % 8)) & ((1 << bpp) - 1)) as u32)
+    } else {
+        let bytes = data.get(bit / 8..bit / 8 + bpp / 8)?;
+        Some(bytes.iter().fold(0, |value, b| value << 8 | *b as u32))
+    }
+}
+
+fn first_mismatch(expected: &[u8], actual: &[u8], bpp: u32, count: usize) -> Option<Mismatch> {
+    let mut mismatches = (0..count).filter_map(|index| {
+        let expected = texel_value(expected, index, bpp)?;
+        let actual = texel_value(actual, index, bpp)?;
+        (expected != actual).then_some((index, expected, actual))
+    });
+
+    let (index, expected, actual) = mismatches.next()?;
+    Some(Mismatch {
+        index,
+        position: None,
+        expected,
+        actual,
+        count: 1 + mismatches.count(),
+    })
+}
+
+fn compare_checksum(data: &[u8], expected: u32) -> VerifyResult {
+    let actual = crc32fast::hash(data);
+    if actual == expected {
+        VerifyResult::Match
+    } else {
+        VerifyResult::ChecksumMismatch { expected, actual }
+    }
+}
@@ -0,0 +1,223 @@
+use anyhow::Result;
+use pigment64::analysis::guess::{guess_textures, write_contact_sheet};
+use pigment64::analysis::quality::analyze_format;
+use pigment64::analysis::select::{pixel_stats, select_format};
+use pigment64::analysis::tlut_search::{DEFAULT_TLUT_ALIGNMENT, find_tluts};
+use pigment64::analysis::width::infer_dimensions;
+use pigment64::color::Color;
+use pigment64::gbi::tmem_footprint;
+use pigment64::image::quantize::quantize;
+use pigment64::{ImageType, NativeImage, PNGImage, TextureLUT};
+
+/// Builds a smooth RGBA16 gradient with the given dimensions.
+fn rgba16_gradient(width: u32, height: u32) -> Vec<u8> {
+    let mut data = Vec::new();
+    for y in 0..height {
+        for x in 0..width {
+            let color = Color::RGB((x * 8) as u8, (y * 8) as u8, 0x80);
+            data.extend_from_slice(&color.rgba16());
+        }
+    }
+    data
+}
+
+#[test]
+fn guess_finds_row_stride() -> Result<()> {
+    let data = rgba16_gradient(32, 32);
+    let candidates = guess_textures(&data, &[8, 16, 32, 64, 128])?;
+
+    let best = &candidates[0];
+    let stride = best.width * best.format.get_size().get_bpp() / 8;
+    assert_eq!(stride, 64);
+    assert!(best.score > candidates.last().unwrap().score);
+    Ok(())
+}
+
+#[test]
+fn guess_contact_sheet() -> Result<()> {
+    let data = rgba16_gradient(16, 16);
+    let candidates = guess_textures(&data, &[8, 16])?;
+
+    let mut output = Vec::new();
+    write_contact_sheet(&mut output, &candidates)?;
+
+    let sheet = PNGImage::read(output.as_slice())?;
+    assert!(sheet.width() > 16 && sheet.height() > 16);
+    Ok(())
+}
+
+#[test]
+fn infer_width() -> Result<()> {
+    let data = rgba16_gradient(32, 24);
+    let candidates = infer_dimensions(&data, ImageType::Rgba16)?;
+
+    assert_eq!((candidates[0].width, candidates[0].height), (32, 24));
+    Ok(())
+}
+
+#[test]
+fn find_tlut_between_noise() -> Result<()> {
+    // A smooth 16 entry RGBA16 palette surrounded by pseudo-random noise
+    let mut seed: u32 = 0x1234_5678;
+    let mut noise = |len: usize| -> Vec<u8> {
+        (0..len)
+            .map(|_| {
+                seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                (seed >> 16) as u8
+            })
+            .collect()
+    };
+
+    let mut data = noise(256);
+    for i in 0..16u8 {
+        data.extend_from_slice(&Color::RGB(i * 16, 0x40, 0xFF - i * 16).rgba16());
+    }
+    data.extend(noise(256));
+
+    // A CI4 image whose indices form a horizontal gradient
+    let indices: Vec<u8> = (0..16 * 16)
+        .map(|i| {
+            let x = (i % 16) as u8;
+            x << 4 | x
+        })
+        .step_by(2)
+        .collect();
+    let image = NativeImage::read(indices.as_slice(), ImageType::Ci4, 16, 16)?;
+
+    let candidates = find_tluts(&data, &image, DEFAULT_TLUT_ALIGNMENT)?;
+    assert_eq!(candidates[0].offset, 256);
+    assert_eq!(candidates[0].mode, TextureLUT::Rgba16);
+    Ok(())
+}
+
+#[test]
+fn analyze_lossless_formats() -> Result<()> {
+    let image = PNGImage::read(&include_bytes!("ci4.png")[..])?;
+
+    let ci4 = analyze_format(&image, ImageType::Ci4)?;
+    assert_eq!(ci4.byte_size, 8);
+    assert_eq!(ci4.tlut_size, 32);
+    assert!(ci4.fits_tmem);
+    assert!(ci4.psnr.is_infinite());
+
+    let ci8 = analyze_format(&image, ImageType::Ci8)?;
+    assert_eq!(ci8.byte_size, 16);
+    assert_eq!(ci8.max_error, 0);
+
+    let rgba16 = analyze_format(&image, ImageType::Rgba16)?;
+    assert_eq!(rgba16.mse, 0.0);
+    Ok(())
+}
+
+#[test]
+fn analyze_lossy_formats() -> Result<()> {
+    let image = PNGImage::read(&include_bytes!("ia16.png")[..])?;
+
+    let ia16 = analyze_format(&image, ImageType::Ia16)?;
+    assert_eq!(ia16.byte_size, 256 * 256 * 2);
+    assert!(!ia16.fits_tmem);
+    assert!(ia16.psnr.is_infinite());
+
+    let ia8 = analyze_format(&image, ImageType::Ia8)?;
+    assert!(ia8.mse > 0.0);
+    assert!(ia8.max_alpha_error > 0);
+    assert!(ia8.alpha_errors > 0);
+    assert!(ia8.psnr < ia16.psnr);
+    Ok(())
+}
+
+#[test]
+fn tmem_footprint_pads_rows() {
+    assert_eq!(tmem_footprint(ImageType::I4, 5, 4), 8 * 4);
+    assert_eq!(tmem_footprint(ImageType::Rgba16, 32, 32), 2048);
+    assert_eq!(tmem_footprint(ImageType::Rgba32, 32, 32), 4096);
+}
+
+#[test]
+fn quantize_reduces_colors() {
+    let mut rgba = Vec::new();
+    for i in 0..1024u32 {
+        rgba.extend_from_slice(&[(i * 7) as u8, (i / 4) as u8, (i * 3) as u8, 0xFF]);
+    }
+
+    let quantized = quantize(&rgba, 16);
+    assert!(quantized.palette.len() <= 16);
+    assert_eq!(quantized.indices.len(), 1024);
+    assert!(
+        quantized
+            .indices
+            .iter()
+            .all(|i| (*i as usize) < quantized.palette.len())
+    );
+
+    // Few enough colors are kept exactly
+    let rgba = [[0xF8, 0x00, 0x00, 0xFF], [0x00, 0x00, 0xF8, 0xFF]].concat();
+    let quantized = quantize(&rgba, 16);
+    assert_eq!(
+        quantized.palette,
+        [Color::RGB(0x00, 0x00, 0xFF), Color::RGB(0xFF, 0x00, 0x00)]
+    );
+    assert_eq!(quantized.indices, [1, 0]);
+}
+
+/// Builds a 64x64 RGBA8 image cycling through the given colors.
+fn rgba8_pattern(colors: &[[u8; 4]]) -> PNGImage {
+    let data = (0..64 * 64)
+        .flat_map(|i| colors[(i / 7) % colors.len()])
+        .collect();
+    PNGImage::from_rgba8(64, 64, data)
+}
+
+#[test]
+fn select_grayscale_format() -> Result<()> {
+    let image = rgba8_pattern(&[
+        [0x00, 0x00, 0x00, 0xFF],
+        [0x30, 0x30, 0x30, 0xFF],
+        [0xA0, 0xA0, 0xA0, 0xFF],
+    ]);
+
+    let stats = pixel_stats(&image);
+    assert!(stats.grayscale);
+    assert_eq!(stats.distinct_colors, 3);
+    assert_eq!(stats.alpha_levels, 1);
+
+    let choice = select_format(&image, 0.0)?;
+    assert_eq!(choice.format, ImageType::I4);
+    assert!(choice.report.psnr.is_infinite());
+    Ok(())
+}
+
+#[test]
+fn select_color_indexed_format() -> Result<()> {
+    let image = rgba8_pattern(&[
+        [0xFF, 0x00, 0x00, 0xFF],
+        [0x00, 0xFF, 0x00, 0xFF],
+        [0x00, 0x00, 0xFF, 0xFF],
+        [0x00, 0x00, 0x00, 0x00],
+    ]);
+
+    let stats = pixel_stats(&image);
+    assert!(stats.rgba5551_exact);
+    assert!(!stats.grayscale);
+
+    let choice = select_format(&image, 0.0)?;
+    assert_eq!(choice.format, ImageType::Ci4);
+    assert_eq!(choice.report.tlut_size, 8);
+    Ok(())
+}
+
+#[test]
+fn select_within_error_budget() -> Result<()> {
+    // Not representable in RGBA5551, and too many colors for a palette
+    let data = (0..64 * 64u32)
+        .flat_map(|i| [(i % 64 * 4 + 1) as u8, (i / 64 * 4 + 1) as u8, 0x81, 0xFF])
+        .collect();
+    let image = PNGImage::from_rgba8(64, 64, data);
+
+    assert_eq!(select_format(&image, 0.0)?.format, ImageType::Rgba32);
+
+    let choice = select_format(&image, 20.0)?;
+    assert_ne!(choice.format, ImageType::Rgba32);
+    assert!(choice.report.mse <= 20.0);
+    Ok(())
+}
@@ -0,0 +1,174 @@
+use anyhow::Result;
+use pigment64::assets::splat::parse_splat_yaml;
+use pigment64::assets::zapd::parse_zapd_xml;
+use pigment64::{Error, ImageType, PNGImage};
+use std::collections::HashMap;
+
+const ZAPD_XML: &str = r#"
+<Root>
+    <File Name="object_test" Segment="6">
+        <Texture Name="gTestTLUT" OutName="test_tlut" Format="rgba16" Width="4" Height="4" Offset="0x0"/>
+        <Texture Name="gTestCITex" OutName="test_ci" Format="ci4" Width="16" Height="4" Offset="0x20" TlutOffset="0x0"/>
+        <DList Name="gTestDL" Offset="0x40"/>
+        <Texture Name="gTestI8Tex" Format="i8" Width="8" Height="8" Offset="0x48"/>
+    </File>
+</Root>
+"#;
+
+#[test]
+fn zapd_parse() -> Result<()> {
+    let files = parse_zapd_xml(ZAPD_XML)?;
+    assert_eq!(files.len(), 1);
+
+    let file = &files[0];
+    assert_eq!(file.name, "object_test");
+    assert_eq!(file.segment, Some(6));
+    assert_eq!(file.textures.len(), 3);
+
+    let ci = &file.textures[1];
+    assert_eq!(ci.format, ImageType::Ci4);
+    assert_eq!((ci.width, ci.height, ci.offset), (16, 4, 0x20));
+    assert_eq!(ci.tlut_offset, Some(0));
+    assert_eq!(ci.file_name(), "test_ci.ci4.png");
+    assert_eq!(file.textures[2].file_name(), "gTestI8Tex.i8.png");
+    Ok(())
+}
+
+#[test]
+fn zapd_roundtrip() -> Result<()> {
+    let file = parse_zapd_xml(ZAPD_XML)?.remove(0);
+
+    let mut binary: Vec<u8> = Vec::new();
+    binary.extend((0..16u16).flat_map(|i| (i << 11 | i << 6 | 1).to_be_bytes()));
+    binary.extend((0..32u8).map(|i| i.wrapping_mul(0x35)));
+    binary.extend([0xDF, 0, 0, 0, 0, 0, 0, 0]);
+    binary.extend((0..64u8).map(|i| i * 4));
+
+    let pngs: HashMap<String, Vec<u8>> = file.extract(&binary)?.into_iter().collect();
+    assert_eq!(pngs.len(), 3);
+
+    let mut rebuilt = vec![0u8; binary.len()];
+    rebuilt[0x40..0x48].copy_from_slice(&binary[0x40..0x48]);
+    file.reassemble(&mut rebuilt, |texture| {
+        PNGImage::read(pngs[&texture.file_name()].as_slice())
+    })?;
+
+    assert_eq!(rebuilt, binary);
+    Ok(())
+}
+
+#[test]
+fn zapd_short_tlut() -> Result<()> {
+    // An 8-color palette at the very end of the file
+    let file = parse_zapd_xml(
+        r#"<Root><File Name="object_test">
+            <Texture Name="gTestCITex" Format="ci8" Width="4" Height="2" Offset="0x0" TlutOffset="0x8"/>
+            <Texture Name="gTestTLUT" Format="rgba16" Width="8" Height="1" Offset="0x8"/>
+        </File></Root>"#,
+    )?
+    .remove(0);
+
+    let mut binary: Vec<u8> = (0..8).collect();
+    binary.extend((0..8u16).flat_map(|i| (i << 11 | 1).to_be_bytes()));
+
+    let pngs: HashMap<String, Vec<u8>> = file.extract(&binary)?.into_iter().collect();
+    let ci = PNGImage::read(pngs["gTestCITex.ci8.png"].as_slice())?;
+    assert_eq!(ci.palette().map(|palette| palette.len()), Some(8));
+    assert_eq!(ci.indices(), Some((0..8).collect()));
+    Ok(())
+}
+
+#[test]
+fn zapd_missing_attribute() {
+    let result =
+        parse_zapd_xml(r#"<Root><File Name="a"><Texture Name="b" Format="i4"/></File></Root>"#);
+    assert!(matches!(result, Err(Error::InvalidAssetDescription(_))));
+}
+
+const SPLAT_YAML: &str = r#"
+options:
+  basename: test
+segments:
+  - name: header
+    type: header
+    start: 0x0
+  - name: main
+    type: code
+    start: 0x10
+    subsegments:
+      - [0x10, ci4, tex/icon, 16, 4]
+      - [0x30, palette, tex/icon]
+      - { start: 0x50, type: rgba16, name: tex/bar, width: 4, height: 2 }
+      - [0x60, bin]
+  - [0x70]
+"#;
+
+#[test]
+fn splat_parse() -> Result<()> {
+    let config = parse_splat_yaml(SPLAT_YAML)?;
+
+    assert_eq!(config.images.len(), 2);
+    assert_eq!(config.images[0].format, ImageType::Ci4);
+    assert_eq!(config.images[0].offset, 0x10);
+    assert_eq!(config.images[1].name, "tex/bar");
+    assert_eq!((config.images[1].width, config.images[1].height), (4, 2));
+
+    let palette = config.palette_for(&config.images[0]).unwrap();
+    assert_eq!((palette.offset, palette.size), (0x30, Some(0x20)));
+    Ok(())
+}
+
+#[test]
+fn splat_roundtrip() -> Result<()> {
+    let config = parse_splat_yaml(SPLAT_YAML)?;
+
+    let mut rom = vec![0xAAu8; 0x70];
+    rom[0x10..0x30].copy_from_slice(&(0..32u8).map(|i| i.wrapping_mul(0x47)).collect::<Vec<_>>());
+    for i in 0..16u16 {
+        rom[0x30 + i as usize * 2..0x32 + i as usize * 2]
+            .copy_from_slice(&(i << 11 | (15 - i) << 1 | 1).to_be_bytes());
+    }
+    for i in 0..8u16 {
+        rom[0x50 + i as usize * 2..0x52 + i as usize * 2]
+            .copy_from_slice(&(i << 6 | 1).to_be_bytes());
+    }
+
+    let pngs: HashMap<String, Vec<u8>> = config.extract(&rom)?.into_iter().collect();
+    assert!(pngs.contains_key("tex/icon.png"));
+
+    let mut rebuilt = rom.clone();
+    rebuilt[0x10..0x60].fill(0);
+    config.rebuild(&mut rebuilt, |image| Ok(pngs[&image.file_name()].clone()))?;
+
+    assert_eq!(rebuilt, rom);
+    Ok(())
+}
+
+#[test]
+fn splat_short_palette() -> Result<()> {
+    // A CI8 image whose palette segment only holds 8 colors, at the end of the ROM
+    let config = parse_splat_yaml(
+        r#"
+segments:
+  - name: main
+    type: data
+    start: 0x0
+    subsegments:
+      - [0x0, ci8, tex/icon, 8, 2]
+      - [0x10, palette, tex/icon]
+  - [0x20]
+"#,
+    )?;
+
+    let mut rom: Vec<u8> = (0..16).map(|i| i % 8).collect();
+    rom.extend((0..8u16).flat_map(|i| (i << 11 | i << 1 | 1).to_be_bytes()));
+
+    let pngs: HashMap<String, Vec<u8>> = config.extract(&rom)?.into_iter().collect();
+    let icon = PNGImage::read(pngs["tex/icon.png"].as_slice())?;
+    assert_eq!(icon.palette().map(|palette| palette.len()), Some(8));
+
+    let mut rebuilt = vec![0u8; rom.len()];
+    config.rebuild(&mut rebuilt, |image| Ok(pngs[&image.file_name()].clone()))?;
+    assert_eq!(rebuilt, rom);
+    Ok(())
+}
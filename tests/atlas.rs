@@ -0,0 +1,90 @@
+use anyhow::Result;
+use pigment64::image::atlas::{AtlasEntry, AtlasOptions, build_atlas};
+use pigment64::{Error, ImageType, PNGImage};
+
+fn solid(width: u32, height: u32, color: [u8; 4]) -> PNGImage {
+    PNGImage::from_rgba8(width, height, color.repeat((width * height) as usize))
+}
+
+fn entry(x: u32, y: u32, width: u32, height: u32) -> AtlasEntry {
+    AtlasEntry {
+        x,
+        y,
+        width,
+        height,
+    }
+}
+
+#[test]
+fn atlas_packs_images() -> Result<()> {
+    let images = [
+        solid(8, 8, [0xFF, 0, 0, 0xFF]),
+        solid(16, 16, [0, 0xFF, 0, 0xFF]),
+        solid(8, 8, [0, 0, 0xFF, 0xFF]),
+    ];
+    let atlas = build_atlas(&images, ImageType::Rgba32, AtlasOptions::default())?;
+
+    // Images are sorted by height, so the smaller ones share a shelf under the larger one
+    assert_eq!((atlas.width, atlas.height), (16, 24));
+    assert_eq!(
+        atlas.entries,
+        [entry(0, 16, 8, 8), entry(0, 0, 16, 16), entry(8, 16, 8, 8)]
+    );
+    assert_eq!(atlas.data.len(), 16 * 24 * 4);
+    assert_eq!(atlas.data[16 * 16 * 4..16 * 16 * 4 + 4], [0xFF, 0, 0, 0xFF]);
+    assert_eq!(atlas.tlut, None);
+    Ok(())
+}
+
+#[test]
+fn atlas_padding_and_extrusion() -> Result<()> {
+    let images = [
+        solid(4, 4, [0xFF; 4]),
+        solid(4, 4, [0x80, 0x80, 0x80, 0xFF]),
+    ];
+    let options = AtlasOptions {
+        padding: 2,
+        extrude: 1,
+        power_of_two: true,
+        ..Default::default()
+    };
+    let atlas = build_atlas(&images, ImageType::I8, options)?;
+
+    assert_eq!((atlas.width, atlas.height), (8, 16));
+    assert_eq!(atlas.entries, [entry(1, 1, 4, 4), entry(1, 9, 4, 4)]);
+
+    // Each image is surrounded by a copy of its edges, then by transparent padding
+    assert_eq!(atlas.data[..8], [0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0, 0]);
+    assert_eq!(atlas.data[6 * 8..8 * 8], [0; 16]);
+    assert_eq!(atlas.data[8 * 8..8 * 8 + 6], [0x80; 6]);
+    assert_eq!(atlas.data[14 * 8..], [0; 16]);
+    Ok(())
+}
+
+#[test]
+fn atlas_shares_palette() -> Result<()> {
+    let images = [
+        solid(4, 4, [0xFF, 0, 0, 0xFF]),
+        solid(4, 4, [0, 0, 0xFF, 0xFF]),
+    ];
+    let atlas = build_atlas(&images, ImageType::Ci4, AtlasOptions::default())?;
+
+    // Both images and the transparent space around them
+    assert_eq!(atlas.tlut.map(|tlut| tlut.len()), Some(3 * 2));
+    assert_eq!(atlas.data.len(), (atlas.width * atlas.height / 2) as usize);
+    Ok(())
+}
+
+#[test]
+fn atlas_errors() {
+    let result = build_atlas(&[], ImageType::Rgba16, AtlasOptions::default());
+    assert!(matches!(result, Err(Error::EmptyAtlas)));
+
+    let images = [solid(32, 32, [0; 4]), solid(32, 32, [0; 4])];
+    let options = AtlasOptions {
+        fit_tmem: true,
+        ..Default::default()
+    };
+    let result = build_atlas(&images, ImageType::Rgba32, options);
+    assert!(matches!(result, Err(Error::TmemOverflow { .. })));
+}
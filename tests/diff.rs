@@ -0,0 +1,85 @@
+use anyhow::Result;
+use pigment64::diff::diff_images;
+use pigment64::{Error, ImageType, NativeImage, PNGImage};
+
+fn image(format: ImageType, width: u32, height: u32, data: &[u8]) -> NativeImage {
+    NativeImage {
+        format,
+        width,
+        height,
+        data: data.to_vec(),
+    }
+}
+
+#[test]
+fn diff_identical() -> Result<()> {
+    let data = include_bytes!("rgba32.png.bin");
+    let a = image(ImageType::Rgba32, 32, 32, data);
+    let b = image(ImageType::Rgba32, 32, 32, data);
+
+    let diff = diff_images(&a, None, &b, None)?;
+
+    assert_eq!(diff.differing_texels, 0);
+    assert_eq!(diff.max_channel_error, 0);
+    assert_eq!(diff.first_difference(), None);
+    assert!(diff.psnr.is_infinite());
+    Ok(())
+}
+
+#[test]
+fn diff_statistics() -> Result<()> {
+    let mut data = [0u8; 4 * 4];
+    let a = image(ImageType::I8, 4, 4, &data);
+    data[6] = 10;
+    data[9] = 40;
+    let b = image(ImageType::I8, 4, 4, &data);
+
+    let diff = diff_images(&a, None, &b, None)?;
+
+    assert_eq!(diff.differing_texels, 2);
+    assert_eq!(diff.max_channel_error, 40);
+    assert_eq!(diff.first_difference(), Some((2, 1)));
+    assert_eq!(diff.errors[6], 10);
+
+    // Three channels of two texels differ, out of 64 channels in total
+    let mse = (3.0 * 10.0f64.powi(2) + 3.0 * 40.0f64.powi(2)) / 64.0;
+    assert!((diff.mse - mse).abs() < 1e-9);
+    assert!((diff.psnr - 10.0 * (255.0f64.powi(2) / mse).log10()).abs() < 1e-9);
+
+    let mut png = Vec::new();
+    diff.write_png(&mut png)?;
+    let png = PNGImage::read(png.as_slice())?;
+    assert_eq!((png.width(), png.height()), (4 * 3 + 4, 4));
+    Ok(())
+}
+
+#[test]
+fn diff_palette_indices() -> Result<()> {
+    // Entries 0 and 1 hold the same color
+    let tlut = [[0xFF, 0x00, 0x00, 0xFF]; 16].concat();
+    let a = image(ImageType::Ci4, 2, 1, &[0x00]);
+    let b = image(ImageType::Ci4, 2, 1, &[0x01]);
+
+    let diff = diff_images(&a, Some(&tlut), &b, Some(&tlut))?;
+
+    assert_eq!(diff.differing_texels, 1);
+    assert_eq!(diff.first_difference(), Some((1, 0)));
+    assert_eq!(diff.max_channel_error, 0);
+    Ok(())
+}
+
+#[test]
+fn diff_dimension_mismatch() {
+    let a = image(ImageType::I8, 4, 4, &[0; 16]);
+    let b = image(ImageType::I8, 8, 2, &[0; 16]);
+
+    let result = diff_images(&a, None, &b, None);
+
+    assert!(matches!(
+        result,
+        Err(Error::DimensionMismatch {
+            expected: (4, 4),
+            actual: (8, 2),
+        })
+    ));
+}
@@ -0,0 +1,198 @@
+use anyhow::Result;
+use pigment64::gbi::display_list::extract_textures;
+use pigment64::gbi::{Microcode, SegmentTable};
+use pigment64::{ImageType, TextureLUT};
+
+fn write_command(buffer: &mut [u8], offset: usize, w0: u32, w1: u32) {
+    buffer[offset..offset + 4].copy_from_slice(&w0.to_be_bytes());
+    buffer[offset + 4..offset + 8].copy_from_slice(&w1.to_be_bytes());
+}
+
+#[test]
+fn ci4_load_block_f3dex2() -> Result<()> {
+    // Segment 6 starts 0x40 bytes into the buffer
+    let mut data = vec![0u8; 0x40 + 0x300];
+    let base = 0x40;
+
+    let tlut: Vec<u8> = (0..32).collect();
+    let texels: Vec<u8> = (0..128).map(|i| i as u8).collect();
+    data[base + 0x100..base + 0x120].copy_from_slice(&tlut);
+    data[base + 0x200..base + 0x280].copy_from_slice(&texels);
+
+    #[rustfmt::skip]
+    let commands: [(u32, u32); 10] = [
+        (0xE300_1001, 0x0000_8000), // gsDPSetTextureLUT(G_TT_RGBA16)
+        (0xFD10_0000, 0x0600_0100), // gsDPSetTextureImage(G_IM_FMT_RGBA, G_IM_SIZ_16b, 1, tlut)
+        (0xF500_0100, 0x0700_0000), // gsDPSetTile(..., 256, G_TX_LOADTILE, ...)
+        (0xF000_0000, 0x0703_C000), // gsDPLoadTLUTCmd(G_TX_LOADTILE, 15)
+        (0xFD50_0000, 0x0600_0200), // gsDPSetTextureImage(G_IM_FMT_CI, G_IM_SIZ_16b, 1, tex)
+        (0xF550_0000, 0x0700_0000), // gsDPSetTile(G_IM_FMT_CI, G_IM_SIZ_16b, 0, 0, G_TX_LOADTILE, ...)
+        (0xF300_0000, 0x0703_F800), // gsDPLoadBlock(G_TX_LOADTILE, 0, 0, 63, 2048)
+        (0xF540_0200, 0x0000_0000), // gsDPSetTile(G_IM_FMT_CI, G_IM_SIZ_4b, 1, 0, G_TX_RENDERTILE, ...)
+        (0xF200_0000, 0x0003_C03C), // gsDPSetTileSize(G_TX_RENDERTILE, 0, 0, 15 << 2, 15 << 2)
+        (0xDF00_0000, 0x0000_0000), // gsSPEndDisplayList()
+    ];
+    for (i, (w0, w1)) in commands.iter().enumerate() {
+        write_command(&mut data, base + i * 8, *w0, *w1);
+    }
+
+    // An outer display list calling into the one above
+    write_command(&mut data, base + 0x80, 0xDE00_0000, 0x0600_0000);
+    write_command(&mut data, base + 0x88, 0xDF00_0000, 0x0000_0000);
+
+    let mut segments = SegmentTable::default();
+    segments.set(6, base);
+    let result = extract_textures(&data, &segments, Microcode::F3dex2, 0x0600_0080)?;
+
+    assert!(result.unresolved.is_empty());
+    assert_eq!(result.textures.len(), 1);
+
+    let texture = &result.textures[0];
+    assert_eq!(texture.address, 0x0600_0200);
+    assert_eq!(texture.image.format, ImageType::Ci4);
+    assert_eq!((texture.image.width, texture.image.height), (16, 16));
+    assert_eq!(texture.image.data, texels);
+    assert_eq!(texture.tlut_mode, TextureLUT::Rgba16);
+    assert_eq!(texture.tlut_address, Some(0x0600_0100));
+    assert_eq!(texture.tlut.as_deref(), Some(tlut.as_slice()));
+    Ok(())
+}
+
+#[test]
+fn ci4_palette_bank() -> Result<()> {
+    let mut data = vec![0u8; 0x200];
+    let tlut: Vec<u8> = (0..32).collect();
+    data[0x100..0x120].copy_from_slice(&tlut);
+
+    #[rustfmt::skip]
+    let commands: [(u32, u32); 9] = [
+        (0xFD10_0000, 0x0600_0100), // gsDPSetTextureImage(G_IM_FMT_RGBA, G_IM_SIZ_16b, 1, tlut)
+        (0xF500_0110, 0x0700_0000), // gsDPSetTile(..., 272, G_TX_LOADTILE, ...)
+        (0xF000_0000, 0x0703_C000), // gsDPLoadTLUTCmd(G_TX_LOADTILE, 15)
+        (0xFD50_0000, 0x0600_0180), // gsDPSetTextureImage(G_IM_FMT_CI, G_IM_SIZ_16b, 1, tex)
+        (0xF550_0000, 0x0700_0000), // gsDPSetTile(G_IM_FMT_CI, G_IM_SIZ_16b, 0, 0, G_TX_LOADTILE, ...)
+        (0xF300_0000, 0x0700_F800), // gsDPLoadBlock(G_TX_LOADTILE, 0, 0, 15, 2048)
+        (0xF540_0200, 0x0010_0000), // gsDPSetTile(G_IM_FMT_CI, G_IM_SIZ_4b, 1, 0, G_TX_RENDERTILE, 1, ...)
+        (0xF200_0000, 0x0001_C01C), // gsDPSetTileSize(G_TX_RENDERTILE, 0, 0, 7 << 2, 7 << 2)
+        (0xDF00_0000, 0x0000_0000), // gsSPEndDisplayList()
+    ];
+    for (i, (w0, w1)) in commands.iter().enumerate() {
+        write_command(&mut data, i * 8, *w0, *w1);
+    }
+
+    let mut segments = SegmentTable::default();
+    segments.set(6, 0);
+    let result = extract_textures(&data, &segments, Microcode::F3dex2, 0x0600_0000)?;
+
+    // gsDPLoadTLUT_pal16(1, tlut) fills entries 16 to 31, which palette 1 uses
+    assert_eq!(result.textures.len(), 1);
+    let texture = &result.textures[0];
+    assert_eq!(texture.tlut_address, Some(0x0600_0100));
+    assert_eq!(texture.tlut.as_deref(), Some(tlut.as_slice()));
+    Ok(())
+}
+
+#[test]
+fn i4_load_texture_tile() -> Result<()> {
+    // A 32x16 I4 image, 16 bytes per row
+    let mut data = vec![0u8; 0x200];
+    let texels: Vec<u8> = (0..=255).collect();
+    data[0x100..0x200].copy_from_slice(&texels);
+
+    // gsDPLoadTextureTile_4b(tex, G_IM_FMT_I, 32, 16, 8, 2, 23, 9, ...) loads the texels as
+    // 8-bit ones, half as wide
+    #[rustfmt::skip]
+    let commands: [(u32, u32); 6] = [
+        (0xFD88_000F, 0x0600_0100), // gsDPSetTextureImage(G_IM_FMT_I, G_IM_SIZ_8b, 16, tex)
+        (0xF588_0200, 0x0700_0000), // gsDPSetTile(G_IM_FMT_I, G_IM_SIZ_8b, 1, 0, G_TX_LOADTILE, ...)
+        (0xF401_0008, 0x0702_C024), // gsDPLoadTile(G_TX_LOADTILE, 4 << 2, 2 << 2, 11 << 2, 9 << 2)
+        (0xF580_0200, 0x0000_0000), // gsDPSetTile(G_IM_FMT_I, G_IM_SIZ_4b, 1, 0, G_TX_RENDERTILE, ...)
+        (0xF202_0008, 0x0005_C024), // gsDPSetTileSize(G_TX_RENDERTILE, 8 << 2, 2 << 2, 23 << 2, 9 << 2)
+        (0xDF00_0000, 0x0000_0000), // gsSPEndDisplayList()
+    ];
+    for (i, (w0, w1)) in commands.iter().enumerate() {
+        write_command(&mut data, i * 8, *w0, *w1);
+    }
+
+    let mut segments = SegmentTable::default();
+    segments.set(6, 0);
+    let result = extract_textures(&data, &segments, Microcode::F3dex2, 0x0600_0000)?;
+
+    assert!(result.unresolved.is_empty());
+    assert_eq!(result.textures.len(), 1);
+    let texture = &result.textures[0];
+    assert_eq!(texture.address, 0x0600_0100 + 2 * 16 + 4);
+    assert_eq!(texture.image.format, ImageType::I4);
+    assert_eq!((texture.image.width, texture.image.height), (16, 8));
+
+    let rows: Vec<u8> = (2..10)
+        .flat_map(|row| texels[row * 16 + 4..row * 16 + 12].to_vec())
+        .collect();
+    assert_eq!(texture.image.data, rows);
+    Ok(())
+}
+
+#[test]
+fn ci8_partial_palette() -> Result<()> {
+    let mut data = vec![0u8; 0x200];
+    let tlut: Vec<u8> = (0..32).collect();
+    data[0x100..0x120].copy_from_slice(&tlut);
+
+    #[rustfmt::skip]
+    let commands: [(u32, u32); 9] = [
+        (0xFD10_0000, 0x0600_0100), // gsDPSetTextureImage(G_IM_FMT_RGBA, G_IM_SIZ_16b, 1, tlut)
+        (0xF500_0100, 0x0700_0000), // gsDPSetTile(..., 256, G_TX_LOADTILE, ...)
+        (0xF000_0000, 0x0703_C000), // gsDPLoadTLUTCmd(G_TX_LOADTILE, 15)
+        (0xFD50_0000, 0x0600_0180), // gsDPSetTextureImage(G_IM_FMT_CI, G_IM_SIZ_16b, 1, tex)
+        (0xF550_0000, 0x0700_0000), // gsDPSetTile(G_IM_FMT_CI, G_IM_SIZ_16b, 0, 0, G_TX_LOADTILE, ...)
+        (0xF300_0000, 0x0701_F800), // gsDPLoadBlock(G_TX_LOADTILE, 0, 0, 31, 2048)
+        (0xF548_0200, 0x0000_0000), // gsDPSetTile(G_IM_FMT_CI, G_IM_SIZ_8b, 1, 0, G_TX_RENDERTILE, ...)
+        (0xF200_0000, 0x0001_C01C), // gsDPSetTileSize(G_TX_RENDERTILE, 0, 0, 7 << 2, 7 << 2)
+        (0xDF00_0000, 0x0000_0000), // gsSPEndDisplayList()
+    ];
+    for (i, (w0, w1)) in commands.iter().enumerate() {
+        write_command(&mut data, i * 8, *w0, *w1);
+    }
+
+    let mut segments = SegmentTable::default();
+    segments.set(6, 0);
+    let result = extract_textures(&data, &segments, Microcode::F3dex2, 0x0600_0000)?;
+
+    // Only the 16 loaded entries of the 256 a CI8 texture can use are kept
+    assert_eq!(result.textures.len(), 1);
+    let texture = &result.textures[0];
+    assert_eq!(texture.image.format, ImageType::Ci8);
+    assert_eq!(texture.tlut.as_deref(), Some(tlut.as_slice()));
+    Ok(())
+}
+
+#[test]
+fn overflowing_addresses() -> Result<()> {
+    let mut data = vec![0u8; 0x20];
+
+    // A tile at the end of the address space, loaded from its second row
+    write_command(&mut data, 0x00, 0xFD10_0007, 0xFFFF_FFF0);
+    write_command(&mut data, 0x08, 0xF400_0004, 0x0701_C004);
+    write_command(&mut data, 0x10, 0xDF00_0000, 0x0000_0000);
+
+    let mut segments = SegmentTable::default();
+    segments.set(6, 0);
+    let result = extract_textures(&data, &segments, Microcode::F3dex2, 0x0600_0000)?;
+
+    assert!(result.textures.is_empty());
+    assert_eq!(result.unresolved, vec![0xFFFF_FFF0]);
+    Ok(())
+}
+
+#[test]
+fn unmapped_segment() -> Result<()> {
+    let mut data = vec![0u8; 16];
+    write_command(&mut data, 0, 0xDE00_0000, 0x0800_0000);
+    write_command(&mut data, 8, 0xDF00_0000, 0x0000_0000);
+
+    let result = extract_textures(&data, &SegmentTable::default(), Microcode::F3dex2, 0)?;
+
+    assert!(result.textures.is_empty());
+    assert_eq!(result.unresolved, vec![0x0800_0000]);
+    Ok(())
+}
@@ -0,0 +1,211 @@
+use anyhow::Result;
+use pigment64::color::Color;
+use pigment64::image::flipbook::{Flipbook, StripLayout, split_strip};
+use pigment64::{Error, ImageType, PNGImage};
+
+const RED: [u8; 4] = [0xFF, 0x00, 0x00, 0xFF];
+const GREEN: [u8; 4] = [0x00, 0xFF, 0x00, 0xFF];
+const BLUE: [u8; 4] = [0x00, 0x00, 0xFF, 0xFF];
+const CLEAR: [u8; 4] = [0x00, 0x00, 0x00, 0x00];
+
+/// Returns a `width`x`height` RGBA8 image filled with `color`.
+fn solid(width: u32, height: u32, color: [u8; 4]) -> PNGImage {
+    PNGImage::from_rgba8(width, height, color.repeat((width * height) as usize))
+}
+
+#[test]
+fn flipbook_strips() -> Result<()> {
+    // Three 2x2 frames side by side
+    let strip: Vec<u8> = [RED, GREEN, BLUE]
+        .repeat(2)
+        .iter()
+        .flat_map(|c| c.repeat(2))
+        .collect();
+    let png = PNGImage::from_rgba8(6, 2, strip);
+
+    let frames = split_strip(&png, StripLayout::Horizontal, 3)?;
+    assert_eq!(frames.len(), 3);
+    for (frame, color) in frames.iter().zip([RED, GREEN, BLUE]) {
+        assert_eq!(frame.as_rgba8(), color.repeat(4));
+    }
+
+    let flipbook = Flipbook::from_frames(&frames, ImageType::Rgba16)?;
+    assert_eq!((flipbook.width, flipbook.height), (2, 2));
+    assert_eq!(flipbook.data()[..2], [0xF8, 0x01]);
+    assert_eq!(flipbook.data().len(), 3 * 2 * 2 * 2);
+    assert_eq!(
+        flipbook.frame(1).map(|frame| frame.data[..2].to_vec()),
+        Some(vec![0x07, 0xC1])
+    );
+
+    let mut bytes = Vec::new();
+    flipbook.write_strip(&mut bytes, StripLayout::Vertical)?;
+    let vertical = PNGImage::read(bytes.as_slice())?;
+    assert_eq!((vertical.width(), vertical.height()), (2, 6));
+    let frames = split_strip(&vertical, StripLayout::Vertical, 3)?;
+    for (frame, color) in frames.iter().zip([RED, GREEN, BLUE]) {
+        assert_eq!(frame.as_rgba8(), color.repeat(4));
+    }
+    Ok(())
+}
+
+#[test]
+fn flipbook_shared_palette() -> Result<()> {
+    let red = Color::RGBA(0xFF, 0x00, 0x00, 0xFF);
+    let green = Color::RGBA(0x00, 0xFF, 0x00, 0xFF);
+    let blue = Color::RGBA(0x00, 0x00, 0xFF, 0xFF);
+    let frames = [
+        PNGImage::from_indexed(2, 1, vec![0, 1], &[red, green]),
+        PNGImage::from_indexed(2, 1, vec![1, 0], &[blue, green]),
+    ];
+
+    let flipbook = Flipbook::from_frames(&frames, ImageType::Ci4)?;
+    assert_eq!(
+        flipbook.tlut,
+        Some([red, green, blue].iter().flat_map(|c| c.rgba16()).collect())
+    );
+    assert_eq!(flipbook.frames, [vec![0x01], vec![0x12]]);
+
+    // Frames decode as indexed PNGs sharing the palette
+    let decoded = flipbook.decode_frames()?;
+    assert_eq!(decoded[1].indices(), Some(vec![1, 2]));
+    assert_eq!(decoded[1].palette().map(|p| p.len()), Some(3));
+    assert_eq!(decoded[1].as_rgba8(), [GREEN, BLUE].concat());
+
+    let native = Flipbook::from_native(
+        ImageType::Ci4,
+        2,
+        1,
+        &flipbook.data(),
+        flipbook.tlut.clone(),
+    )?;
+    assert_eq!(native, flipbook);
+    Ok(())
+}
+
+#[test]
+fn flipbook_odd_4bit_frames() -> Result<()> {
+    // Two 3x2 frames side by side, each row padded to 2 bytes once encoded
+    let white = [0xFF; 4];
+    let black = [0x00, 0x00, 0x00, 0xFF];
+    let strip: Vec<u8> = [[white, black, white, black, white, black]; 2]
+        .concat()
+        .concat();
+    let frames = split_strip(
+        &PNGImage::from_rgba8(6, 2, strip),
+        StripLayout::Horizontal,
+        2,
+    )?;
+
+    for format in [ImageType::Ci4, ImageType::I4] {
+        let flipbook = Flipbook::from_frames(&frames, format)?;
+        assert_eq!(flipbook.data().len(), 2 * 2 * 2);
+
+        let native = Flipbook::from_native(format, 3, 2, &flipbook.data(), flipbook.tlut.clone())?;
+        assert_eq!(native, flipbook);
+        // Texels stay in place once decoded, whatever the precision of the format
+        let lit = |png: &PNGImage| -> Vec<bool> {
+            png.as_rgba8()
+                .chunks_exact(4)
+                .map(|p| p[0] > 0x80)
+                .collect()
+        };
+        for (decoded, frame) in native.decode_frames()?.iter().zip(&frames) {
+            assert_eq!(lit(decoded), lit(frame));
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn flipbook_apng_round_trip() -> Result<()> {
+    let frames = [solid(4, 2, RED), solid(4, 2, GREEN), solid(4, 2, CLEAR)];
+    let flipbook = Flipbook::from_frames(&frames, ImageType::Rgba32)?;
+
+    let mut bytes = Vec::new();
+    flipbook.write_apng(&mut bytes, 1, 10)?;
+    let decoded = PNGImage::read_frames(bytes.as_slice())?;
+    assert_eq!(decoded.len(), 3);
+    for (decoded, frame) in decoded.iter().zip(&frames) {
+        assert_eq!(decoded.as_rgba8(), frame.as_rgba8());
+    }
+
+    // A PNG without animation is a single frame
+    let still = PNGImage::read_frames(include_bytes!("ci4.png").as_slice())?;
+    assert_eq!(still.len(), 1);
+    assert_eq!(still[0].indices().map(|i| i.len()), Some(16));
+    Ok(())
+}
+
+#[test]
+fn flipbook_apng_compositing() -> Result<()> {
+    let mut bytes = Vec::new();
+    let mut encoder = png::Encoder::new(&mut bytes, 2, 2);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_animated(3, 0)?;
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&RED.repeat(4))?;
+
+    // A half-transparent blue pixel blended over the bottom right of the first frame, then
+    // cleared once shown
+    writer.set_frame_dimension(1, 1)?;
+    writer.set_frame_position(1, 1)?;
+    writer.set_blend_op(png::BlendOp::Over)?;
+    writer.set_dispose_op(png::DisposeOp::Background)?;
+    writer.write_image_data(&[0x00, 0x00, 0xFF, 0x80])?;
+
+    writer.set_frame_position(0, 0)?;
+    writer.set_blend_op(png::BlendOp::Source)?;
+    writer.set_dispose_op(png::DisposeOp::None)?;
+    writer.write_image_data(&GREEN)?;
+    writer.finish()?;
+
+    let frames = PNGImage::read_frames(bytes.as_slice())?;
+    assert_eq!(frames.len(), 3);
+    assert_eq!(frames[0].as_rgba8(), RED.repeat(4));
+    assert_eq!(
+        frames[1].as_rgba8(),
+        [RED, RED, RED, [0x7F, 0x00, 0x80, 0xFF]].concat()
+    );
+    assert_eq!(frames[2].as_rgba8(), [GREEN, RED, RED, CLEAR].concat());
+    Ok(())
+}
+
+#[test]
+fn flipbook_errors() {
+    assert!(matches!(
+        split_strip(&solid(5, 2, RED), StripLayout::Horizontal, 2),
+        Err(Error::UnevenStrip {
+            length: 5,
+            frames: 2
+        })
+    ));
+    assert!(matches!(
+        split_strip(&solid(4, 2, RED), StripLayout::Vertical, 0),
+        Err(Error::UnevenStrip { .. })
+    ));
+    assert!(matches!(
+        Flipbook::from_frames(&[], ImageType::Rgba16),
+        Err(Error::EmptyFlipbook)
+    ));
+    assert!(matches!(
+        Flipbook::from_frames(&[solid(2, 2, RED), solid(2, 4, RED)], ImageType::I8),
+        Err(Error::DimensionMismatch {
+            expected: (2, 2),
+            actual: (2, 4)
+        })
+    ));
+    assert!(matches!(
+        Flipbook::from_native(ImageType::Rgba16, 2, 2, &[0; 12], None),
+        Err(Error::UnevenFrames {
+            size: 12,
+            frame_size: 8
+        })
+    ));
+    assert!(matches!(
+        Flipbook::from_native(ImageType::Ci8, 1, 1, &[0], None)
+            .and_then(|flipbook| flipbook.decode_frames()),
+        Err(Error::MissingTlut)
+    ));
+}
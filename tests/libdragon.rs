@@ -0,0 +1,150 @@
+use anyhow::Result;
+use pigment64::image::libdragon::{LibdragonSprite, from_tex_format, tex_format};
+use pigment64::{Error, ImageType, PNGImage};
+use strum::IntoEnumIterator;
+
+#[test]
+fn sprite_tex_formats() -> Result<()> {
+    let expected = [
+        (ImageType::Rgba16, 2),
+        (ImageType::Rgba32, 3),
+        (ImageType::Ci4, 8),
+        (ImageType::Ci8, 9),
+        (ImageType::Ia4, 12),
+        (ImageType::Ia8, 13),
+        (ImageType::Ia16, 14),
+        (ImageType::I4, 16),
+        (ImageType::I8, 17),
+    ];
+    for (format, value) in expected {
+        assert_eq!(tex_format(format)?, value);
+        assert_eq!(from_tex_format(value)?, format);
+    }
+
+    for format in ImageType::iter().filter(|format| *format != ImageType::I1) {
+        assert_eq!(from_tex_format(tex_format(format)?)?, format);
+    }
+    assert!(matches!(
+        tex_format(ImageType::I1),
+        Err(Error::UnsupportedSpriteFormat(ImageType::I1))
+    ));
+    assert!(matches!(from_tex_format(4), Err(Error::InvalidSprite(_))));
+    Ok(())
+}
+
+#[test]
+fn sprite_round_trip_ci4() -> Result<()> {
+    let png = PNGImage::read(include_bytes!("ci4.png").as_slice())?;
+    let mut sprite = LibdragonSprite::from_png(&png, ImageType::Ci4, 0)?;
+    sprite.hslices = 2;
+
+    let mut bytes = Vec::new();
+    sprite.write(&mut bytes)?;
+
+    // Header, texels, then the extended header pointing to the palette
+    assert_eq!(bytes[..8], [0, 4, 0, 4, 0, 0x88, 2, 1]);
+    assert_eq!(bytes[8..16], sprite.data[..]);
+    assert_eq!(bytes[16..20], [0, 124, 0, 4]);
+    let palette = u32::from_be_bytes([bytes[20], bytes[21], bytes[22], bytes[23]]) as usize;
+    assert_eq!(palette, 16 + 124 + 4);
+    assert_eq!(bytes.len(), palette + 16 * 2);
+
+    let read = LibdragonSprite::read(bytes.as_slice())?;
+    assert_eq!(read.data, sprite.data);
+    assert_eq!((read.hslices, read.vslices), (2, 1));
+    let tlut = sprite.tlut.clone().unwrap_or_default();
+    assert_eq!(
+        read.tlut.as_ref().map(|t| t[..tlut.len()].to_vec()),
+        Some(tlut)
+    );
+
+    let mut output = Vec::new();
+    read.as_png(&mut output)?;
+    assert_eq!(
+        PNGImage::read(output.as_slice())?.as_rgba8(),
+        png.as_rgba8()
+    );
+    Ok(())
+}
+
+#[test]
+fn sprite_mipmaps() -> Result<()> {
+    let rgba: Vec<u8> = (0..8 * 8)
+        .flat_map(|i| [i * 4, 0x80, 0xFF - i, 0xFF])
+        .collect();
+    let png = PNGImage::from_rgba8(8, 8, rgba);
+    let sprite = LibdragonSprite::from_png(&png, ImageType::Rgba16, 2)?;
+    assert_eq!(sprite.lods.len(), 2);
+
+    let mut bytes = Vec::new();
+    sprite.write(&mut bytes)?;
+    let read = LibdragonSprite::read(bytes.as_slice())?;
+
+    assert_eq!(read, sprite);
+    let sizes: Vec<_> = read
+        .lods
+        .iter()
+        .map(|lod| (lod.width, lod.height))
+        .collect();
+    assert_eq!(sizes, [(4, 4), (2, 2)]);
+    assert_eq!(read.lods[1].data.len(), 2 * 2 * 2);
+    // The extended header flags hold the number of levels of detail
+    assert_eq!(bytes[8 + 128 + 64..8 + 128 + 66], [0, 2]);
+
+    // Levels of detail don't need power-of-two dimensions or to fit in TMEM
+    let png = PNGImage::from_rgba8(48, 48, vec![0x80; 48 * 48 * 4]);
+    let sprite = LibdragonSprite::from_png(&png, ImageType::Rgba32, 3)?;
+    let sizes: Vec<_> = sprite
+        .lods
+        .iter()
+        .map(|lod| (lod.width, lod.height))
+        .collect();
+    assert_eq!(sizes, [(24, 24), (12, 12), (6, 6)]);
+    Ok(())
+}
+
+#[test]
+fn sprite_4bit_mipmaps() -> Result<()> {
+    let rgba: Vec<u8> = (0..6 * 6)
+        .flat_map(|i| [i * 7, i * 7, i * 7, 0xFF])
+        .collect();
+    let png = PNGImage::from_rgba8(6, 6, rgba);
+
+    for format in [ImageType::I4, ImageType::Ci4] {
+        let sprite = LibdragonSprite::from_png(&png, format, 3)?;
+        let sizes: Vec<_> = sprite
+            .lods
+            .iter()
+            .map(|lod| (lod.width, lod.height))
+            .collect();
+        assert_eq!(sizes, [(3, 3), (1, 1)]);
+
+        // Rows of odd widths are padded to a whole byte
+        assert_eq!(sprite.data.len(), 3 * 6);
+        assert_eq!(sprite.lods[0].data.len(), 2 * 3);
+        assert_eq!(sprite.lods[1].data.len(), 1);
+
+        let mut bytes = Vec::new();
+        sprite.write(&mut bytes)?;
+        assert_eq!(LibdragonSprite::read(bytes.as_slice())?, sprite);
+    }
+    Ok(())
+}
+
+#[test]
+fn sprite_legacy_header() -> Result<()> {
+    let mut bytes = vec![0, 2, 0, 1, 2, 0, 1, 1];
+    bytes.extend_from_slice(&[0xF8, 0x01, 0x07, 0xC1]);
+
+    let sprite = LibdragonSprite::read(bytes.as_slice())?;
+    assert_eq!(sprite.format, ImageType::Rgba16);
+    assert_eq!(sprite.tlut, None);
+
+    let mut rgba = Vec::new();
+    sprite.as_native().decode(&mut rgba, None)?;
+    assert_eq!(rgba, [0xFF, 0, 0, 0xFF, 0, 0xFF, 0, 0xFF]);
+
+    let result = LibdragonSprite::read(&bytes[..10]);
+    assert!(matches!(result, Err(Error::InvalidSprite(_))));
+    Ok(())
+}
@@ -0,0 +1,104 @@
+use anyhow::Result;
+use pigment64::gbi::mipmap::{MipmapFilter, build_mipmaps};
+use pigment64::{Error, ImageType, PNGImage};
+
+/// Builds a grayscale RGBA8 image whose intensity increases with x.
+fn gradient(width: u32, height: u32) -> PNGImage {
+    let data = (0..width * height)
+        .flat_map(|i| {
+            let v = ((i % width) * 16) as u8;
+            [v, v, v, 0xFF]
+        })
+        .collect();
+    PNGImage::from_rgba8(width, height, data)
+}
+
+#[test]
+fn mipmap_levels_and_tiles() -> Result<()> {
+    let mipmaps = build_mipmaps(&gradient(8, 8), ImageType::Rgba16, MipmapFilter::Box, 8)?;
+
+    let sizes: Vec<_> = mipmaps.levels.iter().map(|l| (l.width, l.height)).collect();
+    assert_eq!(sizes, [(8, 8), (4, 4), (2, 2), (1, 1)]);
+
+    let tmem: Vec<_> = mipmaps.levels.iter().map(|l| l.tile.tmem).collect();
+    assert_eq!(tmem, [0, 16, 20, 22]);
+    let lines: Vec<_> = mipmaps.levels.iter().map(|l| l.tile.line).collect();
+    assert_eq!(lines, [2, 1, 1, 1]);
+    assert_eq!(mipmaps.tmem.len(), 23 * 8);
+
+    let level = &mipmaps.levels[1];
+    assert_eq!(level.tile.tile, 1);
+    assert_eq!((level.tile.s.shift, level.tile.s.mask), (1, 2));
+    assert_eq!((level.tile.lrs, level.tile.lrt), (3, 3));
+    Ok(())
+}
+
+#[test]
+fn mipmap_box_filter() -> Result<()> {
+    let mipmaps = build_mipmaps(&gradient(4, 4), ImageType::I8, MipmapFilter::Box, 2)?;
+    assert_eq!(mipmaps.levels.len(), 2);
+
+    // Texels 0x00 and 0x10 average to 0x08, 0x20 and 0x30 to 0x28
+    assert_eq!(mipmaps.levels[1].data, [0x08, 0x28, 0x08, 0x28]);
+
+    let nearest = build_mipmaps(&gradient(4, 4), ImageType::I8, MipmapFilter::Nearest, 2)?;
+    assert_eq!(nearest.levels[1].data, [0x00, 0x20, 0x00, 0x20]);
+    Ok(())
+}
+
+#[test]
+fn mipmap_tmem_swaps_odd_rows() -> Result<()> {
+    let mipmaps = build_mipmaps(&gradient(8, 2), ImageType::I8, MipmapFilter::Box, 1)?;
+
+    let row = [0x00, 0x10, 0x20, 0x30, 0x40, 0x50, 0x60, 0x70];
+    assert_eq!(mipmaps.tmem[..8], row);
+    assert_eq!(mipmaps.tmem[8..12], row[4..]);
+    assert_eq!(mipmaps.tmem[12..], row[..4]);
+    Ok(())
+}
+
+#[test]
+fn mipmap_color_indexed_palette() -> Result<()> {
+    let mipmaps = build_mipmaps(&gradient(8, 8), ImageType::Ci4, MipmapFilter::Box, 4)?;
+
+    let tlut = mipmaps.tlut.expect("CI mipmaps have a palette");
+    assert!(tlut.len() <= 16 * 2);
+    assert!(mipmaps.tmem.len() <= 2048);
+    Ok(())
+}
+
+#[test]
+fn mipmap_errors() {
+    let result = build_mipmaps(&gradient(6, 8), ImageType::Rgba16, MipmapFilter::Box, 4);
+    assert!(matches!(result, Err(Error::InvalidMipmapDimensions(6, 8))));
+
+    let result = build_mipmaps(&gradient(64, 64), ImageType::Rgba16, MipmapFilter::Box, 4);
+    assert!(matches!(
+        result,
+        Err(Error::TmemOverflow {
+            available: 4096,
+            ..
+        })
+    ));
+}
+
+#[test]
+fn mipmap_4bit_narrow_levels() -> Result<()> {
+    // Both texels of row y have intensity y * 0x20
+    let data = (0..8u8)
+        .flat_map(|y| [y * 0x20, y * 0x20, y * 0x20, 0xFF].repeat(2))
+        .collect();
+    let png = PNGImage::from_rgba8(2, 8, data);
+    let mipmaps = build_mipmaps(&png, ImageType::I4, MipmapFilter::Box, 2)?;
+
+    // Each row of the 1x4 level takes a byte, with the texel repeated in the padding
+    let level = &mipmaps.levels[1];
+    assert_eq!((level.width, level.height), (1, 4));
+    assert_eq!(level.data, [0x11, 0x55, 0x99, 0xDD]);
+    assert_eq!(mipmaps.tmem.len(), (8 + 4) * 8);
+
+    let mipmaps = build_mipmaps(&png, ImageType::Ci4, MipmapFilter::Box, 2)?;
+    assert_eq!(mipmaps.levels[1].data.len(), 4);
+    assert_eq!(mipmaps.tmem.len(), (8 + 4) * 8);
+    Ok(())
+}
@@ -1,6 +1,9 @@
 use anyhow::Result;
-use pigment64::image::native_image::parse_tlut;
-use pigment64::{ImageSize, ImageType, NativeImage, PNGImage, TextureLUT, create_palette_from_png};
+use pigment64::color::Color;
+use pigment64::image::native_image::{TlutIndexPolicy, parse_tlut, parse_tlut_entries};
+use pigment64::{
+    Error, ImageSize, ImageType, NativeImage, PNGImage, TextureLUT, create_palette_from_png,
+};
 use strum::{EnumCount, IntoEnumIterator};
 
 #[test]
@@ -222,3 +225,69 @@ fn test_swap_word_rows() {
 
     assert_eq!(image.data, expected_data);
 }
+
+#[test]
+fn parse_ia16_tlut() -> Result<()> {
+    let tlut_bytes: Vec<u8> = (0..16u8).flat_map(|i| [i * 16, 0xFF]).collect();
+    let tlut_table = parse_tlut(&tlut_bytes, ImageSize::Bits4, TextureLUT::Ia16)?;
+
+    assert_eq!(tlut_table.len(), 16 * 4);
+    assert_eq!(&tlut_table[4..8], &[0x10, 0x10, 0x10, 0xFF]);
+    Ok(())
+}
+
+#[test]
+fn ci8_high_indices() -> Result<()> {
+    // Every entry has a distinct color so each index can be told apart
+    let tlut: Vec<u8> = (0..=255u16)
+        .flat_map(|i| (i << 1 | 1).to_be_bytes())
+        .collect();
+    let table = parse_tlut(&tlut, ImageSize::Bits8, TextureLUT::Rgba16)?;
+
+    let data: Vec<u8> = (0..=255).collect();
+    let image = NativeImage::read(data.as_slice(), ImageType::Ci8, 16, 16)?;
+    let mut rgba: Vec<u8> = Vec::new();
+    image.decode(&mut rgba, Some(&table))?;
+    assert_eq!(rgba, table);
+
+    let mut output: Vec<u8> = Vec::new();
+    image.as_png(&mut output, Some(&table))?;
+    let png = PNGImage::read(output.as_slice())?;
+    assert_eq!(png.indices(), Some(data));
+    Ok(())
+}
+
+#[test]
+fn ci8_out_of_range_indices() -> Result<()> {
+    let tlut = [0xF8, 0x01, 0x07, 0xC1, 0x00, 0x3F];
+    let table = parse_tlut_entries(&tlut, 3, TextureLUT::Rgba16)?;
+    let image = NativeImage::read([1u8, 64, 255, 2].as_slice(), ImageType::Ci8, 4, 1)?;
+
+    let result = image.decode(&mut Vec::new(), Some(&table));
+    assert!(matches!(
+        result,
+        Err(Error::PaletteIndexOutOfRange {
+            index: 64,
+            entries: 3
+        })
+    ));
+
+    let mut rgba: Vec<u8> = Vec::new();
+    image.decode_with_policy(&mut rgba, Some(&table), TlutIndexPolicy::Clamp)?;
+    assert_eq!(rgba[4..12], table[8..12].repeat(2));
+
+    let marker = Color::RGB(0xFF, 0, 0xFF);
+    let policy = TlutIndexPolicy::Marker(marker);
+    let mut rgba: Vec<u8> = Vec::new();
+    image.decode_with_policy(&mut rgba, Some(&table), policy)?;
+    assert_eq!(rgba[..4], table[4..8]);
+    assert_eq!(rgba[4..12], [0xFF, 0, 0xFF, 0xFF].repeat(2));
+
+    // Indexed PNGs get the marker as an extra palette entry
+    let mut output: Vec<u8> = Vec::new();
+    image.as_png_with_policy(&mut output, Some(&table), policy)?;
+    let png = PNGImage::read(output.as_slice())?;
+    assert_eq!(png.indices(), Some(vec![1, 3, 3, 2]));
+    assert_eq!(png.palette().map(|p| p[3]), Some(marker));
+    Ok(())
+}
@@ -0,0 +1,509 @@
+use anyhow::Result;
+use pigment64::color::Color;
+use pigment64::image::native_image::{parse_tlut, parse_tlut_bank, parse_tlut_entries};
+use pigment64::palette::banks::{combine_banks, palette_bank};
+use pigment64::palette::edit::{
+    PaletteOrder, match_reference, merge_duplicates, remove_unused, set_transparent_index,
+    sort_palette,
+};
+use pigment64::palette::formats::{PaletteFileFormat, pad_palette, read_palette, write_palette};
+use pigment64::palette::shared::build_shared_palette;
+use pigment64::palette::swatch::{read_swatches, render_swatches};
+use pigment64::palette::variants::{extract_variants, render_variants};
+use pigment64::{
+    Error, ImageSize, ImageType, NativeImage, PNGImage, TextureLUT, create_palette_from_png,
+};
+use std::fs::File;
+use std::io::BufReader;
+
+const RED: Color = Color::RGBA(0xFF, 0, 0, 0xFF);
+const GREEN: Color = Color::RGBA(0, 0xFF, 0, 0xFF);
+const BLUE: Color = Color::RGBA(0, 0, 0xFF, 0xFF);
+const CLEAR: Color = Color::RGBA(0, 0, 0, 0);
+
+#[test]
+fn shared_palette_merges_indexed() -> Result<()> {
+    let images = [
+        PNGImage::from_indexed(2, 2, vec![0, 1, 1, 0], &[CLEAR, RED, BLUE]),
+        PNGImage::from_indexed(2, 2, vec![2, 2, 0, 1], &[BLUE, GREEN, CLEAR]),
+    ];
+    let shared = build_shared_palette(&images, ImageType::Ci4)?;
+
+    // Unused entries are dropped and duplicates are only kept once
+    assert_eq!(shared.palette, [CLEAR, RED, BLUE, GREEN]);
+    assert_eq!(shared.images[0].indices(), Some(vec![0, 1, 1, 0]));
+    assert_eq!(shared.images[1].indices(), Some(vec![0, 0, 2, 3]));
+    assert_eq!(shared.images[1].palette(), Some(shared.palette.clone()));
+    assert_eq!(shared.tlut().len(), 4 * 2);
+    Ok(())
+}
+
+#[test]
+fn shared_palette_quantizes_truecolor() -> Result<()> {
+    let indexed = PNGImage::read(BufReader::new(File::open("tests/ci4.png")?))?;
+    let truecolor = PNGImage::from_rgba8(2, 1, vec![0xFF, 0, 0, 0xFF, 0, 0xFF, 0, 0xFF]);
+    let shared = build_shared_palette(&[indexed.clone(), truecolor], ImageType::Ci8)?;
+
+    assert!(shared.palette.len() <= 256);
+    assert_eq!(shared.images[0].as_rgba8(), indexed.as_rgba8());
+    assert_eq!(
+        shared.images[1].as_rgba8(),
+        [0xFF, 0, 0, 0xFF, 0, 0xFF, 0, 0xFF]
+    );
+    Ok(())
+}
+
+#[test]
+fn shared_palette_overflow() {
+    let palette: Vec<Color> = (0..16).map(|i| Color::RGBA(i * 16, 0, 0, 0xFF)).collect();
+    let indices: Vec<u8> = (0..16).collect();
+    let first = PNGImage::from_indexed(16, 1, indices.clone(), &palette);
+    let second = PNGImage::from_indexed(1, 1, vec![0], &[GREEN]);
+
+    let result = build_shared_palette(&[first, second], ImageType::Ci4);
+    assert!(matches!(
+        result,
+        Err(Error::PaletteOverflow {
+            colors: 17,
+            max: 16
+        })
+    ));
+
+    let result = build_shared_palette(&[], ImageType::Rgba16);
+    assert!(matches!(result, Err(Error::NotColorIndexed(_))));
+}
+
+/// Builds a 256-entry TLUT whose bank `n` is filled with a gray of intensity `n * 16`.
+fn banked_tlut() -> Vec<u8> {
+    (0..256u16)
+        .flat_map(|i| Color::RGBA((i / 16 * 16) as u8, 0, 0, 0xFF).rgba16())
+        .collect()
+}
+
+#[test]
+fn palette_bank_parsing() -> Result<()> {
+    let tlut = banked_tlut();
+
+    let bank = parse_tlut_bank(&tlut, ImageSize::Bits4, TextureLUT::Rgba16, 3)?;
+    assert_eq!(bank.len(), 16 * 4);
+    assert_eq!(
+        bank[..4],
+        parse_tlut(&tlut[3 * 32..], ImageSize::Bits4, TextureLUT::Rgba16)?[..4]
+    );
+    assert_eq!(
+        parse_tlut_bank(&tlut, ImageSize::Bits8, TextureLUT::Rgba16, 0)?,
+        parse_tlut(&tlut, ImageSize::Bits8, TextureLUT::Rgba16)?
+    );
+
+    let result = parse_tlut_bank(&tlut, ImageSize::Bits8, TextureLUT::Rgba16, 1);
+    assert!(matches!(
+        result,
+        Err(Error::InvalidPaletteBank { bank: 1, .. })
+    ));
+    let result = parse_tlut_bank(&tlut[..64], ImageSize::Bits4, TextureLUT::Rgba16, 2);
+    assert!(matches!(
+        result,
+        Err(Error::InvalidPaletteBank { bank: 2, .. })
+    ));
+    Ok(())
+}
+
+#[test]
+fn palette_bank_round_trip() -> Result<()> {
+    let tlut = banked_tlut();
+    let colors: Vec<Color> = tlut
+        .chunks_exact(2)
+        .map(|c| Color::from_u16(u16::from_be_bytes([c[0], c[1]])))
+        .collect();
+
+    // Pixels are matched against bank 5 only, whose entries are all the same color
+    let image = PNGImage::from_rgba8(3, 1, [0xFF, 0, 0, 0xFF].repeat(3));
+    let mut data = Vec::new();
+    image.as_ci4_with_palette_bank(&mut data, &colors, 5)?;
+    assert_eq!(data, [0x00, 0x00]);
+
+    let native = NativeImage::read(data.as_slice(), ImageType::Ci4, 3, 1)?;
+    let table = parse_tlut(&tlut, ImageSize::Bits8, TextureLUT::Rgba16)?;
+    let mut png = Vec::new();
+    native.as_png_with_palette_bank(&mut png, &table, 5)?;
+
+    let decoded = PNGImage::read(png.as_slice())?;
+    assert_eq!(decoded.palette().map(|p| p.len()), Some(16));
+    let color = colors[5 * 16];
+    assert_eq!(
+        decoded.as_rgba8()[..4],
+        [color.r, color.g, color.b, color.a]
+    );
+
+    let result = native.as_png_with_palette_bank(&mut Vec::new(), &table, 16);
+    assert!(matches!(result, Err(Error::InvalidPaletteBank { .. })));
+    Ok(())
+}
+
+#[test]
+fn palette_banks_combine() -> Result<()> {
+    let combined = combine_banks(&[vec![RED, GREEN], vec![BLUE]])?;
+
+    assert_eq!(combined.len(), 17);
+    assert_eq!(combined[2..16], [CLEAR; 14]);
+    assert_eq!(palette_bank(&combined, 1)?, [BLUE]);
+    assert!(palette_bank(&combined, 2).is_err());
+
+    let result = combine_banks(&[vec![RED; 17]]);
+    assert!(matches!(
+        result,
+        Err(Error::PaletteOverflow { colors: 17, .. })
+    ));
+    Ok(())
+}
+
+#[test]
+fn palette_variants_round_trip() -> Result<()> {
+    let data = std::fs::read("tests/ci4.data.bin")?;
+    let tlut = std::fs::read("tests/ci4.tlut.bin")?;
+    let swapped: Vec<u8> = tlut.chunks_exact(2).rev().flatten().copied().collect();
+
+    let image = NativeImage::read(data.as_slice(), ImageType::Ci4, 4, 4)?;
+    let pngs = render_variants(&image, &[tlut.clone(), swapped.clone()])?;
+    let images = pngs
+        .iter()
+        .map(|png| PNGImage::read(png.as_slice()))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let variants = extract_variants(&images, ImageType::Ci4)?;
+    assert_eq!(variants.data, data);
+    assert_eq!(variants.tlut(0), tlut);
+    assert_eq!(variants.tlut(1), swapped);
+    Ok(())
+}
+
+#[test]
+fn palette_variants_from_truecolor() -> Result<()> {
+    let first = PNGImage::from_rgba8(
+        3,
+        1,
+        [[0xFF, 0, 0, 0xFF], [0; 4], [0xFF, 0, 0, 0xFF]].concat(),
+    );
+    let second = PNGImage::from_rgba8(
+        3,
+        1,
+        [[0, 0, 0xFF, 0xFF], [0; 4], [0, 0, 0xFF, 0xFF]].concat(),
+    );
+
+    let variants = extract_variants(&[first, second], ImageType::Ci8)?;
+    assert_eq!(variants.data, [0, 1, 0]);
+    assert_eq!(variants.palettes, [vec![RED, CLEAR], vec![BLUE, CLEAR]]);
+
+    // The two-color TLUTs of the variants render back to the images
+    let image = NativeImage::read(variants.data.as_slice(), ImageType::Ci8, 3, 1)?;
+    let pngs = render_variants(&image, &[variants.tlut(0), variants.tlut(1)])?;
+    let blue = PNGImage::read(pngs[1].as_slice())?;
+    assert_eq!(
+        blue.as_rgba8(),
+        [BLUE, CLEAR, BLUE]
+            .iter()
+            .flat_map(|c| [c.r, c.g, c.b, c.a])
+            .collect::<Vec<_>>()
+    );
+    Ok(())
+}
+
+#[test]
+fn palette_variants_errors() {
+    // The second image splits the red texels of the first into 17 different colors
+    let first = PNGImage::from_rgba8(17, 1, [0xFF, 0, 0, 0xFF].repeat(17));
+    let second = PNGImage::from_rgba8(17, 1, (0..17u8).flat_map(|i| [i * 8, 0, 0, 0xFF]).collect());
+    let result = extract_variants(&[first.clone(), second], ImageType::Ci4);
+    assert!(matches!(
+        result,
+        Err(Error::VariantsDiverge {
+            combinations: 17,
+            max: 16
+        })
+    ));
+
+    let smaller = PNGImage::from_rgba8(1, 1, vec![0; 4]);
+    let result = extract_variants(&[first, smaller], ImageType::Ci4);
+    assert!(matches!(result, Err(Error::DimensionMismatch { .. })));
+
+    let result = extract_variants(&[], ImageType::Ci4);
+    assert!(matches!(result, Err(Error::NoVariants)));
+}
+
+#[test]
+fn palette_edit_cleanup() -> Result<()> {
+    // Entry 3 only differs from entry 1 below RGBA5551 precision, and entry 2 is unused
+    let image = PNGImage::from_indexed(
+        4,
+        1,
+        vec![0, 1, 3, 4],
+        &[CLEAR, RED, GREEN, Color::RGBA(0xFC, 0, 0, 0xFF), BLUE],
+    );
+
+    let merged = merge_duplicates(&image)?;
+    assert_eq!(merged.palette(), Some(vec![CLEAR, RED, GREEN, BLUE]));
+    assert_eq!(merged.indices(), Some(vec![0, 1, 1, 3]));
+
+    let cleaned = remove_unused(&merged)?;
+    assert_eq!(cleaned.palette(), Some(vec![CLEAR, RED, BLUE]));
+    assert_eq!(cleaned.indices(), Some(vec![0, 1, 1, 2]));
+    assert_eq!(cleaned.as_rgba8(), merged.as_rgba8());
+    Ok(())
+}
+
+#[test]
+fn palette_edit_sort() -> Result<()> {
+    let white = Color::RGBA(0xFF, 0xFF, 0xFF, 0xFF);
+    let image = PNGImage::from_indexed(4, 1, vec![0, 1, 2, 3], &[white, BLUE, CLEAR, RED]);
+
+    let by_luminance = sort_palette(&image, PaletteOrder::Luminance)?;
+    assert_eq!(by_luminance.palette(), Some(vec![CLEAR, BLUE, RED, white]));
+    assert_eq!(by_luminance.indices(), Some(vec![3, 1, 0, 2]));
+    assert_eq!(by_luminance.as_rgba8(), image.as_rgba8());
+
+    let by_hue = sort_palette(&image, PaletteOrder::Hue)?;
+    assert_eq!(by_hue.palette(), Some(vec![CLEAR, white, RED, BLUE]));
+    Ok(())
+}
+
+#[test]
+fn palette_edit_transparent_index() -> Result<()> {
+    let image = PNGImage::from_indexed(3, 1, vec![0, 1, 2], &[RED, GREEN, BLUE]);
+    let edited = set_transparent_index(&image, 2)?;
+
+    assert_eq!(
+        edited.palette(),
+        Some(vec![Color::RGBA(0, 0, 0xFF, 0), GREEN, RED])
+    );
+    assert_eq!(edited.indices(), Some(vec![2, 1, 0]));
+
+    let result = set_transparent_index(&image, 3);
+    assert!(matches!(
+        result,
+        Err(Error::PaletteIndexOutOfRange {
+            index: 3,
+            entries: 3
+        })
+    ));
+    Ok(())
+}
+
+#[test]
+fn palette_edit_match_reference() -> Result<()> {
+    let image = PNGImage::from_indexed(3, 1, vec![0, 1, 1], &[BLUE, RED, GREEN]);
+
+    let matched = match_reference(&image, &[CLEAR, RED, GREEN, BLUE])?;
+    assert_eq!(matched.palette(), Some(vec![CLEAR, RED, GREEN, BLUE]));
+    assert_eq!(matched.indices(), Some(vec![3, 1, 1]));
+
+    let result = match_reference(&image, &[RED, GREEN]);
+    assert!(matches!(
+        result,
+        Err(Error::MissingPaletteColor { index: 0, .. })
+    ));
+
+    let result = remove_unused(&PNGImage::from_rgba8(1, 1, vec![0; 4]));
+    assert!(matches!(result, Err(Error::MissingPngPalette)));
+    Ok(())
+}
+
+#[test]
+fn palette_formats_round_trip() -> Result<()> {
+    let colors = [CLEAR, RED, GREEN, BLUE, Color::RGBA(0x80, 0x40, 0x20, 0xFF)];
+
+    for format in [
+        PaletteFileFormat::Rgba16,
+        PaletteFileFormat::Gpl,
+        PaletteFileFormat::Png,
+    ] {
+        let bytes = write_palette(&colors, format)?;
+        let read = read_palette(&bytes, format)?;
+        let expected = match format {
+            // RGBA16 keeps 5 bits per channel
+            PaletteFileFormat::Rgba16 => Color::RGBA(0x84, 0x42, 0x21, 0xFF),
+            _ => colors[4],
+        };
+        assert_eq!(read[..4], colors[..4], "{format:?}");
+        assert_eq!(read[4], expected, "{format:?}");
+    }
+
+    // Adobe color tables only keep a single transparent index
+    let act = write_palette(&colors, PaletteFileFormat::Act)?;
+    assert_eq!(act.len(), 772);
+    assert_eq!(read_palette(&act, PaletteFileFormat::Act)?, colors);
+
+    // JASC palettes have no alpha
+    let jasc = write_palette(&colors, PaletteFileFormat::Jasc)?;
+    let read = read_palette(&jasc, PaletteFileFormat::Jasc)?;
+    assert_eq!(read[0], Color::BLACK);
+    assert_eq!(read[1..], colors[1..]);
+    Ok(())
+}
+
+#[test]
+fn palette_formats_parsing() -> Result<()> {
+    let gpl = "GIMP Palette\nName: Test\nColumns: 4\n# comment\n255   0   0\tRed\n  0 0 255 Blue\n";
+    assert_eq!(
+        read_palette(gpl.as_bytes(), PaletteFileFormat::Gpl)?,
+        [RED, BLUE]
+    );
+
+    let jasc = "JASC-PAL\r\n0100\r\n2\r\n0 255 0\r\n0 0 255 0\r\n";
+    assert_eq!(
+        read_palette(jasc.as_bytes(), PaletteFileFormat::Jasc)?,
+        [GREEN, Color::RGBA(0, 0, 0xFF, 0)]
+    );
+
+    let ia16 = [0x80, 0xFF, 0x20, 0x00];
+    assert_eq!(
+        read_palette(&ia16, PaletteFileFormat::Ia16)?,
+        [
+            Color::RGBA(0x80, 0x80, 0x80, 0xFF),
+            Color::RGBA(0x20, 0x20, 0x20, 0)
+        ]
+    );
+
+    let result = read_palette(b"JASC-PAL\n0100\n3\n0 0 0\n", PaletteFileFormat::Jasc);
+    assert!(matches!(result, Err(Error::InvalidPaletteFile(_))));
+    let result = read_palette(b"255 0 0\n", PaletteFileFormat::Gpl);
+    assert!(matches!(result, Err(Error::InvalidPaletteFile(_))));
+
+    assert_eq!(
+        PaletteFileFormat::from_path("art/hero.GPL".as_ref()),
+        PaletteFileFormat::Gpl
+    );
+    assert_eq!(
+        PaletteFileFormat::from_path("hero.tlut.bin".as_ref()),
+        PaletteFileFormat::Rgba16
+    );
+    Ok(())
+}
+
+#[test]
+fn palette_swatches_round_trip() -> Result<()> {
+    let tlut = std::fs::read("tests/ci4.tlut.bin")?;
+    let colors = read_palette(&tlut, PaletteFileFormat::Rgba16)?;
+
+    let swatches = render_swatches(&colors, 4);
+    assert_eq!((swatches.width(), swatches.height()), (64, 4));
+    assert_eq!(read_swatches(&swatches)?, colors);
+
+    let mut png = Vec::new();
+    swatches.as_png(&mut png)?;
+    assert_eq!(read_palette(&png, PaletteFileFormat::Swatch)?, colors);
+    let png = write_palette(&colors, PaletteFileFormat::Swatch)?;
+    assert_eq!(read_palette(&png, PaletteFileFormat::Swatch)?, colors);
+
+    // Truecolor PNGs read as plain PNGs have one entry per pixel, even when they fit a grid
+    let mut png = Vec::new();
+    PNGImage::from_rgba8(32, 32, vec![0xFF; 32 * 32 * 4]).as_png(&mut png)?;
+    assert_eq!(read_palette(&png, PaletteFileFormat::Png)?.len(), 32 * 32);
+    Ok(())
+}
+
+#[test]
+fn palette_swatches_layout() -> Result<()> {
+    let translucent = Color::RGBA(0xFF, 0, 0, 0x80);
+    let mut colors = vec![translucent];
+    colors.extend(std::iter::repeat_n(GREEN, 16));
+
+    let swatches = render_swatches(&colors, 2);
+    assert_eq!((swatches.width(), swatches.height()), (32, 4));
+
+    // The bottom-right half of a translucent swatch is opaque
+    let rgba = swatches.as_rgba8();
+    assert_eq!(rgba[..4], [0xFF, 0, 0, 0x80]);
+    assert_eq!(rgba[(32 + 1) * 4..(32 + 2) * 4], [0xFF, 0, 0, 0xFF]);
+
+    // Cells past the last color are transparent
+    let read = read_swatches(&swatches)?;
+    assert_eq!(read.len(), 32);
+    assert_eq!(read[..17], colors[..]);
+    assert_eq!(read[17], CLEAR);
+
+    let result = read_swatches(&PNGImage::from_rgba8(24, 4, vec![0; 24 * 4 * 4]));
+    assert!(matches!(result, Err(Error::InvalidPaletteFile(_))));
+    Ok(())
+}
+
+#[test]
+fn partial_tlut_decoding() -> Result<()> {
+    let tlut = write_palette(&[RED, GREEN, BLUE], PaletteFileFormat::Rgba16)?;
+    let table = parse_tlut_entries(&tlut, 3, TextureLUT::Rgba16)?;
+    assert_eq!(table.len(), 3 * 4);
+
+    // Full TLUTs report how many entries are missing
+    let result = parse_tlut(&tlut, ImageSize::Bits4, TextureLUT::Rgba16);
+    assert!(matches!(
+        result,
+        Err(Error::TlutTooShort {
+            expected: 16,
+            actual: 3
+        })
+    ));
+
+    let image = NativeImage {
+        format: ImageType::Ci4,
+        width: 3,
+        height: 2,
+        data: vec![0x01, 0x20, 0x21, 0x00],
+    };
+    let mut png = Vec::new();
+    image.as_png(&mut png, Some(&table))?;
+    let decoded = PNGImage::read(png.as_slice())?;
+    assert_eq!(decoded.palette(), Some(vec![RED, GREEN, BLUE]));
+    assert_eq!(decoded.indices(), Some(vec![0, 1, 2, 2, 1, 0]));
+
+    // Indices past the last entry are rejected
+    let image = NativeImage {
+        data: vec![0x03, 0x00, 0x00, 0x00],
+        ..image
+    };
+    let result = image.as_png(&mut Vec::new(), Some(&table));
+    assert!(matches!(
+        result,
+        Err(Error::PaletteIndexOutOfRange {
+            index: 3,
+            entries: 3
+        })
+    ));
+    Ok(())
+}
+
+#[test]
+fn palette_padding() -> Result<()> {
+    let padded = pad_palette(&[RED, GREEN], 16)?;
+    assert_eq!(padded.len(), 16);
+    assert_eq!(padded[..2], [RED, GREEN]);
+    assert!(padded[2..].iter().all(|&c| c == CLEAR));
+
+    let tlut = write_palette(&pad_palette(&[RED], 256)?, PaletteFileFormat::Rgba16)?;
+    assert_eq!(tlut.len(), 256 * 2);
+
+    let result = pad_palette(&padded, 8);
+    assert!(matches!(
+        result,
+        Err(Error::PaletteOverflow { colors: 16, max: 8 })
+    ));
+    Ok(())
+}
+
+#[test]
+fn short_trns_palette() -> Result<()> {
+    // Only the first entry has a transparency, as GIMP writes it
+    let mut png = Vec::new();
+    let mut encoder = png::Encoder::new(&mut png, 3, 1);
+    encoder.set_color(png::ColorType::Indexed);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_palette(vec![0, 0, 0, 0xFF, 0, 0, 0, 0xFF, 0]);
+    encoder.set_trns(vec![0]);
+    encoder.write_header()?.write_image_data(&[0, 1, 2])?;
+
+    let mut tlut = Vec::new();
+    create_palette_from_png(png.as_slice(), &mut tlut)?;
+    assert_eq!(
+        tlut,
+        write_palette(&[CLEAR, RED, GREEN], PaletteFileFormat::Rgba16)?
+    );
+    Ok(())
+}
@@ -0,0 +1,144 @@
+use anyhow::Result;
+use pigment64::gbi::s2dex::{ObjBg, ObjLoad, ObjSprite, ObjTxtr};
+use pigment64::{Error, ImageType};
+
+#[test]
+fn s2dex_txtr() -> Result<()> {
+    // GS_TB_TSIZE(32 * 32, G_IM_SIZ_16b) and GS_TB_TLINE(32, G_IM_SIZ_16b)
+    assert_eq!(
+        ObjTxtr::block(ImageType::Rgba16, 32, 32, 0)?,
+        ObjTxtr::Block {
+            tmem: 0,
+            tsize: 255,
+            tline: 256
+        }
+    );
+    // GS_TT_TWIDTH(32, G_IM_SIZ_16b) and GS_TT_THEIGHT(32, G_IM_SIZ_16b)
+    assert_eq!(
+        ObjTxtr::tile(ImageType::Rgba16, 32, 32, 0)?,
+        ObjTxtr::Tile {
+            tmem: 0,
+            twidth: 31,
+            theight: 127
+        }
+    );
+    assert_eq!(
+        ObjTxtr::tlut(16, 16)?,
+        ObjTxtr::Tlut {
+            phead: 272,
+            pnum: 15
+        }
+    );
+
+    let source = ObjTxtr::block(ImageType::I8, 16, 16, 64)?.to_c("txtr", "tex");
+    assert_eq!(
+        source,
+        "uObjTxtr txtr = {\n\
+         \x20   G_OBJLT_TXTRBLOCK, /* Type */\n\
+         \x20   (u64 *)tex,        /* Image */\n\
+         \x20   64,                /* TMEM address */\n\
+         \x20   31,                /* Texture size */\n\
+         \x20   1024,              /* Texture line */\n\
+         \x20   0,                 /* Status ID */\n\
+         \x20   (u32)tex,          /* Status flag */\n\
+         \x20   0xFFFFFFFF,        /* Status mask */\n\
+         };\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn s2dex_txtr_errors() {
+    // Color-indexed textures only get the lower half of TMEM
+    assert!(matches!(
+        ObjTxtr::block(ImageType::Ci8, 64, 64, 0),
+        Err(Error::TmemOverflow {
+            required: 4096,
+            available: 2048
+        })
+    ));
+    assert!(matches!(
+        ObjTxtr::block(ImageType::Ci4, 4, 4, 0),
+        Err(Error::UnalignedTextureRow {
+            width: 4,
+            format: ImageType::Ci4
+        })
+    ));
+    assert!(ObjTxtr::tile(ImageType::Ci4, 4, 4, 0).is_ok());
+    assert!(matches!(
+        ObjTxtr::tile(ImageType::Rgba32, 8, 8, 0),
+        Err(Error::UnsupportedTmemFormat(ImageType::Rgba32))
+    ));
+    assert!(matches!(
+        ObjTxtr::tlut(250, 16),
+        Err(Error::PaletteOverflow { .. })
+    ));
+}
+
+#[test]
+fn s2dex_sprite() -> Result<()> {
+    let mut sprite = ObjSprite::new(ImageType::Ia8, 32, 16, 128)?;
+    assert_eq!((sprite.image_w, sprite.image_h), (32 << 5, 16 << 5));
+    assert_eq!(sprite.image_stride, 4);
+    assert_eq!(sprite.scale_w, 1 << 10);
+
+    sprite.obj_x = -8 << 2;
+    let source = sprite.to_c("sprite")?;
+    assert!(source.starts_with("uObjSprite sprite = {\n    -32,"));
+    assert!(source.contains("    128,         /* imageAdrs */\n"));
+    assert!(source.contains("    G_IM_FMT_IA, /* imageFmt */\n"));
+    assert!(source.contains("    G_IM_SIZ_8b, /* imageSiz */\n"));
+
+    assert!(matches!(
+        ObjSprite::new(ImageType::I4, 1024, 1, 0),
+        Err(Error::ObjectTooLarge {
+            width: 1024,
+            height: 1
+        })
+    ));
+    Ok(())
+}
+
+#[test]
+fn s2dex_bg() -> Result<()> {
+    let bg = ObjBg::new(ImageType::Rgba16, 320, 240, ObjLoad::Block)?;
+    assert_eq!((bg.image_w, bg.frame_h), (320 << 2, 240 << 2));
+    assert_eq!(
+        (bg.tmem_w, bg.tmem_h, bg.tmem_size_w, bg.tmem_size),
+        (80, 24, 160, 3840)
+    );
+    // Six rows of 320 texels per load
+    assert_eq!((bg.tmem_load_sh, bg.tmem_load_th), (1919, 26));
+
+    let bg = ObjBg::with_frame(
+        ImageType::Rgba16,
+        320,
+        240,
+        ObjLoad::Tile,
+        (16, 8, 256, 224),
+    )?;
+    assert_eq!((bg.frame_x, bg.frame_y), (16 << 2, 8 << 2));
+    assert_eq!(
+        (bg.tmem_w, bg.tmem_h, bg.tmem_size_w, bg.tmem_size),
+        (65, 28, 160, 4480)
+    );
+    assert_eq!((bg.tmem_load_sh, bg.tmem_load_th), (1039, 27));
+
+    // 250 RGBA16 texels take 62.5 words, which guS2DInitBg truncates to 62
+    let odd = ObjBg::with_frame(ImageType::Rgba16, 320, 240, ObjLoad::Tile, (0, 0, 250, 224))?;
+    assert_eq!(
+        (odd.tmem_w, odd.tmem_h, odd.tmem_size_w, odd.tmem_size),
+        (63, 32, 160, 5120)
+    );
+    assert_eq!((odd.tmem_load_sh, odd.tmem_load_th), (1007, 31));
+
+    let source = bg.to_c("bg", "tex")?;
+    assert!(source.contains("    (u64 *)tex,      /* imagePtr */\n"));
+    assert!(source.contains("    G_BGLT_LOADTILE, /* imageLoad */\n"));
+
+    assert!(matches!(
+        ObjBg::new(ImageType::Ci4, 20, 20, ObjLoad::Block),
+        Err(Error::UnalignedTextureRow { .. })
+    ));
+    Ok(())
+}
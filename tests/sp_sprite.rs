@@ -0,0 +1,84 @@
+use anyhow::Result;
+use pigment64::gbi::split::split_image;
+use pigment64::gbi::sprite::{SpriteAttribute, sp_sprite_source};
+use pigment64::{ImageType, PNGImage};
+
+#[test]
+fn sp_sprite_bitmaps() -> Result<()> {
+    let png = PNGImage::from_rgba8(128, 40, vec![0xFF; 128 * 40 * 4]);
+    let split = split_image(&png, ImageType::Rgba16, Some(64), 0)?;
+    let source = sp_sprite_source("bg", &split, &[])?;
+
+    // Bitmaps are 64 texels wide and as tall as TMEM allows, in rows
+    let bitmaps = [
+        "    { 64, 64, 0, 0, bg_tex_0, 32, 0 },",
+        "    { 64, 64, 0, 0, bg_tex_1, 32, 0 },",
+        "    { 64, 64, 0, 0, bg_tex_2, 8, 0 },",
+        "    { 64, 64, 0, 0, bg_tex_3, 8, 0 },",
+    ];
+    assert!(source.starts_with(&format!(
+        "static Bitmap bg_bitmaps[] = {{\n{}\n}};\n",
+        bitmaps.join("\n")
+    )));
+    assert!(source.contains("#define NUM_BG_BMS (sizeof(bg_bitmaps) / sizeof(Bitmap))\n"));
+    assert!(source.contains("static Gfx bg_dl[NUM_DL(NUM_BG_BMS)];\n"));
+
+    let fields: Vec<&str> = source
+        .lines()
+        .skip_while(|line| !line.starts_with("Sprite bg_sprite"))
+        .skip(1)
+        .map(|line| line.split("/*").next().unwrap_or_default().trim())
+        .collect();
+    assert_eq!(
+        fields,
+        [
+            "0, 0,",
+            "128, 40,",
+            "1.0f, 1.0f,",
+            "0, 0,",
+            "0,",
+            "0,",
+            "255, 255, 255, 255,",
+            "0, 0, NULL,",
+            "0, 1,",
+            "NUM_BG_BMS,",
+            "NUM_DL(NUM_BG_BMS),",
+            "32, 32,",
+            "G_IM_FMT_RGBA,",
+            "G_IM_SIZ_16b,",
+            "bg_bitmaps,",
+            "bg_dl,",
+            "NULL,",
+            "0, 0,",
+            "};",
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn sp_sprite_palette_and_attributes() -> Result<()> {
+    let png = PNGImage::read(include_bytes!("ci4.png").as_slice())?;
+    let split = split_image(&png, ImageType::Ci4, None, 0)?;
+    let attributes = [SpriteAttribute::Transparent, SpriteAttribute::Cutout];
+    let source = sp_sprite_source("icon", &split, &attributes)?;
+
+    assert!(source.contains("    { 4, 4, 0, 0, icon_tex_0, 4, 0 },\n"));
+    assert!(source.contains("    SP_TRANSPARENT | SP_CUTOUT, /* Attributes */\n"));
+    assert!(source.contains("0, 16, (int *)icon_tlut,"));
+    assert!(source.contains("G_IM_FMT_CI,"));
+    assert!(source.contains("G_IM_SIZ_4b,"));
+    Ok(())
+}
+
+#[test]
+fn sp_sprite_odd_4bit_bitmaps() -> Result<()> {
+    let png = PNGImage::from_rgba8(5, 2, vec![0xFF; 5 * 2 * 4]);
+    let split = split_image(&png, ImageType::I4, None, 0)?;
+    let source = sp_sprite_source("odd", &split, &[])?;
+
+    // Rows of 5 texels are stored 6 texels apart
+    assert!(source.contains("    { 5, 6, 0, 0, odd_tex_0, 2, 0 },\n"));
+    assert_eq!(split.tiles[0].data.len(), 3 * 2);
+    Ok(())
+}
@@ -0,0 +1,92 @@
+use anyhow::Result;
+use pigment64::gbi::split::split_image;
+use pigment64::{Error, ImageType, PNGImage};
+
+/// Builds an RGBA8 image whose texels encode their own coordinates.
+fn coordinates(width: u32, height: u32) -> PNGImage {
+    let data = (0..width * height)
+        .flat_map(|i| [(i % width) as u8, (i / width) as u8, 0, 0xFF])
+        .collect();
+    PNGImage::from_rgba8(width, height, data)
+}
+
+#[test]
+fn split_into_strips() -> Result<()> {
+    // A 64 texel wide RGBA16 row takes 128 bytes, so 32 rows fit in TMEM
+    let split = split_image(&coordinates(64, 80), ImageType::Rgba16, None, 0)?;
+
+    assert_eq!((split.columns, split.rows), (1, 3));
+    let layout: Vec<_> = split
+        .tiles
+        .iter()
+        .map(|t| (t.x, t.y, t.width, t.height))
+        .collect();
+    assert_eq!(layout, [(0, 0, 64, 32), (0, 32, 64, 32), (0, 64, 64, 16)]);
+    assert!(split.tiles.iter().all(|t| t.data.len() <= 4096));
+    assert_eq!(split.tlut, None);
+    Ok(())
+}
+
+#[test]
+fn split_with_overlap() -> Result<()> {
+    let image = coordinates(64, 64);
+    let split = split_image(&image, ImageType::Rgba32, Some(32), 1)?;
+
+    // 32 texel wide RGBA32 rows take 128 bytes, so tiles are 32x32 and advance by 31
+    let origins: Vec<_> = split.tiles.iter().map(|t| (t.x, t.y)).collect();
+    assert_eq!(
+        origins,
+        [
+            (0, 0),
+            (31, 0),
+            (62, 0),
+            (0, 31),
+            (31, 31),
+            (62, 31),
+            (0, 62),
+            (31, 62),
+            (62, 62)
+        ]
+    );
+
+    // The first texel of a tile holds its own coordinates
+    let tile = &split.tiles[4];
+    assert_eq!(tile.data[..4], [31, 31, 0, 0xFF]);
+    assert_eq!((split.tiles[8].width, split.tiles[8].height), (2, 2));
+    Ok(())
+}
+
+#[test]
+fn split_color_indexed() -> Result<()> {
+    let split = split_image(&coordinates(64, 4), ImageType::Ci8, Some(32), 0)?;
+
+    assert_eq!((split.columns, split.rows), (2, 1));
+    // Red only takes 8 distinct values at RGBA5551 precision
+    assert_eq!(split.tlut.map(|tlut| tlut.len()), Some(8 * 2));
+    assert_ne!(split.tiles[0].data, split.tiles[1].data);
+    Ok(())
+}
+
+#[test]
+fn split_overlap_too_large() {
+    let result = split_image(&coordinates(2048, 4), ImageType::Rgba32, None, 1);
+    assert!(matches!(result, Err(Error::TmemOverflow { .. })));
+}
+
+#[test]
+fn split_odd_4bit_tiles() -> Result<()> {
+    // The second column is 3 texels wide, so each of its rows gets a padding texel
+    let data = (0..7 * 2)
+        .flat_map(|i| {
+            let intensity = if i % 7 < 4 { 0x00 } else { 0xFF };
+            [intensity, intensity, intensity, 0xFF]
+        })
+        .collect();
+    let split = split_image(&PNGImage::from_rgba8(7, 2, data), ImageType::I4, Some(4), 0)?;
+
+    let tile = &split.tiles[1];
+    assert_eq!((tile.x, tile.width, tile.height), (4, 3, 2));
+    assert_eq!(tile.data, [0xFF, 0xFF, 0xFF, 0xFF]);
+    assert_eq!(split.tiles[0].data, [0x00; 4]);
+    Ok(())
+}
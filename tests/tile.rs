@@ -0,0 +1,35 @@
+use anyhow::Result;
+use pigment64::ImageType;
+use pigment64::gbi::G_TX_RENDERTILE;
+use pigment64::gbi::tile::{TileAxis, TileDescriptor};
+
+#[test]
+fn tile_descriptor_commands() -> Result<()> {
+    let axis = TileAxis {
+        mask: 3,
+        ..Default::default()
+    };
+    let tile = TileDescriptor {
+        tile: G_TX_RENDERTILE,
+        format: ImageType::Rgba16,
+        line: 2,
+        tmem: 0,
+        palette: 0,
+        s: axis,
+        t: axis,
+        uls: 0,
+        ult: 0,
+        lrs: 7,
+        lrt: 7,
+    };
+
+    assert_eq!(tile.set_tile()?, [0xF510_0400, 0x0000_C030]);
+    assert_eq!(tile.set_tile_size(), [0xF200_0000, 0x0001_C01C]);
+    assert_eq!(
+        tile.to_c_macros()?,
+        "gsDPSetTile(G_IM_FMT_RGBA, G_IM_SIZ_16b, 2, 0x000, G_TX_RENDERTILE, 0, \
+         G_TX_NOMIRROR | G_TX_WRAP, 3, 0, G_TX_NOMIRROR | G_TX_WRAP, 3, 0),\n\
+         gsDPSetTileSize(G_TX_RENDERTILE, 0, 0, 7 << G_TEXTURE_IMAGE_FRAC, 7 << G_TEXTURE_IMAGE_FRAC),"
+    );
+    Ok(())
+}
@@ -0,0 +1,129 @@
+use anyhow::Result;
+use pigment64::verify::{
+    Mismatch, Reference, VerifyResult, verify_image, verify_palette, write_highlight_png,
+};
+use pigment64::{ImageType, PNGImage};
+
+#[test]
+fn verify_matching_image() -> Result<()> {
+    let image = PNGImage::read(&include_bytes!("i8.png")[..])?;
+    let reference = include_bytes!("i8.png.bin");
+
+    let report = verify_image(&image, ImageType::I8, false, Reference::Binary(reference))?;
+
+    assert!(report.is_match());
+    assert_eq!(report.encoded, reference);
+    Ok(())
+}
+
+#[test]
+fn verify_reports_first_mismatch() -> Result<()> {
+    let image = PNGImage::read(&include_bytes!("i8.png")[..])?;
+    let mut reference = include_bytes!("i8.png.bin").to_vec();
+
+    // Corrupt the second texel of the third row, and the one after it
+    let index = (image.width() * 2 + 1) as usize;
+    let original = reference[index];
+    reference[index] ^= 0xFF;
+    reference[index + 1] ^= 0xFF;
+
+    let report = verify_image(&image, ImageType::I8, false, Reference::Binary(&reference))?;
+
+    assert_eq!(
+        report.result,
+        VerifyResult::ValueMismatch(Mismatch {
+            index,
+            position: Some((1, 2)),
+            expected: (original ^ 0xFF) as u32,
+            actual: original as u32,
+            count: 2,
+        })
+    );
+
+    let mut diff = Vec::new();
+    write_highlight_png(
+        &mut diff,
+        ImageType::I8,
+        image.width(),
+        image.height(),
+        &reference,
+        &report.encoded,
+    )?;
+
+    let diff = PNGImage::read(diff.as_slice())?.as_rgba8();
+    let pixel = |i: usize| &diff[i * 4..i * 4 + 4];
+    assert_eq!(pixel(index), [0xFF, 0x00, 0xFF, 0xFF]);
+    assert_eq!(pixel(index + 1), [0xFF, 0x00, 0xFF, 0xFF]);
+    assert_ne!(pixel(0), [0xFF, 0x00, 0xFF, 0xFF]);
+    Ok(())
+}
+
+#[test]
+fn verify_size_mismatch() -> Result<()> {
+    let image = PNGImage::read(&include_bytes!("rgba16.png")[..])?;
+    let reference = include_bytes!("rgba16.png.bin");
+
+    let report = verify_image(
+        &image,
+        ImageType::Rgba16,
+        false,
+        Reference::Binary(&reference[..reference.len() - 2]),
+    )?;
+
+    assert_eq!(
+        report.result,
+        VerifyResult::SizeMismatch {
+            expected: reference.len() - 2,
+            actual: reference.len(),
+        }
+    );
+    Ok(())
+}
+
+#[test]
+fn verify_checksum() -> Result<()> {
+    let image = PNGImage::read(&include_bytes!("ia8.png")[..])?;
+
+    let report = verify_image(&image, ImageType::Ia8, false, Reference::Crc32(0))?;
+    let VerifyResult::ChecksumMismatch { expected, actual } = report.result else {
+        panic!("expected a checksum mismatch, got {:?}", report.result);
+    };
+    assert_eq!(expected, 0);
+
+    let report = verify_image(&image, ImageType::Ia8, false, Reference::Crc32(actual))?;
+    assert!(report.is_match());
+    Ok(())
+}
+
+#[test]
+fn verify_palette_entries() -> Result<()> {
+    let png = include_bytes!("ci4.png");
+    let mut reference = include_bytes!("ci4.tlut.bin").to_vec();
+
+    assert!(verify_palette(png, Reference::Binary(&reference))?.is_match());
+
+    reference[6] ^= 0x80;
+    let report = verify_palette(png, Reference::Binary(&reference))?;
+    let VerifyResult::ValueMismatch(mismatch) = report.result else {
+        panic!("expected a value mismatch, got {:?}", report.result);
+    };
+    assert_eq!(mismatch.index, 3);
+    assert_eq!(mismatch.position, None);
+    assert_eq!(mismatch.count, 1);
+    Ok(())
+}
+
+#[test]
+fn indexed_png_keeps_palette() -> Result<()> {
+    let image = PNGImage::read(&include_bytes!("ci4.png")[..])?;
+
+    let mut png = Vec::new();
+    image.as_png(&mut png)?;
+    let reread = PNGImage::read(png.as_slice())?;
+
+    let mut palette = Vec::new();
+    pigment64::create_palette_from_png(png.as_slice(), &mut palette)?;
+    assert_eq!(palette, include_bytes!("ci4.tlut.bin"));
+    assert_eq!(reread.as_rgba8(), image.as_rgba8());
+    Ok(())
+}